@@ -0,0 +1,70 @@
+
+//! Measures the cost of the send/receive hot path: 
+//! heap allocations and time per impulse, over UDP loopback.
+//! `cargo run --release --example hot_path`
+
+use std::alloc::{ GlobalAlloc, Layout, System };
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::time::Instant;
+
+use phantom_limb::{ Sensor, FnMotor };
+
+
+/// Counts every allocation made by the process.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const IMPULSES: usize = 100_000;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), anyhow::Error> {
+
+    let any = "127.0.0.1:0".parse()?;
+    let motor = FnMotor::new("bench", any).await?
+        .with_fiber(1, |_: ()| ());
+
+    let mut sensor: Sensor<u8> = Sensor::new("bench", any).await?;
+    sensor.add_receptor(0, 1);
+    sensor.add_receptor(1, 2);
+    sensor.connect(&motor.address).await?;
+
+    // Sends and receives alternate so the socket buffer never overflows.
+    let report = |label: &str, allocations: usize, started: Instant| {
+        let elapsed = started.elapsed();
+        println!(
+            "{label}: {:.2} allocations and {:?} per impulse", 
+            allocations as f64 / IMPULSES as f64,
+            elapsed / IMPULSES as u32
+        );
+    };
+
+    let (before, started) = (ALLOCATIONS.load(Ordering::Relaxed), Instant::now());
+    for _ in 0..IMPULSES {
+        sensor.send_impulse(&0).await?;
+        motor.recv_impulse(()).await?;
+    }
+    report("recognized", ALLOCATIONS.load(Ordering::Relaxed) - before, started);
+
+    let (before, started) = (ALLOCATIONS.load(Ordering::Relaxed), Instant::now());
+    for _ in 0..IMPULSES {
+        let _ = sensor.send_impulse(&9).await;
+    }
+    report("unrecognized", ALLOCATIONS.load(Ordering::Relaxed) - before, started);
+
+    Ok(())
+}
+
@@ -0,0 +1,137 @@
+
+//! Derive macros re-exported by `phantom_limb` under the `derive` feature.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{ parse_macro_input, Data, DeriveInput, Error, Fields, Path, Type };
+
+
+/// Implements `phantom_limb::spectrum::Spectrum` for a fieldless enum,
+/// assigning fiber IDs 0, 1, 2... to its variants in declaration order.
+#[proc_macro_derive(Spectrum)]
+pub fn derive_spectrum(input: TokenStream) -> TokenStream {
+
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let Data::Enum(data) = &input.data else {
+        return Error::new_spanned(name, "Spectrum can only be derived for enums")
+            .to_compile_error().into()
+    };
+
+    if let Some(variant) = data.variants.iter().find(|v| !matches!(v.fields, Fields::Unit)) {
+        return Error::new_spanned(variant, "Spectrum variants cannot carry data")
+            .to_compile_error().into()
+    }
+
+    if data.variants.len() > u16::MAX as usize {
+        return Error::new_spanned(name, "Spectrum enums are limited to u16::MAX variants")
+            .to_compile_error().into()
+    }
+
+    let variants: Vec<_> = data.variants.iter().map(|v| &v.ident).collect();
+    let ids = 0..variants.len() as u16;
+    let count = variants.len() as u16;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::phantom_limb::spectrum::Spectrum for #name #ty_generics #where_clause {
+            const FIBER_COUNT: u16 = #count;
+
+            fn fiber_id(&self) -> u16 {
+                match self { #( #name::#variants => #ids, )* }
+            }
+
+            fn variants() -> ::std::vec::Vec<Self> {
+                ::std::vec![ #( #name::#variants ),* ]
+            }
+        }
+    }.into()
+}
+
+
+/// Implements `phantom_limb::registry::MotorFibers` for a fieldless enum,
+/// assigning fiber IDs 0, 1, 2... to its variants in declaration order.
+/// The enum needs `#[motor(arg = A)]` (and optionally `ret = R`, 
+/// default `()`), and every variant needs `#[behavior(handler)]`
+/// naming a `fn(A) -> R`; a variant without one is a compile error.
+#[proc_macro_derive(MotorFibers, attributes(motor, behavior))]
+pub fn derive_motor_fibers(input: TokenStream) -> TokenStream {
+
+    let input = parse_macro_input!(input as DeriveInput);
+    match motor_fibers(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into()
+    }
+}
+
+fn motor_fibers(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+
+    let name = &input.ident;
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(name, "MotorFibers can only be derived for enums"))
+    };
+
+    let mut arg: Option<Type> = None;
+    let mut ret: Option<Type> = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("motor")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("arg") { arg = Some(meta.value()?.parse()?) }
+            else if meta.path.is_ident("ret") { ret = Some(meta.value()?.parse()?) }
+            else { return Err(meta.error("expected `arg` or `ret`")) }
+            Ok(())
+        })?;
+    }
+    let arg = arg.ok_or_else(|| Error::new_spanned(name, "missing #[motor(arg = ...)]"))?;
+    let ret = ret.unwrap_or_else(|| syn::parse_quote!(()));
+
+    if data.variants.len() > u16::MAX as usize {
+        return Err(Error::new_spanned(name, "MotorFibers enums are limited to u16::MAX variants"))
+    }
+
+    let mut variants = Vec::new();
+    let mut handlers = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(variant, "MotorFibers variants cannot carry data"))
+        }
+        let handler: Path = variant.attrs.iter()
+            .find(|a| a.path().is_ident("behavior"))
+            .ok_or_else(|| Error::new_spanned(variant, "missing #[behavior(handler)]"))?
+            .parse_args()?;
+        variants.push(&variant.ident);
+        handlers.push(handler);
+    }
+
+    let ids: Vec<u16> = (0..variants.len() as u16).collect();
+    let count = variants.len() as u16;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::phantom_limb::registry::MotorFibers for #name #ty_generics #where_clause {
+            type Arg = #arg;
+            type Ret = #ret;
+            const FIBER_COUNT: u16 = #count;
+
+            fn fiber_id(&self) -> u16 {
+                match self { #( #name::#variants => #ids, )* }
+            }
+
+            fn from_fiber(fiber: u16) -> ::std::option::Option<Self> {
+                match fiber {
+                    #( #ids => ::std::option::Option::Some(#name::#variants), )*
+                    _ => ::std::option::Option::None
+                }
+            }
+
+            fn behavior(&self) -> fn(#arg) -> #ret {
+                match self { #( #name::#variants => #handlers, )* }
+            }
+
+            fn variants() -> ::std::vec::Vec<Self> {
+                ::std::vec![ #( #name::#variants ),* ]
+            }
+        }
+    })
+}
+
@@ -0,0 +1,121 @@
+
+use std::sync::Mutex;
+
+use crate::transport::Transport;
+use crate::types::Motor;
+
+
+/// A motor behavior implemented on a type, for behaviors that carry 
+/// configuration or state, or that need to be mocked in tests.
+/// Object safe, so different implementations can share one motor
+/// (see `BoxMotor::add_behavior`).
+pub trait Behavior {
+
+    /// Argument the behavior is fired with.
+    type Args;
+
+    /// Value returned by the behavior.
+    type Out;
+
+    /// Run the behavior for one impulse.
+    fn fire(&mut self, args: Self::Args) -> Self::Out;
+
+    /// Name used to list and inspect the behavior; the type name by default.
+    fn name(&self) -> &str { std::any::type_name::<Self>() }
+}
+
+impl<A, R> Behavior for Box<dyn Behavior<Args = A, Out = R> + Send> {
+    type Args = A;
+    type Out = R;
+    fn fire(&mut self, args: A) -> R { (**self).fire(args) }
+    fn name(&self) -> &str { (**self).name() }
+}
+
+impl<A, R, T: Transport> Motor<Box<dyn Fn(A) -> R + Send + Sync>, A, R, T> {
+
+    /// Maps a fiber ID to a `Behavior`, recording its name.
+    /// NOTE: Calls are serialized by a lock (see `types::stateful`).
+    /// NOTE: Overwrites existing fiber ID keys without checking.
+    pub fn add_behavior(
+        &mut self,
+        impulse: u16,
+        behavior: impl Behavior<Args = A, Out = R> + Send + 'static
+    ) 
+    where
+        A: 'static,
+        R: 'static
+    {
+
+        let name = behavior.name().to_owned();
+        let behavior = Mutex::new(behavior);
+        self.add_fiber(impulse, Box::new(move |args| {
+            behavior.lock().unwrap_or_else(|e| e.into_inner()).fire(args)
+        }));
+        self.behavior_names.insert(impulse, name);
+    }
+}
+
+impl<B: Fn(A) -> R, A, R, T: Transport> Motor<B, A, R, T> {
+
+    /// Name of the `Behavior` mapped to a fiber ID, if it was added with `add_behavior`.
+    pub fn behavior_name(&self, impulse: u16) -> Option<&str> {
+
+        self.behavior_names.get(&impulse).map(String::as_str)
+    }
+
+    /// Fiber IDs and names of every `Behavior` added with `add_behavior`,
+    /// in fiber ID order.
+    pub fn behaviors(&self) -> Vec<(u16, &str)> {
+
+        let mut behaviors: Vec<(u16, &str)> = self.behavior_names.iter()
+            .map(|(fid, name)| (*fid, name.as_str()))
+            .collect();
+        behaviors.sort_by_key(|(fid, _)| *fid);
+        behaviors
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::transport::ChannelTransport;
+    use crate::types::Motor;
+    use super::Behavior;
+
+    struct Odometer { distance: u32 }
+
+    impl Behavior for Odometer {
+        type Args = u32;
+        type Out = u32;
+        fn fire(&mut self, step: u32) -> u32 { self.distance += step; self.distance }
+        fn name(&self) -> &str { "odometer" }
+    }
+
+    struct Echo;
+
+    impl Behavior for Echo {
+        type Args = u32;
+        type Out = u32;
+        fn fire(&mut self, step: u32) -> u32 { step }
+    }
+
+    #[tokio::test]
+    async fn behaviors_keep_state_and_names() {
+
+        let (_, b) = ChannelTransport::pair(1);
+        let mut motor: Motor<Box<dyn Fn(u32) -> u32 + Send + Sync>, u32, u32, _> = 
+            Motor::in_process("behavior", b);
+        motor.add_behavior(1, Odometer { distance: 0 });
+        motor.add_behavior(2, Echo);
+
+        motor.dispatch(1, 3).unwrap();
+        assert_eq!(motor.dispatch(1, 4).unwrap(), 7);
+        assert_eq!(motor.dispatch(2, 4).unwrap(), 4);
+        assert_eq!(motor.behavior_name(1), Some("odometer"));
+        assert!(motor.behaviors()[1].1.ends_with("Echo"));
+
+        motor.remove_fiber(1);
+        assert_eq!(motor.behavior_name(1), None);
+    }
+}
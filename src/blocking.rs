@@ -0,0 +1,122 @@
+
+//! Synchronous facade over `Sensor` and `Motor` for non-async codebases.
+//! Each blocking tract owns a current-thread tokio runtime
+//! that is only driven while one of its methods is running.
+//! These methods block the calling thread and will panic 
+//! if called from within an async context (use the async types there).
+//! The types are `Send` when their async counterparts are,
+//! so they can be moved to a dedicated control thread.
+
+use std::net::SocketAddr;
+use std::hash::Hash;
+use tokio::runtime::{ Builder, Runtime };
+
+use crate::error::{ BuildError, CommunicationError };
+use crate::types::{ Sensor, Motor, FiberId };
+
+
+fn runtime() -> Result<Runtime, BuildError> {
+
+    Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(BuildError::RuntimeFailed)
+}
+
+
+/// A `Sensor` that can be used without `async`.
+pub struct BlockingSensor<Q: Hash + Eq> {
+    pub sensor: Sensor<Q>,
+    runtime: Runtime
+}
+
+impl<Q: Hash + Eq> BlockingSensor<Q> {
+
+    /// Create a sensor socket on its own runtime. See `Sensor::new`.
+    pub fn new(
+        tract_name: &str, 
+        address: SocketAddr
+    ) -> Result<Self, BuildError> {
+
+        let runtime = runtime()?;
+        let sensor = runtime.block_on(Sensor::new(tract_name, address))?;
+        Ok(BlockingSensor { sensor, runtime })
+    }
+
+    /// See `Sensor::add_receptor`.
+    pub fn add_receptor(&mut self, quantum: Q, fid: impl Into<FiberId>) {
+
+        self.sensor.add_receptor(quantum, fid)
+    }
+
+    /// See `Sensor::connect`.
+    pub fn connect(
+        &mut self, 
+        remote: &SocketAddr
+    ) -> Result<Option<SocketAddr>, BuildError> {
+
+        self.runtime.block_on(self.sensor.connect(remote))
+    }
+
+    /// Block until the impulse for `quantum` has been sent.
+    pub fn send_impulse(&self, quantum: &Q) -> Result<(), CommunicationError> {
+
+        self.runtime.block_on(self.sensor.send_impulse(quantum))
+    }
+}
+
+
+/// A `Motor` that can be used without `async`.
+pub struct BlockingMotor<B: Fn(A) -> R, A, R> {
+    pub motor: Motor<B, A, R>,
+    runtime: Runtime
+}
+
+impl<B: Fn(A) -> R, A, R> BlockingMotor<B, A, R> {
+
+    /// Create a motor socket on its own runtime. See `Motor::new`.
+    pub fn new(
+        tract_name: &str, 
+        address: SocketAddr
+    ) -> Result<Self, BuildError> {
+
+        let runtime = runtime()?;
+        let motor = runtime.block_on(Motor::new(tract_name, address))?;
+        Ok(BlockingMotor { motor, runtime })
+    }
+
+    /// See `Motor::add_fiber`.
+    pub fn add_fiber(&mut self, impulse: impl Into<FiberId>, behavior: B) {
+
+        self.motor.add_fiber(impulse, behavior)
+    }
+
+    /// Block until an impulse is received and its behavior executed.
+    pub fn recv_impulse(&self, args: A) -> Result<R, CommunicationError> {
+
+        self.runtime.block_on(self.motor.recv_impulse(args))
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ BlockingSensor, BlockingMotor };
+
+    #[test]
+    fn round_trips_without_async() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let mut motor: BlockingMotor<fn(u8) -> u8, u8, u8> = BlockingMotor::new("sync", any).unwrap();
+        motor.add_fiber(7, |x| x * 2);
+
+        let mut sensor = BlockingSensor::new("sync", any).unwrap();
+        sensor.add_receptor('a', 7);
+        sensor.connect(&motor.motor.address).unwrap();
+
+        sensor.send_impulse(&'a').unwrap();
+        assert_eq!(motor.recv_impulse(21).unwrap(), 42);
+    }
+}
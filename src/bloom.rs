@@ -0,0 +1,87 @@
+
+//! Minimal bloom filter used to pre-screen fiber IDs.
+
+/// Bits allocated per expected item (~1% false positives with 7 hashes).
+const BITS_PER_ITEM: usize = 10;
+const NUM_HASHES: u64 = 7;
+
+
+/// Set membership test with no false negatives.
+/// A `false` from `may_contain` is definitive; `true` may be wrong.
+#[derive(Debug, Clone)]
+pub(crate) struct BloomFilter {
+    bits: Vec<u64>
+}
+
+impl BloomFilter {
+
+    /// Build a filter sized for the given keys.
+    pub(crate) fn from_keys(keys: impl ExactSizeIterator<Item = u64>) -> Self {
+
+        let words = (keys.len() * BITS_PER_ITEM).div_ceil(64).max(1);
+        let mut filter = BloomFilter { bits: vec![0; words] };
+        keys.for_each(|key| filter.insert(key));
+        filter
+    }
+
+    fn num_bits(&self) -> u64 { self.bits.len() as u64 * 64 }
+
+    /// Double hashing: position i is h1 + i * h2.
+    fn positions(&self, key: u64) -> impl Iterator<Item = usize> + '_ {
+
+        let h1 = mix(key);
+        let h2 = mix(h1) | 1;
+        (0..NUM_HASHES).map(move |i| {
+            (h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits()) as usize
+        })
+    }
+
+    pub(crate) fn insert(&mut self, key: u64) {
+
+        let positions: Vec<usize> = self.positions(key).collect();
+        for bit in positions {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    pub(crate) fn may_contain(&self, key: u64) -> bool {
+
+        self.positions(key).all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+}
+
+/// SplitMix64 finalizer.
+fn mix(mut x: u64) -> u64 {
+
+    x = x.wrapping_add(0x9E3779B97F4A7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+    x ^ (x >> 31)
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::BloomFilter;
+
+    #[test]
+    fn has_no_false_negatives_and_few_false_positives() {
+
+        let filter = BloomFilter::from_keys((0..1000u64).map(|key| key * 3));
+        assert!((0..1000u64).all(|key| filter.may_contain(key * 3)));
+
+        let false_positives = (0..1000u64)
+            .filter(|key| filter.may_contain(key * 3 + 1))
+            .count();
+        assert!(false_positives < 50, "{false_positives} false positives");
+    }
+
+    #[test]
+    fn empty_filter_contains_nothing() {
+
+        let filter = BloomFilter::from_keys(std::iter::empty());
+        assert!(!(0..100u64).any(|key| filter.may_contain(key)));
+    }
+}
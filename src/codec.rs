@@ -0,0 +1,424 @@
+
+//! Interchangeable serializations of `ImpulseFrame`, chosen per tract 
+//! with `Sensor::set_codec` and `Motor::set_codec`.
+//! Both ends of a tract MUST use the same codec.
+//! Only `BincodeCodec` (the default), `RawCodec` and `EnvelopeCodec` encode 
+//! plain impulses the way cajal expects; the others are for tracts between 
+//! phantom limbs or for interop with other tooling.
+
+use std::sync::Arc;
+use serde::{ Serialize, Deserialize };
+
+use crate::error::CommunicationError;
+use crate::wire::*;
+
+
+/// Converts frames to and from datagrams.
+pub trait ImpulseCodec: Send + Sync {
+
+    /// Serialize a frame into a datagram.
+    fn encode(&self, frame: &ImpulseFrame) -> Result<Vec<u8>, CommunicationError>;
+
+    /// Deserialize a datagram into a frame.
+    fn decode(&self, datagram: &[u8]) -> Result<ImpulseFrame, CommunicationError>;
+
+    /// Serialize a frame so that it cannot be mistaken for stray traffic
+    /// (see `Sensor::set_tagged`). Codecs without an untagged form
+    /// encode as usual.
+    fn encode_tagged(&self, frame: &ImpulseFrame) -> Result<Vec<u8>, CommunicationError> {
+        self.encode(frame)
+    }
+
+    /// The plain encoding of a lone fiber ID, without allocating,
+    /// if this codec has one. Used by sensors on the send hot path.
+    fn encode_plain(&self, _fiber: u16) -> Option<[u8; IMPULSE_LEN]> { None }
+
+    /// Which codec this is, so it can be recorded in a `SensorSnapshot`.
+    fn kind(&self) -> CodecKind { CodecKind::Custom }
+
+    /// Whether `datagram` is this codec's plain (headerless) form of an impulse,
+    /// for motors that reject plain impulses (`Motor::set_reject_plain`).
+    /// Codecs with no plain form have nothing to reject.
+    fn is_plain(&self, _datagram: &[u8]) -> bool { false }
+}
+
+
+/// The built-in codecs, by name, for recording a tract's codec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CodecKind {
+    Bincode,
+    Raw,
+    Envelope { version: u8, checksum: bool },
+    #[cfg(feature = "postcard")]
+    Postcard,
+    #[cfg(feature = "json")]
+    Json,
+    /// A codec defined outside this crate, which cannot be rebuilt from its kind.
+    Custom
+}
+
+impl CodecKind {
+
+    /// A new instance of the codec, unless it is `Custom`.
+    pub fn codec(&self) -> Option<Arc<dyn ImpulseCodec>> {
+
+        match *self {
+            CodecKind::Bincode => Some(Arc::new(BincodeCodec)),
+            CodecKind::Raw => Some(Arc::new(RawCodec)),
+            CodecKind::Envelope { version, checksum } => Some(Arc::new(EnvelopeCodec { version, checksum })),
+            #[cfg(feature = "postcard")]
+            CodecKind::Postcard => Some(Arc::new(PostcardCodec)),
+            #[cfg(feature = "json")]
+            CodecKind::Json => Some(Arc::new(JsonCodec)),
+            CodecKind::Custom => None
+        }
+    }
+}
+
+
+/// The format described in `wire`: plain impulses as a bincode `u16`,
+/// everything else as a tagged frame with a bincode body.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BincodeCodec;
+
+impl ImpulseCodec for BincodeCodec {
+    fn encode(&self, frame: &ImpulseFrame) -> Result<Vec<u8>, CommunicationError> { frame.encode() }
+    fn decode(&self, datagram: &[u8]) -> Result<ImpulseFrame, CommunicationError> { ImpulseFrame::decode(datagram) }
+    fn encode_tagged(&self, frame: &ImpulseFrame) -> Result<Vec<u8>, CommunicationError> { frame.encode_tagged() }
+    fn encode_plain(&self, fiber: u16) -> Option<[u8; IMPULSE_LEN]> { Some(plain_impulse(fiber)) }
+    fn kind(&self) -> CodecKind { CodecKind::Bincode }
+    fn is_plain(&self, datagram: &[u8]) -> bool { crate::wire::is_plain(datagram) }
+}
+
+
+/// Fixed-width little-endian fields behind the same header as `BincodeCodec`,
+/// without bincode's length prefixes, so tools in any language can parse it:
+/// - plain impulse: `fiber: u16` (identical to bincode);
+/// - data: `fiber: u16`, `flags: u8` (bit 0 seq, bit 1 payload), 
+///   then `seq: u64` and `payload: f32` if flagged;
+/// - ack and report: `u64`; unrecognized: `u16`;
+/// - batch and handshake: `u16` fiber IDs until the end of the datagram;
+/// - silence and heartbeat: no body.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RawCodec;
+
+impl RawCodec {
+
+    fn header(tag: u8) -> Vec<u8> {
+
+        let mut datagram = Vec::with_capacity(HEADER_LEN + 8);
+        datagram.extend_from_slice(&WIRE_MAGIC);
+        datagram.extend_from_slice(&[WIRE_VERSION, tag]);
+        datagram
+    }
+}
+
+fn truncated() -> CommunicationError { CommunicationError::CodecFailed("truncated frame".into()) }
+
+fn take<const N: usize>(body: &mut &[u8]) -> Result<[u8; N], CommunicationError> {
+
+    if body.len() < N { return Err(truncated()) }
+    let (head, rest) = body.split_at(N);
+    *body = rest;
+    Ok(head.try_into().expect("Split at the array length"))
+}
+
+fn fiber_list(body: &[u8]) -> Result<Vec<u16>, CommunicationError> {
+
+    if body.len() % 2 != 0 { return Err(truncated()) }
+    Ok(body.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect())
+}
+
+impl ImpulseCodec for RawCodec {
+
+    fn encode(&self, frame: &ImpulseFrame) -> Result<Vec<u8>, CommunicationError> {
+
+        match frame {
+            ImpulseFrame::Data { seq: None, fiber, payload: None } => Ok(fiber.to_le_bytes().to_vec()),
+            _ => self.encode_tagged(frame)
+        }
+    }
+
+    fn encode_plain(&self, fiber: u16) -> Option<[u8; IMPULSE_LEN]> { Some(fiber.to_le_bytes()) }
+
+    fn kind(&self) -> CodecKind { CodecKind::Raw }
+
+    fn is_plain(&self, datagram: &[u8]) -> bool { crate::wire::is_plain(datagram) }
+
+    fn encode_tagged(&self, frame: &ImpulseFrame) -> Result<Vec<u8>, CommunicationError> {
+
+        use ImpulseFrame::*;
+        let datagram = match frame {
+            Data { seq, fiber, payload } => {
+                let mut datagram = Self::header(DATA_TAG);
+                datagram.extend_from_slice(&fiber.to_le_bytes());
+                datagram.push(u8::from(seq.is_some()) | u8::from(payload.is_some()) << 1);
+                if let Some(seq) = seq { datagram.extend_from_slice(&seq.to_le_bytes()) }
+                if let Some(payload) = payload { datagram.extend_from_slice(&payload.to_le_bytes()) }
+                datagram
+            },
+            Silence => Self::header(SILENCE_TAG),
+            Heartbeat => Self::header(HEARTBEAT_TAG),
+            Closing => Self::header(CLOSING_TAG),
+            Ack(seq) => [Self::header(ACK_TAG), seq.to_le_bytes().to_vec()].concat(),
+            Report { sent } => [Self::header(REPORT_TAG), sent.to_le_bytes().to_vec()].concat(),
+            Unrecognized(fiber) => [Self::header(UNRECOGNIZED_TAG), fiber.to_le_bytes().to_vec()].concat(),
+            Batch(fibers) | Handshake(fibers) => {
+                let tag = if matches!(frame, Batch(_)) { BATCH_TAG } else { HANDSHAKE_TAG };
+                let mut datagram = Self::header(tag);
+                fibers.iter().for_each(|fiber| datagram.extend_from_slice(&fiber.to_le_bytes()));
+                datagram
+            }
+        };
+        Ok(datagram)
+    }
+
+    fn decode(&self, datagram: &[u8]) -> Result<ImpulseFrame, CommunicationError> {
+
+        use ImpulseFrame::*;
+        if datagram.len() == IMPULSE_LEN {
+            let fiber = u16::from_le_bytes([datagram[0], datagram[1]]);
+            return Ok(Data { seq: None, fiber, payload: None })
+        }
+
+        let (version, tag) = peek_header(datagram).ok_or(CommunicationError::ProtocolMismatch)?;
+        if version != WIRE_VERSION {
+            return Err(CommunicationError::UnsupportedVersion(version))
+        }
+
+        let mut body = &datagram[HEADER_LEN..];
+        let frame = match tag {
+            DATA_TAG => {
+                let fiber = u16::from_le_bytes(take(&mut body)?);
+                let [flags] = take::<1>(&mut body)?;
+                let seq = if flags & 1 != 0 { Some(u64::from_le_bytes(take(&mut body)?)) } else { None };
+                let payload = if flags & 2 != 0 { Some(f32::from_le_bytes(take(&mut body)?)) } else { None };
+                Data { seq, fiber, payload }
+            },
+            SILENCE_TAG => Silence,
+            HEARTBEAT_TAG => Heartbeat,
+            CLOSING_TAG => Closing,
+            ACK_TAG => Ack(u64::from_le_bytes(take(&mut body)?)),
+            REPORT_TAG => Report { sent: u64::from_le_bytes(take(&mut body)?) },
+            UNRECOGNIZED_TAG => Unrecognized(u16::from_le_bytes(take(&mut body)?)),
+            BATCH_TAG => Batch(fiber_list(body)?),
+            HANDSHAKE_TAG => Handshake(fiber_list(body)?),
+            unknown => return Err(CommunicationError::UnknownFrame(unknown))
+        };
+        Ok(frame)
+    }
+}
+
+
+/// Plain impulses wrapped in an envelope, for cajal releases that frame 
+/// a `NeuronId` with a version tag and checksum rather than as a bare `u16`:
+/// - plain impulse: `version: u8`, `fiber: u16` (bincode), then, if enabled,
+///   `checksum: u16` (little-endian Fletcher-16 of the preceding bytes);
+/// - every other frame: tagged, exactly as with `BincodeCodec`.
+/// Bare two-byte impulses are rejected, so a peer still sending them
+/// fails loudly with `ProtocolMismatch` instead of being misparsed.
+/// NOTE: `cajal_cx` is not vendored with this crate, so the envelope is
+/// configurable rather than fixed: set `version` and `checksum` to match 
+/// the `Input`/`Output` framing of the cajal release in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvelopeCodec {
+    pub version: u8,
+    pub checksum: bool
+}
+
+impl Default for EnvelopeCodec {
+    fn default() -> Self { EnvelopeCodec { version: 1, checksum: true } }
+}
+
+impl EnvelopeCodec {
+
+    /// Length of an enveloped plain impulse.
+    pub fn envelope_len(&self) -> usize { 1 + IMPULSE_LEN + if self.checksum { 2 } else { 0 } }
+}
+
+/// Fletcher-16, as carried in the envelope's checksum field.
+fn fletcher16(bytes: &[u8]) -> u16 {
+
+    let (mut low, mut high) = (0u16, 0u16);
+    for byte in bytes {
+        low = (low + *byte as u16) % 255;
+        high = (high + low) % 255;
+    }
+    high << 8 | low
+}
+
+impl ImpulseCodec for EnvelopeCodec {
+
+    fn encode(&self, frame: &ImpulseFrame) -> Result<Vec<u8>, CommunicationError> {
+
+        let ImpulseFrame::Data { seq: None, fiber, payload: None } = frame else {
+            return frame.encode_tagged()
+        };
+
+        let mut datagram = Vec::with_capacity(self.envelope_len());
+        datagram.push(self.version);
+        datagram.extend_from_slice(&encode_impulse(*fiber)?);
+        if self.checksum {
+            let checksum = fletcher16(&datagram);
+            datagram.extend_from_slice(&checksum.to_le_bytes());
+        }
+        Ok(datagram)
+    }
+
+    fn encode_tagged(&self, frame: &ImpulseFrame) -> Result<Vec<u8>, CommunicationError> { frame.encode_tagged() }
+
+    fn kind(&self) -> CodecKind { CodecKind::Envelope { version: self.version, checksum: self.checksum } }
+
+    fn is_plain(&self, datagram: &[u8]) -> bool { datagram.len() == self.envelope_len() }
+
+    fn decode(&self, datagram: &[u8]) -> Result<ImpulseFrame, CommunicationError> {
+
+        if datagram.len() == IMPULSE_LEN { return Err(CommunicationError::ProtocolMismatch) }
+        if datagram.len() != self.envelope_len() { return ImpulseFrame::decode(datagram) }
+
+        let (body, checksum) = datagram.split_at(1 + IMPULSE_LEN);
+        if body[0] != self.version {
+            return Err(CommunicationError::UnsupportedVersion(body[0]))
+        }
+        if self.checksum && fletcher16(body).to_le_bytes() != checksum {
+            return Err(CommunicationError::CodecFailed("envelope checksum mismatch".into()))
+        }
+        Ok(ImpulseFrame::Data { seq: None, fiber: decode_impulse(&body[1..])?, payload: None })
+    }
+}
+
+
+/// Whole frames serialized with postcard: compact varint encoding,
+/// with no header and no plain form.
+#[cfg(feature = "postcard")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PostcardCodec;
+
+#[cfg(feature = "postcard")]
+impl ImpulseCodec for PostcardCodec {
+
+    fn encode(&self, frame: &ImpulseFrame) -> Result<Vec<u8>, CommunicationError> {
+        postcard::to_allocvec(frame).map_err(|e| CommunicationError::CodecFailed(e.to_string()))
+    }
+
+    fn decode(&self, datagram: &[u8]) -> Result<ImpulseFrame, CommunicationError> {
+        postcard::from_bytes(datagram).map_err(|e| CommunicationError::CodecFailed(e.to_string()))
+    }
+
+    fn kind(&self) -> CodecKind { CodecKind::Postcard }
+}
+
+
+/// Whole frames as JSON text, e.g. `{"Data":{"seq":null,"fiber":3,"payload":null}}`,
+/// readable in a packet capture. Slow and large; meant for debugging.
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+#[cfg(feature = "json")]
+impl ImpulseCodec for JsonCodec {
+
+    fn encode(&self, frame: &ImpulseFrame) -> Result<Vec<u8>, CommunicationError> {
+        serde_json::to_vec(frame).map_err(|e| CommunicationError::CodecFailed(e.to_string()))
+    }
+
+    fn decode(&self, datagram: &[u8]) -> Result<ImpulseFrame, CommunicationError> {
+        serde_json::from_slice(datagram).map_err(|e| CommunicationError::CodecFailed(e.to_string()))
+    }
+
+    fn kind(&self) -> CodecKind { CodecKind::Json }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn frames() -> Vec<ImpulseFrame> {
+
+        vec![
+            ImpulseFrame::Data { seq: None, fiber: 3, payload: None },
+            ImpulseFrame::Data { seq: Some(9), fiber: 3, payload: Some(0.5) },
+            ImpulseFrame::Silence,
+            ImpulseFrame::Heartbeat,
+            ImpulseFrame::Ack(7),
+            ImpulseFrame::Batch(vec![1, 2, 3]),
+            ImpulseFrame::Unrecognized(4),
+            ImpulseFrame::Report { sent: 11 },
+            ImpulseFrame::Handshake(vec![5, 6]),
+            ImpulseFrame::Closing
+        ]
+    }
+
+    #[test]
+    fn every_codec_round_trips_every_frame() {
+
+        let codecs: Vec<Box<dyn ImpulseCodec>> = vec![
+            Box::new(BincodeCodec),
+            Box::new(RawCodec),
+            Box::new(EnvelopeCodec::default()),
+            Box::new(EnvelopeCodec { version: 3, checksum: false }),
+            #[cfg(feature = "postcard")]
+            Box::new(PostcardCodec),
+            #[cfg(feature = "json")]
+            Box::new(JsonCodec)
+        ];
+
+        for codec in &codecs {
+            for frame in frames() {
+                let datagram = codec.encode(&frame).unwrap();
+                assert_eq!(codec.decode(&datagram).unwrap(), frame);
+                let tagged = codec.encode_tagged(&frame).unwrap();
+                assert_eq!(codec.decode(&tagged).unwrap(), frame);
+            }
+        }
+    }
+
+    #[test]
+    fn raw_frames_are_fixed_width() {
+
+        assert_eq!(RawCodec.encode_plain(0x0102), Some([0x02, 0x01]));
+        let ack = RawCodec.encode(&ImpulseFrame::Ack(1)).unwrap();
+        assert_eq!(ack.len(), HEADER_LEN + 8);
+        assert!(RawCodec.decode(&ack[..HEADER_LEN + 3]).is_err());
+    }
+
+    #[test]
+    fn enveloped_impulses_decode_to_their_fiber() {
+
+        let codec = EnvelopeCodec::default();
+        let plain = ImpulseFrame::Data { seq: None, fiber: 0x0102, payload: None };
+        let datagram = codec.encode(&plain).unwrap();
+        assert_eq!(datagram[..3], [1, 0x02, 0x01]);
+        assert_eq!(datagram.len(), codec.envelope_len());
+        assert_eq!(codec.decode(&datagram).unwrap().fiber(), Some(0x0102));
+
+        let mut corrupted = datagram.clone();
+        corrupted[1] ^= 0xFF;
+        assert!(matches!(codec.decode(&corrupted), Err(CommunicationError::CodecFailed(_))));
+        let newer = EnvelopeCodec { version: 2, ..codec };
+        assert!(matches!(newer.decode(&datagram), Err(CommunicationError::UnsupportedVersion(1))));
+        assert!(matches!(codec.decode(&plain_impulse(0x0102)), Err(CommunicationError::ProtocolMismatch)));
+    }
+
+    #[test]
+    fn plain_forms_depend_on_the_codec() {
+
+        let plain = ImpulseFrame::Data { seq: None, fiber: 3, payload: None };
+        for codec in [&BincodeCodec as &dyn ImpulseCodec, &RawCodec, &EnvelopeCodec::default()] {
+            assert!(codec.is_plain(&codec.encode(&plain).unwrap()));
+            assert!(!codec.is_plain(&codec.encode_tagged(&plain).unwrap()));
+        }
+        assert!(!EnvelopeCodec::default().is_plain(&plain_impulse(3)));
+
+        // Two bytes long, but not a plain impulse.
+        #[cfg(feature = "postcard")]
+        {
+            let ack = PostcardCodec.encode(&ImpulseFrame::Ack(1)).unwrap();
+            assert_eq!(ack.len(), IMPULSE_LEN);
+            assert!(!PostcardCodec.is_plain(&ack));
+        }
+    }
+}
@@ -0,0 +1,438 @@
+
+//! Encoders that turn continuous values into impulse patterns.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::error::{ CommunicationError, ConfigError };
+use crate::transport::Transport;
+use crate::types::{ Sensor, Motor };
+
+
+/// Rate coding: fires one fiber at a frequency proportional to a value.
+/// A value of 0.0 is silent and 1.0 fires at `max_rate` Hz;
+/// values outside that range are clamped. The firing task runs until 
+/// the coder is dropped. A new value applies to the interval in progress,
+/// so the next impulse comes one new interval after the last one.
+pub struct RateCoder {
+    value: watch::Sender<f64>,
+    task: JoinHandle<()>
+}
+
+impl RateCoder {
+
+    /// Start firing `fiber` through `sensor`, initially silent.
+    pub fn spawn<Q, T>(sensor: Arc<Sensor<Q, T>>, fiber: u16, max_rate: f64) -> Self
+    where
+        Q: Hash + Eq + Send + Sync + 'static,
+        T: Transport + Send + Sync + 'static
+    {
+
+        let (value, mut updates) = watch::channel(0.0);
+        let task = tokio::spawn(async move {
+            // Each impulse is due one interval (at the current rate) after the last,
+            // so a new value reschedules the pending impulse rather than restarting it.
+            let mut last_fired = Instant::now();
+            loop {
+                let rate = *updates.borrow_and_update() * max_rate;
+                if !(rate > 0.0 && rate.is_finite()) {
+                    tokio::select! {
+                        _ = sensor.cancel.cancelled() => return,
+                        changed = updates.changed() => if changed.is_err() { return }
+                    }
+                    last_fired = Instant::now();
+                    continue
+                }
+
+                let deadline = last_fired + Duration::from_secs_f64(1.0 / rate);
+                tokio::select! {
+                    _ = sensor.cancel.cancelled() => return,
+                    changed = updates.changed() => if changed.is_err() { return },
+                    _ = tokio::time::sleep_until(deadline) => {
+                        last_fired = deadline;
+                        let _ = sensor.transmit(fiber).await;
+                    }
+                }
+            }
+        });
+
+        RateCoder { value, task }
+    }
+
+    /// Change the encoded value, from 0.0 (silent) to 1.0 (`max_rate`).
+    pub fn set_value(&self, value: f64) {
+
+        let value = if value.is_nan() { 0.0 } else { value.clamp(0.0, 1.0) };
+        self.value.send_replace(value);
+    }
+
+    /// The value currently being encoded.
+    pub fn value(&self) -> f64 { *self.value.borrow() }
+}
+
+impl Drop for RateCoder {
+    fn drop(&mut self) {
+        self.task.abort()
+    }
+}
+
+
+/// Population coding: spreads a scalar range across a bank of fibers,
+/// each tuned to a preferred value with a gaussian response curve.
+/// An input fires every fiber whose response reaches the threshold,
+/// so neighbouring receptors overlap and the value can be recovered
+/// from which fibers fired together.
+#[derive(Debug, Clone)]
+pub struct PopulationEncoder {
+    fibers: Vec<u16>,
+    min: f64,
+    max: f64,
+    width: f64,
+    threshold: f64
+}
+
+impl PopulationEncoder {
+
+    /// Tune `fibers` to evenly spaced values from `min` to `max`, in order.
+    /// The default curve width is the spacing between preferred values,
+    /// and the default threshold is 0.5.
+    pub fn new(fibers: Vec<u16>, min: f64, max: f64) -> Result<Self, ConfigError> {
+
+        if fibers.is_empty() || !min.is_finite() || !max.is_finite() || min >= max {
+            return Err(ConfigError::InvalidEncoding)
+        }
+
+        let width = (max - min) / (fibers.len().max(2) - 1) as f64;
+        Ok(PopulationEncoder { fibers, min, max, width, threshold: 0.5 })
+    }
+
+    /// Set the standard deviation of each tuning curve.
+    /// Wider curves make more fibers fire for every value.
+    pub fn with_width(mut self, width: f64) -> Result<Self, ConfigError> {
+
+        if !(width.is_finite() && width > 0.0) { return Err(ConfigError::InvalidEncoding) }
+        self.width = width;
+        Ok(self)
+    }
+
+    /// Set the response (from 0.0 to 1.0) a fiber needs in order to fire.
+    /// NOTE: Values above 1.0 silence the encoder entirely.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+
+        self.threshold = threshold;
+        self
+    }
+
+    /// The value each fiber responds to most strongly.
+    pub fn preferred_values(&self) -> impl Iterator<Item = (u16, f64)> + '_ {
+
+        let count = self.fibers.len();
+        self.fibers.iter().enumerate().map(move |(i, &fiber)| {
+            let center = if count == 1 { (self.min + self.max) / 2.0 } 
+                else { self.min + self.width_step() * i as f64 };
+            (fiber, center)
+        })
+    }
+
+    fn width_step(&self) -> f64 { (self.max - self.min) / (self.fibers.len() - 1) as f64 }
+
+    /// The response of every fiber to `value`, from 0.0 to 1.0.
+    pub fn activations(&self, value: f64) -> Vec<(u16, f64)> {
+
+        self.preferred_values()
+            .map(|(fiber, center)| {
+                let z = (value - center) / self.width;
+                (fiber, (-0.5 * z * z).exp())
+            })
+            .collect()
+    }
+
+    /// The fibers that fire for `value`.
+    /// NaN fires nothing; values outside the range fire the edge of the bank.
+    pub fn encode(&self, value: f64) -> Vec<u16> {
+
+        if value.is_nan() { return Vec::new() }
+        let value = value.clamp(self.min, self.max);
+        self.activations(value).into_iter()
+            .filter(|(_, response)| *response >= self.threshold)
+            .map(|(fiber, _)| fiber)
+            .collect()
+    }
+
+    /// Encode `value` and send the resulting fibers through `sensor`
+    /// as a batch, returning the fibers that were sent.
+    pub async fn fire<Q: Hash + Eq, T: Transport>(
+        &self, 
+        sensor: &Sensor<Q, T>, 
+        value: f64
+    ) -> Result<Vec<u16>, CommunicationError> {
+
+        let fibers = self.encode(value);
+        sensor.transmit_batch(&fibers).await?;
+        Ok(fibers)
+    }
+}
+
+
+#[derive(Default)]
+struct PoissonState {
+    rates: HashMap<u16, f64>,
+    running: bool
+}
+
+/// Drives fibers with independent Poisson spike trains,
+/// i.e. exponentially distributed intervals between impulses.
+/// Each fiber has its own rate in Hz; the source starts stopped,
+/// and the background task ends when the source is dropped.
+pub struct PoissonSource {
+    state: watch::Sender<PoissonState>,
+    task: JoinHandle<()>
+}
+
+impl PoissonSource {
+
+    /// Create a stopped source that fires through `sensor`.
+    pub fn spawn<Q, T>(sensor: Arc<Sensor<Q, T>>) -> Self
+    where
+        Q: Hash + Eq + Send + Sync + 'static,
+        T: Transport + Send + Sync + 'static
+    {
+
+        let (state, mut updates) = watch::channel(PoissonState::default());
+        let task = tokio::spawn(async move {
+            loop {
+                // The fibers' trains are merged into one process at the total rate,
+                // and each spike is assigned to a fiber in proportion to its rate.
+                let rates: Vec<(u16, f64)> = {
+                    let state = updates.borrow_and_update();
+                    if state.running { state.rates.iter().map(|(f, r)| (*f, *r)).collect() } 
+                    else { Vec::new() }
+                };
+                let total: f64 = rates.iter().map(|(_, r)| r).sum();
+                if !(total > 0.0 && total.is_finite()) {
+                    tokio::select! {
+                        _ = sensor.cancel.cancelled() => return,
+                        changed = updates.changed() => if changed.is_err() { return }
+                    }
+                    continue
+                }
+
+                let (interval, fiber) = {
+                    let interval = -(1.0 - rand::random::<f64>()).ln() / total;
+                    let mut pick = rand::random::<f64>() * total;
+                    let fiber = rates.iter()
+                        .find(|(_, rate)| { pick -= rate; pick < 0.0 })
+                        .unwrap_or(&rates[rates.len() - 1]).0;
+                    (Duration::from_secs_f64(interval), fiber)
+                };
+
+                // Intervals are memoryless, so redrawing after a change is exact.
+                tokio::select! {
+                    _ = sensor.cancel.cancelled() => return,
+                    changed = updates.changed() => if changed.is_err() { return },
+                    _ = tokio::time::sleep(interval) => { let _ = sensor.transmit(fiber).await; }
+                }
+            }
+        });
+
+        PoissonSource { state, task }
+    }
+
+    /// Set the mean firing rate of `fiber` in Hz. 
+    /// Zero, negative or non-finite rates silence the fiber.
+    pub fn set_rate(&self, fiber: u16, rate: f64) {
+
+        self.state.send_modify(|state| {
+            if rate > 0.0 && rate.is_finite() { state.rates.insert(fiber, rate); } 
+            else { state.rates.remove(&fiber); }
+        })
+    }
+
+    /// The current rate of `fiber` in Hz (0.0 if it is silent).
+    pub fn rate(&self, fiber: u16) -> f64 {
+
+        self.state.borrow().rates.get(&fiber).copied().unwrap_or(0.0)
+    }
+
+    /// Begin generating impulses.
+    pub fn start(&self) { self.state.send_modify(|state| state.running = true) }
+
+    /// Pause generation; rates are kept for the next `start`.
+    pub fn stop(&self) { self.state.send_modify(|state| state.running = false) }
+
+    /// Whether the source is currently generating impulses.
+    pub fn is_running(&self) -> bool { self.state.borrow().running }
+}
+
+impl Drop for PoissonSource {
+    fn drop(&mut self) {
+        self.task.abort()
+    }
+}
+
+
+/// Latency coding: a sync impulse opens a coding window, and each fiber
+/// then fires once, delayed in proportion to its value. 
+/// A value of 0.0 fires with the sync pulse and 1.0 at the end of the window.
+/// The same code is used to encode on the sensor and decode on the motor.
+/// NOTE: Decoding precision depends on network jitter relative to `window`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencyCode {
+    pub sync_fiber: u16,
+    pub window: Duration
+}
+
+impl LatencyCode {
+
+    /// Create a code using `sync_fiber` to mark the start of each window.
+    pub fn new(sync_fiber: u16, window: Duration) -> Self {
+
+        LatencyCode { sync_fiber, window }
+    }
+
+    /// Send the sync impulse, then each fiber at its delay.
+    /// Values are clamped to the range 0.0 to 1.0, and NaN is skipped.
+    /// Returns when the last impulse has been sent.
+    pub async fn fire<Q: Hash + Eq, T: Transport>(
+        &self, 
+        sensor: &Sensor<Q, T>, 
+        values: &[(u16, f64)]
+    ) -> Result<(), CommunicationError> {
+
+        let mut delays: Vec<(Duration, u16)> = values.iter()
+            .filter(|(_, value)| !value.is_nan())
+            .map(|(fiber, value)| (self.window.mul_f64(value.clamp(0.0, 1.0)), *fiber))
+            .collect();
+        delays.sort_by_key(|(delay, _)| *delay);
+
+        let start = Instant::now();
+        sensor.transmit(self.sync_fiber).await?;
+        for (delay, fiber) in delays {
+            tokio::time::sleep_until(start + delay).await;
+            sensor.transmit(fiber).await?;
+        }
+        Ok(())
+    }
+
+    /// Wait for the sync impulse, then collect the fibers that arrive 
+    /// before the window closes and reconstruct their values from 
+    /// their offsets. Impulses before the sync pulse are discarded, 
+    /// and only the first arrival of each fiber is kept.
+    pub async fn decode<B: Fn(A) -> R, A, R, T: Transport>(
+        &self,
+        motor: &Motor<B, A, R, T>
+    ) -> Result<Vec<(u16, f64)>, CommunicationError> {
+
+        while motor.recv_fiber().await? != self.sync_fiber {}
+
+        let start = Instant::now();
+        let close = start + self.window;
+        let mut values: Vec<(u16, f64)> = Vec::new();
+        while let Ok(fiber) = tokio::time::timeout_at(close, motor.recv_fiber()).await {
+            let fiber = fiber?;
+            if fiber == self.sync_fiber || values.iter().any(|(f, _)| *f == fiber) { continue }
+
+            let offset = start.elapsed().as_secs_f64();
+            let value = if self.window.is_zero() { 0.0 } 
+                else { (offset / self.window.as_secs_f64()).min(1.0) };
+            values.push((fiber, value));
+        }
+        Ok(values)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::transport::{ Transport, ChannelTransport };
+    use crate::types::{ Sensor, Motor };
+    use super::{ RateCoder, PopulationEncoder, PoissonSource, LatencyCode };
+
+    #[tokio::test(start_paused = true)]
+    async fn new_rates_keep_the_last_impulse_as_reference() {
+
+        let (a, b) = ChannelTransport::pair(16);
+        let sensor: Arc<Sensor<char, _>> = Arc::new(Sensor::in_process("rate", a));
+        let coder = RateCoder::spawn(sensor, 1, 10.0);
+        coder.set_value(1.0);
+
+        let mut buffer = [0u8; 64];
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(b.try_recv_from(&mut buffer).is_err());
+
+        // Halving the rate moves the pending impulse to 200ms, not 250ms.
+        coder.set_value(0.5);
+        tokio::time::sleep(Duration::from_millis(160)).await;
+        assert!(b.try_recv_from(&mut buffer).is_ok());
+        assert!(b.try_recv_from(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn neighbouring_fibers_fire_together() {
+
+        assert!(PopulationEncoder::new(vec![], 0.0, 1.0).is_err());
+        let encoder = PopulationEncoder::new(vec![1, 2, 3, 4, 5], 0.0, 1.0).unwrap();
+        let centers: Vec<f64> = encoder.preferred_values().map(|(_, c)| c).collect();
+        assert_eq!(centers, vec![0.0, 0.25, 0.5, 0.75, 1.0]);
+
+        assert_eq!(encoder.encode(0.5), vec![2, 3, 4]);
+        assert_eq!(encoder.encode(-7.0), vec![1, 2]);
+        assert!(encoder.encode(f64::NAN).is_empty());
+        assert!(encoder.clone().with_threshold(1.1).encode(0.5).is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn poisson_trains_fire_near_their_rate_only_while_running() {
+
+        let (a, b) = ChannelTransport::pair(1024);
+        let sensor: Arc<Sensor<char, _>> = Arc::new(Sensor::in_process("poisson", a));
+        let source = PoissonSource::spawn(sensor);
+        source.set_rate(1, 100.0);
+        source.set_rate(2, -1.0);
+        assert_eq!(source.rate(2), 0.0);
+
+        let mut buffer = [0u8; 64];
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(b.try_recv_from(&mut buffer).is_err());
+
+        source.start();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        source.stop();
+        let mut fired = 0;
+        while let Ok((n, _)) = b.try_recv_from(&mut buffer) {
+            assert_eq!(crate::wire::decode_impulse(&buffer[..n]).unwrap(), 1);
+            fired += 1;
+        }
+        assert!((50..150).contains(&fired), "fired {fired} times at 100 Hz");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn latency_codes_decode_to_their_values() {
+
+        let (a, b) = ChannelTransport::pair(16);
+        let sensor: Sensor<char, _> = Sensor::in_process("latency", a);
+        let motor: Motor<fn(()), (), (), _> = Motor::in_process("latency", b);
+        let code = LatencyCode::new(0, Duration::from_millis(100));
+
+        let (fired, decoded) = tokio::join!(
+            code.fire(&sensor, &[(2, 0.5), (1, 0.25), (3, f64::NAN)]),
+            code.decode(&motor)
+        );
+        fired.unwrap();
+        let decoded = decoded.unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].0, 1);
+        assert!((decoded[0].1 - 0.25).abs() < 0.01);
+        assert_eq!(decoded[1].0, 2);
+        assert!((decoded[1].1 - 0.5).abs() < 0.01);
+    }
+}
@@ -0,0 +1,198 @@
+
+//! Declarative limb topology, so deployments can retarget addresses
+//! without recompiling. A config file lists every sensor and motor:
+//! ```toml
+//! [[sensor]]
+//! name = "touch"
+//! bind = "0.0.0.0:0"
+//! remote = "10.0.0.2:9000"
+//! spectrum = { light = 0, firm = 1 }
+//!
+//! [[motor]]
+//! name = "wheel"
+//! bind = "0.0.0.0:9100"
+//! fibers = { 0 = "forward", 1 = "reverse" }
+//! ```
+//! Sensor quanta are the spectrum's keys, so configured sensors are `Sensor<String>`.
+//! Motor behaviors are resolved by name from a `BehaviorRegistry`.
+
+use std::collections::{ BTreeMap, HashMap };
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use serde::Deserialize;
+
+use crate::error::BuildError;
+use crate::limb::Limb;
+use crate::registry::BehaviorRegistry;
+use crate::types::{ Sensor, Motor };
+
+
+/// The topology of a limb, as read from a config file.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LimbConfig {
+    #[serde(default, rename = "sensor")]
+    pub sensors: Vec<SensorConfig>,
+    #[serde(default, rename = "motor")]
+    pub motors: Vec<MotorConfig>
+}
+
+/// A sensor: where it binds, where it sends, and its spectrum.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SensorConfig {
+    pub name: String,
+    pub bind: SocketAddr,
+    pub remote: Option<SocketAddr>,
+    #[serde(default)]
+    pub spectrum: BTreeMap<String, u16>
+}
+
+/// A motor: where it binds, and the behavior name for each fiber ID.
+/// NOTE: Fiber IDs are TOML keys, so they are written as (bare) strings.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MotorConfig {
+    pub name: String,
+    pub bind: SocketAddr,
+    #[serde(default)]
+    pub fibers: BTreeMap<String, String>
+}
+
+/// The limb built by `Limb::from_config`, with its sensors by name.
+/// The motors are owned by the limb; call `Limb::start` to run them.
+pub struct ConfiguredLimb {
+    pub limb: Limb,
+    pub sensors: HashMap<String, Arc<Sensor<String>>>
+}
+
+impl LimbConfig {
+
+    /// Parse a config from TOML text.
+    pub fn parse(text: &str) -> Result<Self, BuildError> {
+
+        toml::from_str(text).map_err(|e| BuildError::InvalidConfig(e.to_string()))
+    }
+
+    /// Read and parse a config file.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, BuildError> {
+
+        let text = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| BuildError::InvalidConfig(format!("{}: {e}", path.as_ref().display())))?;
+        Self::parse(&text)
+    }
+}
+
+impl MotorConfig {
+
+    fn fiber_ids(&self) -> Result<Vec<(u16, &str)>, BuildError> {
+
+        self.fibers.iter()
+            .map(|(fid, behavior)| fid.parse::<u16>()
+                .map(|fid| (fid, behavior.as_str()))
+                .map_err(|_| BuildError::InvalidConfig(
+                    format!("motor '{}': '{fid}' is not a fiber ID", self.name)
+                )))
+            .collect()
+    }
+}
+
+impl Limb {
+
+    /// Build a limb from the config file at `path` (see `config`).
+    /// Every motor runs its behaviors with an argument from its own clone of `args`.
+    pub async fn from_config<B, A, R, F>(
+        path: impl AsRef<Path>,
+        registry: &BehaviorRegistry<B>,
+        args: F
+    ) -> Result<ConfiguredLimb, BuildError>
+    where
+        B: Fn(A) -> R + Clone + Send + Sync + 'static,
+        A: Send + 'static,
+        R: Send + 'static,
+        F: FnMut() -> A + Clone + Send + 'static
+    {
+
+        Self::build_config(&LimbConfig::read(path)?, registry, args).await
+    }
+
+    /// Build a limb from an already-parsed config.
+    pub async fn build_config<B, A, R, F>(
+        config: &LimbConfig,
+        registry: &BehaviorRegistry<B>,
+        args: F
+    ) -> Result<ConfiguredLimb, BuildError>
+    where
+        B: Fn(A) -> R + Clone + Send + Sync + 'static,
+        A: Send + 'static,
+        R: Send + 'static,
+        F: FnMut() -> A + Clone + Send + 'static
+    {
+
+        let mut limb = Limb::new();
+        let mut sensors = HashMap::new();
+        for sensor_config in &config.sensors {
+            let mut sensor = Sensor::new(&sensor_config.name, sensor_config.bind).await?
+                .with_spectrum(sensor_config.spectrum.clone());
+            if let Some(remote) = &sensor_config.remote {
+                sensor.connect(remote).await?;
+            }
+            sensors.insert(sensor_config.name.clone(), limb.add_sensor(sensor)?);
+        }
+
+        for motor_config in &config.motors {
+            let mut motor = Motor::new(&motor_config.name, motor_config.bind).await?;
+            for (fid, behavior) in motor_config.fiber_ids()? {
+                motor.add_named_fiber(fid, behavior, registry)?;
+            }
+            limb.add_motor(motor, args.clone())?;
+        }
+
+        Ok(ConfiguredLimb { limb, sensors })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::error::BuildError;
+    use crate::limb::{ Limb, TractKind };
+    use crate::registry::BehaviorRegistry;
+    use super::LimbConfig;
+
+    const TOPOLOGY: &str = r#"
+        [[sensor]]
+        name = "touch"
+        bind = "127.0.0.1:0"
+        remote = "127.0.0.1:9000"
+        spectrum = { light = 0, firm = 1 }
+
+        [[motor]]
+        name = "wheel"
+        bind = "127.0.0.1:0"
+        fibers = { 0 = "forward", 1 = "reverse" }
+    "#;
+
+    #[tokio::test]
+    async fn config_builds_every_tract() {
+
+        let registry = BehaviorRegistry::new()
+            .with("forward", (|x| x + 1) as fn(i8) -> i8)
+            .with("reverse", (|x| x - 1) as fn(i8) -> i8);
+        let config = LimbConfig::parse(TOPOLOGY).unwrap();
+        let configured = Limb::build_config(&config, &registry, || 0).await.unwrap();
+
+        let touch = &configured.sensors["touch"];
+        assert_eq!(touch.peer(), Some("127.0.0.1:9000".parse().unwrap()));
+        assert_eq!(touch.spectrum.get("firm"), Some(&1));
+        let mut names: Vec<(&str, TractKind)> = configured.limb.tract_names().collect();
+        names.sort_by_key(|(name, _)| *name);
+        assert_eq!(names, vec![("touch", TractKind::Sensor), ("wheel", TractKind::Motor)]);
+
+        let bad = LimbConfig::parse(&TOPOLOGY.replace("0 = \"forward\"", "zero = \"forward\"")).unwrap();
+        assert!(matches!(
+            Limb::build_config(&bad, &registry, || 0).await, 
+            Err(BuildError::InvalidConfig(_))
+        ));
+        assert!(matches!(LimbConfig::parse("[[sensor]]"), Err(BuildError::InvalidConfig(_))));
+    }
+}
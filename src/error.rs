@@ -3,7 +3,25 @@
 pub enum BuildError {
 
     #[error("Failed to create socket: {0}")]
-    SocketFailed(#[from] std::io::Error)
+    SocketFailed(#[from] std::io::Error),
+
+    #[error("Failed to create runtime: {0}")]
+    RuntimeFailed(std::io::Error),
+
+    #[error("Tract is misconfigured: {0}")]
+    Misconfigured(#[from] ConfigError),
+
+    #[error("Already connected to {0}")]
+    AlreadyConnected(std::net::SocketAddr),
+
+    #[error("Handshake with peer failed: {0}")]
+    HandshakeFailed(#[from] CommunicationError),
+
+    #[error("Failed to decode limb export: {0}")]
+    InvalidExport(Box<bincode::ErrorKind>),
+
+    #[error("Invalid limb config: {0}")]
+    InvalidConfig(String)
 }
 
 
@@ -16,10 +34,105 @@ pub enum CommunicationError {
     #[error("Failed to serialize/deserialize message: {0}")]
     SerdeFailed(#[from] Box<bincode::ErrorKind>),
 
+    #[error("Failed to encode/decode frame: {0}")]
+    CodecFailed(String),
+
     #[error("Unrecognized impulse via fiber ID: {0}")]
     UnrecognizedImpulse(u16),
 
     #[error("Unrecognized trigger from Sensor '{0}'")]
-    UnrecognizedTrigger(String)
+    UnrecognizedTrigger(String),
+
+    #[error("Tract is misconfigured: {0}")]
+    Misconfigured(#[from] ConfigError),
+
+    #[error("Timed out waiting for peer")]
+    Timeout,
+
+    #[error("Transfer incomplete, {0} chunks missing")]
+    TransferIncomplete(usize),
+
+    #[error("Transfer of {0} chunks exceeds the limit")]
+    TransferTooLarge(usize),
+
+    #[error("Impulse queue is full")]
+    QueueFull,
+
+    #[error("Impulse queue is closed")]
+    QueueClosed,
+
+    #[error("Behavior panicked for fiber ID: {0}")]
+    BehaviorPanicked(u16),
+
+    #[error("Sensor is quiescing and not accepting new impulses")]
+    Quiescing,
+
+    #[error("Unsupported wire format version: {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Datagram is not a recognized impulse frame")]
+    ProtocolMismatch,
+
+    #[error("Unrecognized frame type: {0:#04x}")]
+    UnknownFrame(u8),
+
+    #[error("Behavior failed: {0}")]
+    BehaviorFailed(String),
+
+    #[error("Behavior for fiber ID {fiber} timed out")]
+    BehaviorTimedOut { fiber: u16 },
+
+    #[error("Failed to reach {} of the sensor's destinations", .0.len())]
+    FanOutFailed(Vec<(std::net::SocketAddr, std::io::Error)>),
+
+    #[error("Burst interrupted after {sent} impulses: {source}")]
+    PartialBurst { sent: usize, source: Box<CommunicationError> },
+
+    #[error("Fiber ID {0} is refractory")]
+    Refractory(u16)
+}
+
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+
+    #[error("No usable argument source for fiber IDs: {0:?}")]
+    MissingArgSources(Vec<u16>),
+
+    #[error("No behavior registered under the name '{0}'")]
+    UnknownBehavior(String),
+
+    #[error("Weights must be finite, non-negative and not all zero")]
+    InvalidWeights,
+
+    #[error("Peer has no behavior for fiber IDs: {0:?}")]
+    FiberIdMismatch(Vec<u16>),
+
+    #[error("Replay speed must be zero or positive, got {0}")]
+    InvalidReplaySpeed(f64),
+
+    #[error("An encoder needs at least one fiber and a finite, non-empty range")]
+    InvalidEncoding,
+
+    #[error("Range {0:?} is empty, not finite, or overlaps an existing receptor")]
+    InvalidRange(std::ops::Range<f64>),
+
+    #[error("Fiber IDs are assigned more than once: {0:?}")]
+    DuplicateFiberIds(Vec<u16>),
+
+    #[error("A quantum is mapped more than once")]
+    DuplicateQuantum,
+
+    #[error("Got {quanta} quanta for {fibers} fiber IDs")]
+    LengthMismatch { quanta: usize, fibers: usize },
+
+    #[error("Already mapped to fiber ID {0}")]
+    AlreadyMapped(u16),
+
+    #[error("A tract named '{0}' is already in the limb")]
+    DuplicateTractName(String),
+
+    #[error("Tracts '{tract}' and '{other}' both send fiber IDs {fibers:?} to the same peer")]
+    FiberCollision { tract: String, other: String, fibers: Vec<u16> }
 }
 
@@ -0,0 +1,44 @@
+
+use std::net::{ SocketAddr, IpAddr, Ipv4Addr, Ipv6Addr };
+use tokio::net::UdpSocket;
+
+use crate::error::CommunicationError;
+
+
+/// Send a single raw fiber ID to `address` from a throwaway socket.
+/// Intended for scripts, tooling and tests, NOT the hot path:
+/// every call binds (and then drops) a new ephemeral socket,
+/// so use a `Sensor` for repeated impulses.
+pub async fn fire(
+    address: SocketAddr, 
+    fiber_id: u16
+) -> Result<(), CommunicationError> {
+
+    let local = match address {
+        SocketAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+        SocketAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+    };
+
+    let socket = UdpSocket::bind(local).await?;
+    let impulse = crate::wire::encode_impulse(fiber_id)?;
+    socket.send_to(&impulse, address).await?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::types::FnMotor;
+    use super::fire;
+
+    #[tokio::test]
+    async fn fired_impulse_runs_the_motor_behavior() {
+
+        let motor = FnMotor::new("fire", "127.0.0.1:0".parse().unwrap()).await.unwrap()
+            .with_fiber(513, |x: u16| x * 2);
+        fire(motor.address, 513).await.unwrap();
+
+        assert_eq!(motor.recv_impulse(21).await.unwrap(), 42);
+    }
+}
@@ -1,5 +1,37 @@
 
 pub mod error;
 pub mod types;
-pub use types::{ Sensor, Motor };
+pub use types::{ TractId, FiberId, FiberWidth, Sensor, Motor, FnMotor, BoxMotor, LocalMotor, DynMotor, AsyncMotor };
+
+pub mod blocking;
+pub mod registry;
+pub use registry::{ BehaviorRegistry, MotorFibers };
+pub mod behavior;
+pub use behavior::Behavior;
+pub mod testing;
+pub mod transport;
+pub mod spectrum;
+pub use spectrum::{ FiberLookup, StaticSpectrum, RangeSpectrum, Spectrum };
+#[cfg(feature = "derive")]
+pub use phantom_limb_derive::{ Spectrum, MotorFibers };
+pub mod quantize;
+pub use quantize::Quantize;
+pub mod limb;
+pub mod metrics;
+pub mod codec;
+pub mod coding;
+#[cfg(feature = "tower")]
+pub mod service;
+pub use metrics::TractMetrics;
+pub use limb::{ PhantomLimb, TractGuard, ImportedLimb, Limb };
+pub mod reflex;
+#[cfg(feature = "config")]
+pub mod config;
+pub use reflex::Reflex;
+
+mod wire;
+pub use wire::{ ImpulseFrame, WIRE_VERSION, WIRE_MAGIC, DEFAULT_MTU, max_impulses_per_datagram };
+mod bloom;
+mod fire;
+pub use fire::fire;
 
@@ -0,0 +1,647 @@
+
+use std::net::SocketAddr;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{ Arc, Weak, Mutex, MutexGuard };
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use serde::{ Serialize, Deserialize, de::DeserializeOwned };
+
+use crate::error::{ BuildError, CommunicationError, ConfigError };
+use crate::metrics::{ Metrics, TractMetrics };
+use crate::registry::BehaviorRegistry;
+use crate::transport::Transport;
+use crate::types::{ TractId, Sensor, Motor, SensorSnapshot, MotorSnapshot };
+
+
+/// Whether a tract sends into or receives from a Complex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TractKind { Sensor, Motor }
+
+/// What a `PhantomLimb` knows about each registered tract.
+#[derive(Debug, Clone)]
+pub struct TractInfo {
+    pub name: String,
+    pub kind: TractKind,
+    pub address: SocketAddr,
+    config: Option<TractConfig>,
+    metrics: Metrics
+}
+
+/// Exportable wiring captured when a tract is registered.
+/// Sensor quanta are stored individually serialized,
+/// so one limb can hold sensors with different quantum types.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum TractConfig {
+    Sensor(SensorSnapshot<Vec<u8>>),
+    Motor(MotorSnapshot)
+}
+
+/// The serialized form produced by `PhantomLimb::export`.
+#[derive(Serialize, Deserialize)]
+struct LimbExport {
+    sensors: Vec<SensorSnapshot<Vec<u8>>>,
+    motors: Vec<MotorSnapshot>
+}
+
+/// The tracts rebuilt by `PhantomLimb::import`, already registered 
+/// with `limb`. Keep the guards alive as long as the tracts are in use.
+pub struct ImportedLimb<Q: Hash + Eq, B: Fn(A) -> R, A, R> {
+    pub limb: PhantomLimb,
+    pub sensors: Vec<(Sensor<Q>, TractGuard)>,
+    pub motors: Vec<(Motor<B, A, R>, TractGuard)>
+}
+
+type TractMap = Mutex<HashMap<TractId, TractInfo>>;
+
+fn lock(tracts: &TractMap) -> MutexGuard<'_, HashMap<TractId, TractInfo>> {
+    tracts.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+
+/// Keeps track of the tracts that make up one peripheral system.
+/// Registering a tract returns a `TractGuard` that keeps it listed;
+/// drop or `unregister` the guard when the tract goes away.
+#[derive(Default)]
+pub struct PhantomLimb {
+    tracts: Arc<TractMap>,
+    cancel: CancellationToken
+}
+
+impl PhantomLimb {
+
+    /// Create a limb with no tracts.
+    pub fn new() -> Self { Self::default() }
+
+    /// A token for a tract's `set_cancellation_token`, 
+    /// cancelled when `shutdown` is called on this limb.
+    pub fn cancellation_token(&self) -> CancellationToken { self.cancel.child_token() }
+
+    /// Stop the background tasks of every tract given a token from `cancellation_token`.
+    pub fn shutdown(&self) { self.cancel.cancel() }
+
+    /// Whether `shutdown` has been called.
+    pub fn is_shut_down(&self) -> bool { self.cancel.is_cancelled() }
+
+    /// List a sensor in this limb until the returned guard is dropped.
+    pub fn add_sensor<Q: Hash + Eq, T: Transport>(
+        &self, 
+        sensor: &Sensor<Q, T>
+    ) -> TractGuard {
+
+        self.register(sensor.tract_id(), TractInfo {
+            name: sensor.tract_name.clone(),
+            kind: TractKind::Sensor,
+            address: sensor.advertised_address(),
+            config: None,
+            metrics: sensor.metrics.clone()
+        })
+    }
+
+    /// List a sensor and capture its spectrum for `export`.
+    /// The spectrum is captured now; call `refresh_sensor` after
+    /// changing receptors to update the exported copy.
+    pub fn add_exportable_sensor<Q: Hash + Eq + Clone + Serialize>(
+        &self, 
+        sensor: &Sensor<Q>
+    ) -> TractGuard {
+
+        self.register(sensor.tract_id(), TractInfo {
+            name: sensor.tract_name.clone(),
+            kind: TractKind::Sensor,
+            address: sensor.advertised_address(),
+            config: Some(TractConfig::Sensor(encode_snapshot(sensor))),
+            metrics: sensor.metrics.clone()
+        })
+    }
+
+    /// Update the exportable spectrum of a sensor that is already listed.
+    /// Returns false if the sensor is not registered with this limb.
+    pub fn refresh_sensor<Q: Hash + Eq + Clone + Serialize>(
+        &self, 
+        sensor: &Sensor<Q>
+    ) -> bool {
+
+        match lock(&self.tracts).get_mut(&sensor.tract_id()) {
+            Some(info) => {
+                info.config = Some(TractConfig::Sensor(encode_snapshot(sensor)));
+                true
+            },
+            None => false
+        }
+    }
+
+    /// List a motor in this limb until the returned guard is dropped.
+    pub fn add_motor<B: Fn(A) -> R, A, R, T: Transport>(
+        &self, 
+        motor: &Motor<B, A, R, T>
+    ) -> TractGuard {
+
+        self.register(motor.tract_id(), TractInfo {
+            name: motor.tract_name.clone(),
+            kind: TractKind::Motor,
+            address: motor.address,
+            config: Some(TractConfig::Motor(motor.snapshot())),
+            metrics: motor.metrics.clone()
+        })
+    }
+
+    fn register(&self, id: TractId, info: TractInfo) -> TractGuard {
+
+        lock(&self.tracts).insert(id, info);
+        TractGuard { id, limb: Arc::downgrade(&self.tracts), task: None }
+    }
+
+    /// Every registered tract.
+    pub fn tracts(&self) -> Vec<(TractId, TractInfo)> {
+
+        lock(&self.tracts).iter()
+            .map(|(id, info)| (*id, info.clone()))
+            .collect()
+    }
+
+    /// Information about a registered tract.
+    pub fn get(&self, id: TractId) -> Option<TractInfo> {
+
+        lock(&self.tracts).get(&id).cloned()
+    }
+
+    /// Whether a tract is currently registered.
+    pub fn contains(&self, id: TractId) -> bool { lock(&self.tracts).contains_key(&id) }
+
+    /// Number of registered tracts.
+    pub fn len(&self) -> usize { lock(&self.tracts).len() }
+
+    /// Whether no tracts are registered.
+    pub fn is_empty(&self) -> bool { lock(&self.tracts).is_empty() }
+
+    /// Current counters of every registered tract, ordered by ID.
+    pub fn metrics(&self) -> Vec<TractMetrics> {
+
+        let tracts = lock(&self.tracts);
+        let mut ids: Vec<&TractId> = tracts.keys().collect();
+        ids.sort();
+        ids.into_iter()
+            .map(|id| tracts[id].metrics.snapshot(&tracts[id].name))
+            .collect()
+    }
+
+    /// Render the counters of every registered tract 
+    /// in the Prometheus text exposition format.
+    #[cfg(feature = "prometheus")]
+    pub fn render_prometheus(&self) -> String {
+
+        crate::metrics::render_prometheus(&self.metrics())
+    }
+
+    /// Serialize the wiring of every exportable tract: the spectrums of 
+    /// sensors added with `add_exportable_sensor`, and the named fibers
+    /// of every motor (see `Motor::snapshot`). Tracts are ordered by ID.
+    pub fn export(&self) -> Vec<u8> {
+
+        let tracts = lock(&self.tracts);
+        let mut ids: Vec<&TractId> = tracts.keys().collect();
+        ids.sort();
+
+        let mut export = LimbExport { sensors: Vec::new(), motors: Vec::new() };
+        for id in ids {
+            match &tracts[id].config {
+                Some(TractConfig::Sensor(snapshot)) => export.sensors.push(snapshot.clone()),
+                Some(TractConfig::Motor(snapshot)) => export.motors.push(snapshot.clone()),
+                None => {}
+            }
+        }
+
+        bincode::serialize(&export).expect("limb export is always serializable")
+    }
+
+    /// Rebuild every tract from `export` bytes into a new limb.
+    /// Each tract binds to the address in `bind_overrides` under its name,
+    /// or to an OS-assigned port on all interfaces if it has none.
+    /// Motor behaviors are resolved by name from `registry`.
+    /// NOTE: All sensors are rebuilt with the same quantum type `Q`.
+    pub async fn import<Q, B, A, R>(
+        bytes: &[u8],
+        registry: &BehaviorRegistry<B>,
+        bind_overrides: &HashMap<String, SocketAddr>
+    ) -> Result<ImportedLimb<Q, B, A, R>, BuildError> 
+    where 
+        Q: Hash + Eq + Clone + Serialize + DeserializeOwned,
+        B: Fn(A) -> R + Clone
+    {
+
+        let export: LimbExport = bincode::deserialize(bytes)
+            .map_err(BuildError::InvalidExport)?;
+
+        let default_bind = SocketAddr::from(([0, 0, 0, 0], 0));
+        let bind_for = |name: &str| bind_overrides.get(name).copied().unwrap_or(default_bind);
+
+        let limb = PhantomLimb::new();
+        let mut sensors = Vec::new();
+        for snapshot in export.sensors {
+            let spectrum = snapshot.spectrum.iter()
+                .map(|(quantum, fid)| Ok((bincode::deserialize(quantum)?, *fid)))
+                .collect::<Result<Vec<(Q, u16)>, Box<bincode::ErrorKind>>>()
+                .map_err(BuildError::InvalidExport)?;
+
+            let bind = bind_for(&snapshot.tract_name);
+            let snapshot = SensorSnapshot { 
+                tract_name: snapshot.tract_name, 
+                advertised: snapshot.advertised, 
+                peer: snapshot.peer, 
+                spectrum 
+            };
+            let sensor = Sensor::restore(snapshot, bind).await?;
+            let guard = limb.add_exportable_sensor(&sensor);
+            sensors.push((sensor, guard));
+        }
+
+        let mut motors = Vec::new();
+        for snapshot in export.motors {
+            let bind = bind_for(&snapshot.tract_name);
+            let motor = Motor::restore(snapshot, registry, bind).await?;
+            let guard = limb.add_motor(&motor);
+            motors.push((motor, guard));
+        }
+
+        Ok(ImportedLimb { limb, sensors, motors })
+    }
+}
+
+fn encode_snapshot<Q: Hash + Eq + Clone + Serialize>(sensor: &Sensor<Q>) -> SensorSnapshot<Vec<u8>> {
+
+    let snapshot = sensor.snapshot();
+    SensorSnapshot {
+        tract_name: snapshot.tract_name,
+        advertised: snapshot.advertised,
+        peer: snapshot.peer,
+        spectrum: snapshot.spectrum.iter()
+            .map(|(quantum, fid)| {
+                let quantum = bincode::serialize(quantum).expect("quantum is serializable");
+                (quantum, *fid)
+            })
+            .collect()
+    }
+}
+
+
+/// Keeps a tract registered with a `PhantomLimb`.
+/// Dropping the guard removes the tract and aborts its attached task
+/// (if any). Abort in `Drop` is best-effort: the task stops at its next
+/// `.await`, but `Drop` cannot wait for it. Use `unregister` to also 
+/// wait for the task to finish. If the limb has already been dropped,
+/// there is nothing to remove.
+pub struct TractGuard {
+    id: TractId,
+    limb: Weak<TractMap>,
+    task: Option<JoinHandle<()>>
+}
+
+impl TractGuard {
+
+    /// The ID of the guarded tract.
+    pub fn id(&self) -> TractId { self.id }
+
+    /// Tie a spawned loop (e.g. the one driving the tract) to this guard,
+    /// so it is stopped when the tract is unregistered.
+    /// Aborts any task that was previously attached.
+    pub fn attach_task(&mut self, task: JoinHandle<()>) {
+
+        if let Some(previous) = self.task.replace(task) { previous.abort() }
+    }
+
+    fn remove(&self) {
+
+        if let Some(tracts) = self.limb.upgrade() {
+            lock(&tracts).remove(&self.id);
+        }
+    }
+
+    /// Remove the tract from the limb, then stop its task 
+    /// and wait for it to finish.
+    pub async fn unregister(mut self) {
+
+        self.remove();
+        if let Some(task) = self.task.take() {
+            task.abort();
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for TractGuard {
+    fn drop(&mut self) {
+        self.remove();
+        if let Some(task) = self.task.take() { task.abort() }
+    }
+}
+
+
+
+/// Errors held by a `Limb` before new errors are dropped.
+pub const LIMB_ERROR_CAPACITY: usize = 256;
+
+type MotorStarter = Box<dyn FnOnce(mpsc::Sender<(String, CommunicationError)>) -> JoinHandle<()> + Send>;
+
+/// Owns the tracts of one peripheral system and runs them together.
+/// Tract names must be unique, and sensors sending to the same peer 
+/// must not share fiber IDs, since the motor could not tell them apart.
+/// Every tract is listed in the limb's `PhantomLimb` and shares its 
+/// cancellation token, so `stop` ends every background task at once.
+/// Dropping the limb aborts the motor loops.
+pub struct Limb {
+    registry: PhantomLimb,
+    names: HashMap<String, TractKind>,
+    sensor_fibers: Vec<(String, SocketAddr, Vec<u16>)>,
+    guards: Vec<TractGuard>,
+    starters: Vec<MotorStarter>,
+    tasks: Vec<JoinHandle<()>>,
+    errors_tx: mpsc::Sender<(String, CommunicationError)>,
+    errors: mpsc::Receiver<(String, CommunicationError)>
+}
+
+impl Default for Limb {
+    fn default() -> Self {
+        let (errors_tx, errors) = mpsc::channel(LIMB_ERROR_CAPACITY);
+        Limb {
+            registry: PhantomLimb::new(),
+            names: HashMap::new(),
+            sensor_fibers: Vec::new(),
+            guards: Vec::new(),
+            starters: Vec::new(),
+            tasks: Vec::new(),
+            errors_tx,
+            errors
+        }
+    }
+}
+
+impl Limb {
+
+    /// Create a limb with no tracts.
+    pub fn new() -> Self { Self::default() }
+
+    /// The registry listing every tract in the limb.
+    pub fn registry(&self) -> &PhantomLimb { &self.registry }
+
+    fn claim_name(&mut self, name: &str, kind: TractKind) -> Result<(), ConfigError> {
+
+        if self.names.contains_key(name) {
+            return Err(ConfigError::DuplicateTractName(name.to_owned()))
+        }
+        self.names.insert(name.to_owned(), kind);
+        Ok(())
+    }
+
+    /// Add a sensor, returning it shared so it can be fired from anywhere.
+    /// Fails if its name is taken, or if another sensor sends any of the 
+    /// same fiber IDs to the same peer.
+    pub fn add_sensor<Q: Hash + Eq, T: Transport>(
+        &mut self, 
+        mut sensor: Sensor<Q, T>
+    ) -> Result<Arc<Sensor<Q, T>>, ConfigError> {
+
+        let mut fibers: Vec<u16> = sensor.receptors().map(|(_, fid)| fid).collect();
+        fibers.sort_unstable();
+        fibers.dedup();
+
+        if let Some(peer) = sensor.peer() {
+            for (other, other_peer, other_fibers) in &self.sensor_fibers {
+                let shared: Vec<u16> = fibers.iter()
+                    .filter(|fid| other_fibers.binary_search(fid).is_ok())
+                    .copied()
+                    .collect();
+                if *other_peer == peer && !shared.is_empty() {
+                    return Err(ConfigError::FiberCollision { 
+                        tract: sensor.tract_name.clone(), 
+                        other: other.clone(), 
+                        fibers: shared 
+                    })
+                }
+            }
+        }
+
+        self.claim_name(&sensor.tract_name, TractKind::Sensor)?;
+        if let Some(peer) = sensor.peer() {
+            self.sensor_fibers.push((sensor.tract_name.clone(), peer, fibers));
+        }
+
+        sensor.set_cancellation_token(self.registry.cancellation_token());
+        self.guards.push(self.registry.add_sensor(&sensor));
+        Ok(Arc::new(sensor))
+    }
+
+    /// Add a motor, to be run by `start` with an argument from `args` 
+    /// for every impulse. Results are discarded; errors are collected 
+    /// (see `next_error`). Fails if its name is taken.
+    pub fn add_motor<B, A, R, T, F>(
+        &mut self, 
+        mut motor: Motor<B, A, R, T>, 
+        mut args: F
+    ) -> Result<(), ConfigError>
+    where
+        B: Fn(A) -> R + Send + Sync + 'static,
+        A: Send + 'static,
+        R: Send + 'static,
+        T: Transport + Send + Sync + 'static,
+        F: FnMut() -> A + Send + 'static
+    {
+
+        self.claim_name(&motor.tract_name, TractKind::Motor)?;
+        motor.set_cancellation_token(self.registry.cancellation_token());
+        self.guards.push(self.registry.add_motor(&motor));
+
+        self.starters.push(Box::new(move |errors| tokio::spawn(async move {
+            let cancel = motor.cancellation_token().clone();
+            loop {
+                let fiber = tokio::select! {
+                    biased;
+                    _ = cancel.cancelled() => break,
+                    fiber = motor.recv_fiber() => fiber
+                };
+                if let Err(e) = fiber.and_then(|fiber| motor.dispatch(fiber, args())) {
+                    let _ = errors.try_send((motor.tract_name.clone(), e));
+                }
+            }
+        })));
+        Ok(())
+    }
+
+    /// Start every motor added since the last call. Must be called within a runtime.
+    pub fn start(&mut self) {
+
+        for starter in self.starters.drain(..) {
+            self.tasks.push(starter(self.errors_tx.clone()));
+        }
+    }
+
+    /// Stop every tract's background tasks and motor loop.
+    pub fn stop(&self) { self.registry.shutdown() }
+
+    /// Stop every tract and wait for the motor loops to finish 
+    /// the behaviors in progress.
+    pub async fn shutdown(mut self) {
+
+        self.stop();
+        for task in self.tasks.drain(..) { let _ = task.await; }
+    }
+
+    /// Wait for the next error from any motor, with the name of its tract.
+    /// NOTE: Once `LIMB_ERROR_CAPACITY` errors are queued, new ones are dropped.
+    pub async fn next_error(&mut self) -> Option<(String, CommunicationError)> {
+
+        self.errors.recv().await
+    }
+
+    /// Take an error without waiting, if one is queued.
+    pub fn try_next_error(&mut self) -> Option<(String, CommunicationError)> {
+
+        self.errors.try_recv().ok()
+    }
+
+    /// Current counters for every tract in the limb.
+    pub fn metrics(&self) -> Vec<TractMetrics> { self.registry.metrics() }
+
+    /// Names of every tract in the limb and whether each is a sensor or motor.
+    pub fn tract_names(&self) -> impl Iterator<Item = (&str, TractKind)> {
+
+        self.names.iter().map(|(name, kind)| (name.as_str(), *kind))
+    }
+}
+
+impl Drop for Limb {
+    fn drop(&mut self) {
+        for task in &self.tasks { task.abort() }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+    use super::*;
+
+    fn in_process_pair() -> (Sensor<u8, ChannelTransport>, Motor<fn(()), (), (), ChannelTransport>) {
+
+        let (a, b) = ChannelTransport::pair(16);
+        (Sensor::in_process("sensor", a), Motor::in_process("motor", b))
+    }
+
+    #[tokio::test]
+    async fn dropping_a_guard_unregisters_and_aborts_its_task() {
+
+        let limb = PhantomLimb::new();
+        let (sensor, motor) = in_process_pair();
+        let sensor_guard = limb.add_sensor(&sensor);
+        let mut motor_guard = limb.add_motor(&motor);
+        assert_eq!(limb.len(), 2);
+        assert_eq!(limb.get(motor.tract_id()).unwrap().kind, TractKind::Motor);
+
+        let (alive, mut stopped) = tokio::sync::oneshot::channel::<()>();
+        motor_guard.attach_task(tokio::spawn(async move {
+            let _alive = alive;
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+        }));
+
+        drop(motor_guard);
+        assert!(!limb.contains(motor.tract_id()));
+        assert!((&mut stopped).await.is_err());
+
+        sensor_guard.unregister().await;
+        assert!(limb.is_empty());
+    }
+
+    #[test]
+    fn guard_outliving_its_limb_is_harmless() {
+
+        let limb = PhantomLimb::new();
+        let (sensor, _motor) = in_process_pair();
+        let guard = limb.add_sensor(&sensor);
+        drop(limb);
+        drop(guard);
+    }
+
+    #[tokio::test]
+    async fn export_round_trips_through_import() {
+
+        let any: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let registry = BehaviorRegistry::new().with("grip", (|x| x + 1) as fn(u8) -> u8);
+        let mut motor: Motor<fn(u8) -> u8, u8, u8> = Motor::new("hand", any).await.unwrap();
+        motor.add_named_fiber(2, "grip", &registry).unwrap();
+        let sensor: Sensor<String> = Sensor::new("eye", any).await.unwrap()
+            .with_spectrum([("light".to_owned(), 2)]);
+
+        let limb = PhantomLimb::new();
+        let _motor_guard = limb.add_motor(&motor);
+        let _sensor_guard = limb.add_exportable_sensor(&sensor);
+        let bytes = limb.export();
+
+        let overrides = HashMap::from([("eye".to_owned(), any), ("hand".to_owned(), any)]);
+        let imported: ImportedLimb<String, _, _, _> = PhantomLimb::import(&bytes, &registry, &overrides)
+            .await.unwrap();
+
+        assert_eq!(imported.limb.len(), 2);
+        let (eye, _) = &imported.sensors[0];
+        assert_eq!(eye.tract_name, "eye");
+        assert_eq!(eye.spectrum, sensor.spectrum);
+        let (hand, _) = &imported.motors[0];
+        assert_eq!(hand.dispatch(2, 1).unwrap(), 2);
+        assert!(PhantomLimb::import::<String, fn(u8) -> u8, u8, u8>(b"junk", &registry, &overrides)
+            .await.is_err());
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_every_tract_given_a_token() {
+
+        let limb = PhantomLimb::new();
+        let (mut sensor, mut motor) = in_process_pair();
+        sensor.set_cancellation_token(limb.cancellation_token());
+        motor.set_cancellation_token(limb.cancellation_token());
+
+        let (_quanta, driver) = Arc::new(sensor).channel(4);
+        let mut serving = motor.serve(|| ());
+        assert!(!limb.is_shut_down());
+
+        limb.shutdown();
+        assert!(limb.is_shut_down());
+        assert!(matches!(driver.join().await, Some(Ok(0))));
+        assert!(serving.next_error().await.is_none());
+        assert!(serving.shutdown().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn limb_rejects_clashing_tracts_and_collects_motor_errors() {
+
+        let any: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let hand: Motor<fn(()), (), ()> = Motor::new("hand", any).await.unwrap().with_fiber(1, |_| ());
+        let eye: Sensor<u8> = Sensor::connected("eye", any, hand.address).await.unwrap()
+            .with_spectrum([(0, 1), (9, 9)]);
+        let ear: Sensor<u8> = Sensor::connected("ear", any, hand.address).await.unwrap()
+            .with_spectrum([(0, 9)]);
+        let blind: Sensor<u8> = Sensor::new("eye", any).await.unwrap();
+
+        let mut limb = Limb::new();
+        let eye = limb.add_sensor(eye).unwrap();
+        match limb.add_sensor(ear) {
+            Err(ConfigError::FiberCollision { fibers, .. }) => assert_eq!(fibers, vec![9]),
+            other => panic!("unexpected {:?}", other.err())
+        }
+        assert!(matches!(limb.add_sensor(blind), Err(ConfigError::DuplicateTractName(name)) if name == "eye"));
+        limb.add_motor(hand, || ()).unwrap();
+        limb.start();
+
+        eye.send_impulse(&9).await.unwrap();
+        match limb.next_error().await {
+            Some((tract, CommunicationError::UnrecognizedImpulse(9))) => assert_eq!(tract, "hand"),
+            other => panic!("unexpected {other:?}")
+        }
+        assert!(!eye.cancellation_token().is_cancelled());
+        limb.shutdown().await;
+        assert!(eye.cancellation_token().is_cancelled());
+    }
+}
@@ -0,0 +1,182 @@
+
+use std::sync::{ Arc, Mutex };
+use std::sync::atomic::{ AtomicU64, Ordering };
+use serde::{ Serialize, Deserialize };
+
+
+/// Counters for one tract at a point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TractMetrics {
+    pub tract_name: String,
+
+    /// Impulses handed to the transport by a sensor.
+    pub impulses_sent: u64,
+
+    /// Impulses received by a motor.
+    pub impulses_received: u64,
+
+    /// Quanta (sensor) or fiber IDs (motor) with no mapping.
+    pub unrecognized: u64,
+
+    /// Impulses that were triggered but never sent (suppressed or failed),
+    /// or datagrams a motor could not decode.
+    pub dropped: u64
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    sent: AtomicU64,
+    received: AtomicU64,
+    unrecognized: AtomicU64,
+    dropped: AtomicU64
+}
+
+/// Shared, lock-free recorder behind `TractMetrics`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Metrics(Arc<Counters>);
+
+impl Metrics {
+
+    pub(crate) fn record_sent(&self) { self.0.sent.fetch_add(1, Ordering::Relaxed); }
+    pub(crate) fn record_received(&self) { self.0.received.fetch_add(1, Ordering::Relaxed); }
+    pub(crate) fn record_unrecognized(&self) { self.0.unrecognized.fetch_add(1, Ordering::Relaxed); }
+    pub(crate) fn record_dropped(&self) { self.0.dropped.fetch_add(1, Ordering::Relaxed); }
+
+    /// Counters starting from a previous snapshot, e.g. for a restored tract.
+    pub(crate) fn resume(snapshot: &TractMetrics) -> Self {
+
+        Metrics(Arc::new(Counters {
+            sent: AtomicU64::new(snapshot.impulses_sent),
+            received: AtomicU64::new(snapshot.impulses_received),
+            unrecognized: AtomicU64::new(snapshot.unrecognized),
+            dropped: AtomicU64::new(snapshot.dropped)
+        }))
+    }
+
+    pub(crate) fn snapshot(&self, tract_name: &str) -> TractMetrics {
+
+        TractMetrics {
+            tract_name: tract_name.to_owned(),
+            impulses_sent: self.0.sent.load(Ordering::Relaxed),
+            impulses_received: self.0.received.load(Ordering::Relaxed),
+            unrecognized: self.0.unrecognized.load(Ordering::Relaxed),
+            dropped: self.0.dropped.load(Ordering::Relaxed)
+        }
+    }
+}
+
+
+#[cfg(feature = "prometheus")]
+impl TractMetrics {
+
+    /// Render these counters in the Prometheus text exposition format,
+    /// labeled with the tract name.
+    pub fn render_prometheus(&self) -> String {
+
+        render_prometheus(std::slice::from_ref(self))
+    }
+}
+
+/// Render the counters of several tracts as one Prometheus exposition,
+/// with a single `# HELP`/`# TYPE` header per metric.
+#[cfg(feature = "prometheus")]
+pub(crate) fn render_prometheus(tracts: &[TractMetrics]) -> String {
+
+    use std::fmt::Write;
+
+    let families: [(&str, &str, fn(&TractMetrics) -> u64); 4] = [
+        ("impulses_sent", "Impulses handed to the transport by a sensor.", |m| m.impulses_sent),
+        ("impulses_received", "Impulses received by a motor.", |m| m.impulses_received),
+        ("unrecognized", "Quanta or fiber IDs with no mapping.", |m| m.unrecognized),
+        ("dropped", "Impulses triggered but not sent, or datagrams not decoded.", |m| m.dropped)
+    ];
+
+    let mut out = String::new();
+    for (name, help, value) in families {
+        let _ = writeln!(out, "# HELP phantom_limb_{name}_total {help}");
+        let _ = writeln!(out, "# TYPE phantom_limb_{name}_total counter");
+        for metrics in tracts {
+            let tract = escape_label(&metrics.tract_name);
+            let _ = writeln!(out, "phantom_limb_{name}_total{{tract=\"{tract}\"}} {}", value(metrics));
+        }
+    }
+    out
+}
+
+#[cfg(feature = "prometheus")]
+fn escape_label(value: &str) -> String {
+
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+
+type Hook = Box<dyn FnOnce(&TractMetrics) + Send>;
+
+/// Runs a callback with the final metrics when its tract is dropped.
+#[derive(Default)]
+pub(crate) struct ShutdownHook(Mutex<Option<(String, Metrics, Hook)>>);
+
+impl ShutdownHook {
+
+    pub(crate) fn set(&mut self, tract_name: &str, metrics: &Metrics, hook: Hook) {
+
+        let hook = (tract_name.to_owned(), metrics.clone(), hook);
+        *self.0.get_mut().unwrap_or_else(|e| e.into_inner()) = Some(hook);
+    }
+}
+
+impl Drop for ShutdownHook {
+    fn drop(&mut self) {
+        let hook = self.0.get_mut().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some((tract_name, metrics, hook)) = hook {
+            hook(&metrics.snapshot(&tract_name))
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::{ Arc, Mutex };
+
+    use crate::transport::ChannelTransport;
+    use crate::types::Sensor;
+    use super::TractMetrics;
+
+    #[tokio::test]
+    async fn shutdown_hook_sees_the_final_metrics() {
+
+        let (a, _b) = ChannelTransport::pair(4);
+        let flushed: Arc<Mutex<Option<TractMetrics>>> = Arc::default();
+        let mut sensor: Sensor<char, _> = Sensor::in_process("flush", a).with_spectrum([('a', 1)]);
+        let hook = flushed.clone();
+        sensor.on_shutdown(move |metrics| *hook.lock().unwrap() = Some(metrics.clone()));
+
+        sensor.send_impulse(&'a').await.unwrap();
+        let _ = sensor.send_impulse(&'b').await;
+        assert!(flushed.lock().unwrap().is_none());
+
+        drop(sensor);
+        let metrics = flushed.lock().unwrap().take().unwrap();
+        assert_eq!(metrics.tract_name, "flush");
+        assert_eq!(metrics.impulses_sent, 1);
+        assert_eq!(metrics.unrecognized, 1);
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn renders_one_header_per_family() {
+
+        let tracts = [
+            TractMetrics { tract_name: "eye".into(), impulses_sent: 3, ..Default::default() },
+            TractMetrics { tract_name: "say \"hi\"".into(), dropped: 1, ..Default::default() }
+        ];
+        let text = super::render_prometheus(&tracts);
+
+        assert_eq!(text.matches("# TYPE phantom_limb_impulses_sent_total counter").count(), 1);
+        assert!(text.contains("phantom_limb_impulses_sent_total{tract=\"eye\"} 3\n"));
+        assert!(text.contains("phantom_limb_dropped_total{tract=\"say \\\"hi\\\"\"} 1\n"));
+    }
+}
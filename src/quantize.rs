@@ -0,0 +1,69 @@
+
+//! Converting raw data into the quanta a sensor's spectrum is keyed on.
+
+use std::hash::Hash;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use crate::types::Sensor;
+
+
+/// Converts a raw datum `T` into a quantum `Q`, or `None` to ignore it.
+/// Implemented for closures, so `|t: f64| Some(t.round() as i64)` qualifies.
+pub trait Quantize<T, Q> {
+    fn quantize(&self, datum: T) -> Option<Q>;
+}
+
+impl<T, Q, F: Fn(T) -> Option<Q>> Quantize<T, Q> for F {
+    fn quantize(&self, datum: T) -> Option<Q> { self(datum) }
+}
+
+
+impl<Q: Hash + Eq, T: Transport> Sensor<Q, T> {
+
+    /// Attempts to send a raw datum, converting it with `quantizer`
+    /// before looking it up in the spectrum.
+    /// A datum the quantizer rejects fails with `UnrecognizedTrigger`.
+    pub async fn send_raw<D>(
+        &self,
+        quantizer: &impl Quantize<D, Q>,
+        datum: D
+    ) -> Result<(), CommunicationError> {
+
+        match quantizer.quantize(datum) {
+            Some(quantum) => self.send_impulse(&quantum).await,
+            None => {
+                self.metrics.record_unrecognized();
+                let name = self.tract_name.clone();
+                Err(CommunicationError::UnrecognizedTrigger(name)) 
+            }
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::error::CommunicationError;
+    use crate::transport::{ Transport, ChannelTransport };
+    use crate::types::Sensor;
+
+    #[tokio::test]
+    async fn raw_data_is_quantized_before_lookup() {
+
+        let (a, b) = ChannelTransport::pair(4);
+        let sensor: Sensor<i64, _> = Sensor::in_process("raw", a).with_spectrum([(3, 7)]);
+        let round = |t: f64| t.is_finite().then(|| t.round() as i64);
+
+        sensor.send_raw(&round, 2.7).await.unwrap();
+        assert!(matches!(sensor.send_raw(&round, f64::NAN).await, Err(CommunicationError::UnrecognizedTrigger(_))));
+        assert!(sensor.send_raw(&round, 9.0).await.is_err());
+
+        let mut buffer = [0u8; 8];
+        let (n_bytes, _) = b.recv_from(&mut buffer).await.unwrap();
+        assert_eq!(crate::wire::decode_impulse(&buffer[..n_bytes]).unwrap(), 7);
+        assert_eq!(sensor.metrics().unrecognized, 2);
+    }
+}
@@ -0,0 +1,129 @@
+
+//! The reflex arc: a motor whose outputs are fed straight back 
+//! into the Complex through a sensor.
+
+use std::hash::Hash;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use crate::types::{ Sensor, Motor };
+use tokio_util::sync::CancellationToken;
+
+
+/// Converts a dispatched fiber ID and its behavior's result into 
+/// the quantum to send back, if any.
+pub type ReflexPolicy<Q, R> = Box<dyn Fn(u16, &R) -> Option<Q> + Send + Sync>;
+
+/// One sensor and one motor forming a closed loop, sharing one lifecycle:
+/// each tract holds a child of the reflex's cancellation token, so stopping 
+/// the reflex stops both tracts' background tasks.
+pub struct Reflex<Q: Hash + Eq, B: Fn(A) -> R, A, R, T: Transport = tokio::net::UdpSocket> {
+    sensor: Sensor<Q, T>,
+    motor: Motor<B, A, R, T>,
+    policy: ReflexPolicy<Q, R>,
+    cancel: CancellationToken
+}
+
+impl<Q, B, A, R, T> Reflex<Q, B, A, R, T> 
+where
+    Q: Hash + Eq,
+    B: Fn(A) -> R,
+    T: Transport
+{
+
+    /// Pair `sensor` and `motor`, sending back whatever `policy` makes of each result.
+    /// The reflex's token is the motor's, and both tracts are given children of it.
+    pub fn new(
+        mut sensor: Sensor<Q, T>,
+        mut motor: Motor<B, A, R, T>,
+        policy: impl Fn(u16, &R) -> Option<Q> + Send + Sync + 'static
+    ) -> Self {
+
+        let cancel = motor.cancellation_token().clone();
+        sensor.set_cancellation_token(cancel.clone());
+        motor.set_cancellation_token(cancel.clone());
+        Reflex { sensor, motor, policy: Box::new(policy), cancel }
+    }
+
+    /// The sensor results are sent back through.
+    pub fn sensor(&self) -> &Sensor<Q, T> { &self.sensor }
+
+    /// The motor that runs the behaviors.
+    pub fn motor(&self) -> &Motor<B, A, R, T> { &self.motor }
+
+    /// Receive impulses, run their behaviors and send the policy's quanta back,
+    /// until `stop` (or `close`) is called. Every result, and every error from 
+    /// dispatching or sending feedback, is passed to `sink`.
+    /// Only a socket or deserialization error on the motor ends the loop early.
+    pub async fn run<F, S>(&self, mut args: F, mut sink: S) -> Result<(), CommunicationError>
+    where
+        F: FnMut() -> A,
+        S: FnMut(Result<R, CommunicationError>)
+    {
+
+        let cancel = self.motor.cancellation_token();
+        loop {
+            let fiber = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => return Ok(()),
+                fiber = self.motor.recv_fiber() => fiber?
+            };
+
+            let result = self.motor.dispatch(fiber, args());
+            if let Some(quantum) = result.as_ref().ok().and_then(|r| (self.policy)(fiber, r)) {
+                if let Err(e) = self.sensor.send_impulse(&quantum).await { sink(Err(e)) }
+            }
+            sink(result)
+        }
+    }
+
+    /// Stop `run` and both tracts' background tasks.
+    pub fn stop(&self) { self.cancel.cancel() }
+
+    /// Stop the reflex and close both tracts (see `Sensor::close` and `Motor::close`).
+    /// Returns the fibers left over from a batch that were never dispatched.
+    pub async fn close(&self, notify: bool) -> Result<Vec<u16>, CommunicationError> {
+
+        let leftover = self.motor.close();
+        self.sensor.close(notify).await?;
+        Ok(leftover)
+    }
+
+    /// Separate the sensor and motor. Each keeps its own token, 
+    /// still cancelled by whatever token the motor was given before the reflex.
+    pub fn into_parts(self) -> (Sensor<Q, T>, Motor<B, A, R, T>) { (self.sensor, self.motor) }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+    use super::Reflex;
+
+    #[tokio::test]
+    async fn reflex_feeds_results_back_until_stopped() {
+
+        let (a, b) = ChannelTransport::pair(4);
+        let (c, d) = ChannelTransport::pair(4);
+        let stimulus: Sensor<char, _> = Sensor::in_process("knee", a).with_spectrum([('t', 1)]);
+        let motor: Motor<fn(()) -> u8, (), u8, _> = Motor::in_process("knee", b).with_fiber(1, |_| 5);
+        let sensor: Sensor<char, _> = Sensor::in_process("jerk", c).with_spectrum([('j', 9)]);
+        let cortex: Motor<fn(()) -> u8, (), u8, _> = Motor::in_process("jerk", d).with_fiber(9, |_| 9);
+        let reflex = Reflex::new(sensor, motor, |_, result| (*result == 5).then_some('j'));
+
+        let mut results = Vec::new();
+        let stimulate = async {
+            stimulus.send_impulse(&'t').await.unwrap();
+            assert_eq!(cortex.recv_impulse(()).await.unwrap(), 9);
+            reflex.stop();
+        };
+        let (ran, _) = tokio::join!(reflex.run(|| (), |result| results.push(result)), stimulate);
+
+        ran.unwrap();
+        assert!(matches!(results[..], [Ok(5)]));
+        assert!(reflex.motor().cancellation_token().is_cancelled());
+        assert!(reflex.sensor().cancellation_token().is_cancelled());
+    }
+}
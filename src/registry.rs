@@ -0,0 +1,87 @@
+
+use std::collections::HashMap;
+
+use crate::error::ConfigError;
+
+
+/// Named behaviors, so that motor wiring can be described by name
+/// (in snapshots or configuration) and resolved back to functions.
+/// `B` must be `Clone` so that one behavior can serve many fibers;
+/// function pointers and `Arc`-wrapped closures both qualify.
+pub struct BehaviorRegistry<B> {
+    behaviors: HashMap<String, B>
+}
+
+impl<B> Default for BehaviorRegistry<B> {
+    fn default() -> Self { BehaviorRegistry { behaviors: HashMap::new() } }
+}
+
+impl<B: Clone> BehaviorRegistry<B> {
+
+    /// Create an empty registry.
+    pub fn new() -> Self { Self::default() }
+
+    /// Register a behavior under a name.
+    /// NOTE: Overwrites existing name without checking.
+    pub fn register(&mut self, name: &str, behavior: B) {
+
+        self.behaviors.insert(name.to_owned(), behavior);
+    }
+
+    /// Chainable `register`.
+    pub fn with(mut self, name: &str, behavior: B) -> Self {
+
+        self.register(name, behavior);
+        self
+    }
+
+    /// Look up a behavior by name.
+    pub fn resolve(&self, name: &str) -> Result<B, ConfigError> {
+
+        self.behaviors.get(name)
+            .cloned()
+            .ok_or_else(|| ConfigError::UnknownBehavior(name.to_owned()))
+    }
+
+    /// Names of all registered behaviors.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+
+        self.behaviors.keys().map(String::as_str)
+    }
+}
+
+
+
+/// An enum of motor actions, each owning one fiber ID and one behavior,
+/// so the numbering shared with `Output.senders` lives in one place.
+/// With the `derive` feature, `#[derive(MotorFibers)]` implements this 
+/// for fieldless enums, numbering variants from 0 in declaration order.
+pub trait MotorFibers: Sized {
+
+    /// Argument passed to every behavior.
+    type Arg;
+
+    /// Value returned by every behavior.
+    type Ret;
+
+    /// Number of senders the corresponding Output needs.
+    const FIBER_COUNT: u16;
+
+    /// The fiber ID that triggers this action.
+    fn fiber_id(&self) -> u16;
+
+    /// The action triggered by a fiber ID, if any.
+    fn from_fiber(fiber: u16) -> Option<Self>;
+
+    /// The behavior to run for this action.
+    fn behavior(&self) -> fn(Self::Arg) -> Self::Ret;
+
+    /// Every action, in fiber ID order.
+    fn variants() -> Vec<Self>;
+
+    /// The complete wiring, for `Motor.fibers` or `MotorBuilder::fibers`.
+    fn fibers() -> HashMap<u16, fn(Self::Arg) -> Self::Ret> {
+
+        Self::variants().into_iter().map(|action| (action.fiber_id(), action.behavior())).collect()
+    }
+}
@@ -0,0 +1,75 @@
+
+use std::sync::Arc;
+use std::future::{ ready, Ready };
+use std::task::{ Context, Poll };
+use tower_service::Service;
+
+use crate::error::CommunicationError;
+use crate::types::Motor;
+
+
+/// Exposes motor dispatch as a `tower::Service<u16>`,
+/// so middleware (timeouts, rate limits, load shedding) can be layered on top.
+/// Each call runs the behavior for the given fiber ID.
+/// Since a request carries only the fiber ID, the behavior argument `A` 
+/// is produced by `args`, an arg source called once per request.
+pub struct MotorService<B: Fn(A) -> R, A, R, F: Fn() -> A> {
+    motor: Arc<Motor<B, A, R>>,
+    args: Arc<F>
+}
+
+impl<B: Fn(A) -> R, A, R, F: Fn() -> A> MotorService<B, A, R, F> {
+
+    /// Wrap a motor, using `args` to build the argument for each call.
+    pub fn new(motor: Arc<Motor<B, A, R>>, args: F) -> Self {
+
+        MotorService { motor, args: Arc::new(args) }
+    }
+
+    /// The wrapped motor.
+    pub fn motor(&self) -> &Arc<Motor<B, A, R>> { &self.motor }
+}
+
+impl<B: Fn(A) -> R, A, R, F: Fn() -> A> Clone for MotorService<B, A, R, F> {
+    fn clone(&self) -> Self {
+        MotorService { motor: self.motor.clone(), args: self.args.clone() }
+    }
+}
+
+impl<B: Fn(A) -> R, A, R, F: Fn() -> A> Service<u16> for MotorService<B, A, R, F> {
+    type Response = R;
+    type Error = CommunicationError;
+    type Future = Ready<Result<R, CommunicationError>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, fiber: u16) -> Self::Future {
+        ready(self.motor.dispatch(fiber, (self.args)()))
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use tower_service::Service;
+
+    use crate::error::CommunicationError;
+    use crate::types::FnMotor;
+    use super::MotorService;
+
+    #[tokio::test]
+    async fn calls_dispatch_the_fiber() {
+
+        let motor = FnMotor::new("service", "127.0.0.1:0".parse().unwrap()).await.unwrap()
+            .with_fiber(1, |x: u8| x + 1);
+        let mut service = MotorService::new(Arc::new(motor), || 41);
+
+        assert_eq!(service.call(1).await.unwrap(), 42);
+        assert!(matches!(service.call(2).await, Err(CommunicationError::UnrecognizedImpulse(2))));
+    }
+}
@@ -0,0 +1,301 @@
+
+//! Ways of resolving a quantum to the fiber ID it should trigger.
+
+use std::collections::HashMap;
+use std::hash::{ Hash, BuildHasher };
+use std::ops::Range;
+
+use crate::error::{ CommunicationError, ConfigError };
+use crate::transport::Transport;
+use crate::types::Sensor;
+
+
+/// Resolves a quantum to a fiber ID.
+pub trait FiberLookup<Q: ?Sized> {
+    fn fiber_for(&self, quantum: &Q) -> Option<u16>;
+}
+
+impl<Q: Hash + Eq, S: BuildHasher> FiberLookup<Q> for HashMap<Q, u16, S> {
+    fn fiber_for(&self, quantum: &Q) -> Option<u16> { self.get(quantum).copied() }
+}
+
+
+/// A quantum type with a fixed set of values, each owning one fiber ID,
+/// so the sensor's spectrum and the size of the Input come from one place.
+/// With the `derive` feature, `#[derive(Spectrum)]` implements this 
+/// for fieldless enums, numbering variants from 0 in declaration order.
+pub trait Spectrum: Hash + Eq + Sized {
+
+    /// Number of fibers the corresponding Input needs.
+    const FIBER_COUNT: u16;
+
+    /// The fiber ID this value fires.
+    fn fiber_id(&self) -> u16;
+
+    /// Every value, in fiber ID order.
+    fn variants() -> Vec<Self>;
+
+    /// The complete spectrum, for `Sensor::with_spectrum`.
+    fn spectrum() -> HashMap<Self, u16> {
+
+        Self::variants().into_iter().map(|q| { let fid = q.fiber_id(); (q, fid) }).collect()
+    }
+}
+
+
+/// Pair an ordered list of quanta with the fiber IDs of the Input,
+/// e.g. from `Input::fiber_ids`, so the first quantum fires the first fiber.
+/// Fails if the two lists differ in length.
+pub fn zip_spectrum<Q: Hash + Eq>(
+    quanta: impl IntoIterator<Item = Q>,
+    fiber_ids: impl IntoIterator<Item = u16>
+) -> Result<HashMap<Q, u16>, ConfigError> {
+
+    let quanta: Vec<Q> = quanta.into_iter().collect();
+    let fiber_ids: Vec<u16> = fiber_ids.into_iter().collect();
+    if quanta.len() != fiber_ids.len() {
+        return Err(ConfigError::LengthMismatch { quanta: quanta.len(), fibers: fiber_ids.len() })
+    }
+    Ok(quanta.into_iter().zip(fiber_ids).collect())
+}
+
+
+/// An immutable spectrum backed by a borrowed table sorted by quantum,
+/// resolved by binary search. Suitable for `const`/`static` tables,
+/// so a sensor can resolve quanta without any heap allocation.
+/// NOTE: The table MUST be sorted by `Q` with no duplicate quanta,
+/// otherwise lookups may miss; check with `is_sorted` if unsure.
+#[derive(Debug, Clone, Copy)]
+pub struct StaticSpectrum<'a, Q> {
+    table: &'a [(Q, u16)]
+}
+
+impl<'a, Q> StaticSpectrum<'a, Q> {
+
+    /// Wrap a table sorted by quantum.
+    pub const fn new(table: &'a [(Q, u16)]) -> Self { StaticSpectrum { table } }
+
+    /// Number of receptors in the table.
+    pub const fn len(&self) -> usize { self.table.len() }
+
+    /// Whether the table has no receptors.
+    pub const fn is_empty(&self) -> bool { self.table.is_empty() }
+
+    /// The receptors, in table order.
+    pub fn iter(&self) -> impl Iterator<Item = &'a (Q, u16)> { self.table.iter() }
+}
+
+impl<'a, Q: Ord> StaticSpectrum<'a, Q> {
+
+    /// Whether the table is strictly sorted, as lookups require.
+    pub fn is_sorted(&self) -> bool {
+
+        self.table.windows(2).all(|pair| pair[0].0 < pair[1].0)
+    }
+
+    /// The fiber ID mapped to `quantum`.
+    pub fn get(&self, quantum: &Q) -> Option<u16> {
+
+        self.table.binary_search_by(|(key, _)| key.cmp(quantum))
+            .ok()
+            .map(|index| self.table[index].1)
+    }
+}
+
+impl<'a, Q: Ord> FiberLookup<Q> for StaticSpectrum<'a, Q> {
+    fn fiber_for(&self, quantum: &Q) -> Option<u16> { self.get(quantum) }
+}
+
+/// A spectrum for continuous readings, binning values into 
+/// non-overlapping half-open ranges (e.g. `0.0..0.2` fires fiber 4).
+/// Values outside every range have no receptor.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RangeSpectrum {
+    /// Sorted by the start of each range.
+    bins: Vec<(Range<f64>, u16)>
+}
+
+impl RangeSpectrum {
+
+    /// Create a spectrum with no receptors.
+    pub fn new() -> Self { Self::default() }
+
+    /// Split `range` into `count` equal bins firing `first_fiber`, 
+    /// `first_fiber + 1`, and so on, from low to high.
+    pub fn uniform(range: Range<f64>, count: u16, first_fiber: u16) -> Result<Self, ConfigError> {
+
+        let mut spectrum = RangeSpectrum::new();
+        let width = (range.end - range.start) / count as f64;
+        for i in 0..count {
+            let start = range.start + width * i as f64;
+            let end = if i + 1 == count { range.end } else { start + width };
+            spectrum.add_receptor(start..end, first_fiber.wrapping_add(i))?;
+        }
+        Ok(spectrum)
+    }
+
+    /// Map values in `range` to a fiber ID.
+    /// Fails if the range is empty, not finite, or overlaps another.
+    pub fn add_receptor(&mut self, range: Range<f64>, fid: u16) -> Result<(), ConfigError> {
+
+        let index = self.bins.partition_point(|(bin, _)| bin.start < range.start);
+        let valid = range.start.is_finite() && range.end.is_finite() && range.start < range.end
+            && self.bins.get(index).map_or(true, |(next, _)| range.end <= next.start)
+            && index.checked_sub(1).map_or(true, |i| self.bins[i].0.end <= range.start);
+
+        if !valid { return Err(ConfigError::InvalidRange(range)) }
+        self.bins.insert(index, (range, fid));
+        Ok(())
+    }
+
+    /// Removes the receptor whose range contains `value`, 
+    /// returning its range and fiber ID.
+    pub fn remove_receptor(&mut self, value: f64) -> Option<(Range<f64>, u16)> {
+
+        let index = self.index_of(value)?;
+        Some(self.bins.remove(index))
+    }
+
+    fn index_of(&self, value: f64) -> Option<usize> {
+
+        let index = self.bins.partition_point(|(bin, _)| bin.start <= value).checked_sub(1)?;
+        self.bins[index].0.contains(&value).then_some(index)
+    }
+
+    /// The fiber ID for the bin containing `value`.
+    pub fn get(&self, value: f64) -> Option<u16> {
+
+        self.index_of(value).map(|index| self.bins[index].1)
+    }
+
+    /// Number of receptors.
+    pub fn len(&self) -> usize { self.bins.len() }
+
+    /// Whether there are no receptors.
+    pub fn is_empty(&self) -> bool { self.bins.is_empty() }
+
+    /// The receptors, from the lowest range to the highest.
+    pub fn iter(&self) -> impl Iterator<Item = &(Range<f64>, u16)> { self.bins.iter() }
+}
+
+impl FiberLookup<f64> for RangeSpectrum {
+    fn fiber_for(&self, quantum: &f64) -> Option<u16> { self.get(*quantum) }
+}
+
+
+impl<Q: Hash + Eq, T: Transport> Sensor<Q, T> {
+
+    /// Replace the spectrum used to bin continuous readings for `send_analog`.
+    pub fn set_range_spectrum(&mut self, ranges: RangeSpectrum) {
+
+        self.ranges = ranges;
+    }
+
+    /// The spectrum used to bin continuous readings.
+    pub fn range_spectrum(&self) -> &RangeSpectrum { &self.ranges }
+
+    /// Attempts to send a continuous reading, firing the fiber 
+    /// for the range it falls in (see `set_range_spectrum`).
+    pub async fn send_analog(&self, value: f64) -> Result<(), CommunicationError> {
+
+        if let Some(fid) = self.ranges.get(value) {
+            self.transmit(fid).await
+        } else {
+            self.metrics.record_unrecognized();
+            let name = self.tract_name.clone();
+            Err(CommunicationError::UnrecognizedTrigger(name)) 
+        }
+    }
+
+    /// Attempts to send a sensory datum, resolving it through `lookup`
+    /// (e.g. a `StaticSpectrum`) instead of the sensor's own spectrum.
+    pub async fn send_via(
+        &self,
+        lookup: &impl FiberLookup<Q>,
+        quantum: &Q
+    ) -> Result<(), CommunicationError> {
+
+        if let Some(fid) = lookup.fiber_for(quantum) {
+            self.transmit(fid).await
+        } else {
+            self.metrics.record_unrecognized();
+            let name = self.tract_name.clone();
+            Err(CommunicationError::UnrecognizedTrigger(name)) 
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::transport::{ Transport, ChannelTransport };
+    use crate::types::Sensor;
+    use super::*;
+
+    const COLORS: StaticSpectrum<'static, &str> = StaticSpectrum::new(&[
+        ("blue", 2),
+        ("green", 1),
+        ("red", 0)
+    ]);
+
+    #[test]
+    fn const_table_resolves_by_binary_search() {
+
+        assert!(COLORS.is_sorted());
+        assert_eq!(COLORS.len(), 3);
+        assert_eq!(COLORS.get(&"green"), Some(1));
+        assert_eq!(COLORS.get(&"violet"), None);
+        assert!(!StaticSpectrum::new(&[(2, 0), (1, 1)]).is_sorted());
+    }
+
+    #[tokio::test]
+    async fn sends_through_a_static_table() {
+
+        let (a, b) = ChannelTransport::pair(4);
+        let sensor: Sensor<&str, _> = Sensor::in_process("colors", a);
+        sensor.send_via(&COLORS, &"red").await.unwrap();
+        assert!(sensor.send_via(&COLORS, &"violet").await.is_err());
+
+        let mut buffer = [0u8; 8];
+        let (n_bytes, _) = b.recv_from(&mut buffer).await.unwrap();
+        assert_eq!(crate::wire::decode_impulse(&buffer[..n_bytes]).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn analog_readings_fire_their_bin() {
+
+        let mut ranges = RangeSpectrum::uniform(0.0..1.0, 4, 10).unwrap();
+        assert!(ranges.add_receptor(0.9..1.5, 20).is_err());
+        assert!(ranges.add_receptor(2.0..f64::INFINITY, 20).is_err());
+        ranges.add_receptor(1.0..2.0, 20).unwrap();
+        assert_eq!(ranges.get(0.0), Some(10));
+        assert_eq!(ranges.get(0.5), Some(12));
+        assert_eq!(ranges.get(1.0), Some(20));
+        assert_eq!(ranges.get(2.0), None);
+
+        let (a, b) = ChannelTransport::pair(4);
+        let mut sensor: Sensor<u8, _> = Sensor::in_process("analog", a);
+        sensor.set_range_spectrum(ranges);
+        sensor.send_analog(0.8).await.unwrap();
+        assert!(sensor.send_analog(-1.0).await.is_err());
+
+        let mut buffer = [0u8; 8];
+        let (n_bytes, _) = b.recv_from(&mut buffer).await.unwrap();
+        assert_eq!(crate::wire::decode_impulse(&buffer[..n_bytes]).unwrap(), 13);
+        assert_eq!(sensor.metrics().unrecognized, 1);
+    }
+
+    #[test]
+    fn zipped_spectra_pair_quanta_in_order() {
+
+        let spectrum = zip_spectrum(['a', 'b', 'c'], [7, 8, 9]).unwrap();
+        let sensor = Sensor::new_with_sink("zip", |_| ()).with_spectrum(spectrum);
+        assert_eq!(sensor.spectrum.get(&'c'), Some(&9));
+        assert!(matches!(
+            zip_spectrum(['a', 'b'], [1]),
+            Err(ConfigError::LengthMismatch { quanta: 2, fibers: 1 })
+        ));
+    }
+}
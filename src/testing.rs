@@ -0,0 +1,110 @@
+//! Batteries-included assertions for testing signal topologies.
+//! The harness links its tracts with an in-process `ChannelTransport`,
+//! so nothing touches the network and every wait is measured by tokio's
+//! clock: under `tokio::time::pause` (or `#[tokio::test(start_paused = true)]`)
+//! timeouts, debounce windows and refractory periods elapse instantly.
+
+use std::hash::Hash;
+use std::time::Duration;
+use tokio::time::timeout;
+
+use crate::transport::ChannelTransport;
+use crate::types::{ Sensor, Motor };
+
+
+/// How long `expect_impulse` waits by default.
+pub const DEFAULT_EXPECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Datagrams the harness link holds before sends wait.
+const HARNESS_CAPACITY: usize = 1024;
+
+/// A `Sensor` wired in-process to a `Motor` that records what arrives.
+/// The sensor is public so receptors (and modes) can be configured
+/// before driving it; the assertions panic, like `assert!`.
+pub struct TestHarness<Q: Hash + Eq> {
+    pub sensor: Sensor<Q, ChannelTransport>,
+    pub motor: Motor<fn(()), (), (), ChannelTransport>,
+
+    /// Time allowed for each impulse to arrive in `expect_impulse`.
+    pub timeout: Duration
+}
+
+impl<Q: Hash + Eq> TestHarness<Q> {
+
+    /// Link a sensor and motor in-process.
+    pub fn new(tract_name: &str) -> Self {
+
+        let (sensor_end, motor_end) = ChannelTransport::pair(HARNESS_CAPACITY);
+        let sensor = Sensor::in_process(tract_name, sensor_end);
+        let motor = Motor::in_process(tract_name, motor_end);
+
+        TestHarness { sensor, motor, timeout: DEFAULT_EXPECT_TIMEOUT }
+    }
+
+    /// Panics unless the next impulse to arrive is `fiber`.
+    pub async fn expect_impulse(&self, fiber: u16) {
+
+        match timeout(self.timeout, self.motor.recv_fiber()).await {
+            Ok(Ok(received)) => assert_eq!(
+                received, fiber,
+                "Expected impulse on fiber {}, received fiber {}", fiber, received
+            ),
+            Ok(Err(e)) => panic!("Expected impulse on fiber {}, failed: {}", fiber, e),
+            Err(_) => panic!(
+                "Expected impulse on fiber {}, none arrived within {:?}",
+                fiber,
+                self.timeout
+            )
+        }
+    }
+
+    /// Panics if any impulse arrives within `duration`.
+    pub async fn expect_no_impulse_for(&self, duration: Duration) {
+
+        if let Ok(received) = timeout(duration, self.motor.recv_fiber()).await {
+            panic!("Expected no impulse for {:?}, received {:?}", duration, received)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+    use super::TestHarness;
+
+    #[tokio::test(start_paused = true)]
+    async fn edge_triggered_sensor_fires_once_per_change() {
+
+        let mut harness = TestHarness::new("edge");
+        harness.sensor.add_receptor("touch", 1);
+        harness.sensor.add_receptor("release", 2);
+        harness.sensor.set_edge_triggered(true);
+
+        for _ in 0..3 { harness.sensor.send_impulse(&"touch").await.unwrap() }
+        harness.expect_impulse(1).await;
+        harness.expect_no_impulse_for(Duration::from_secs(10)).await;
+
+        harness.sensor.send_impulse(&"release").await.unwrap();
+        harness.sensor.send_impulse(&"touch").await.unwrap();
+        harness.expect_impulse(2).await;
+        harness.expect_impulse(1).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn debounce_window_follows_paused_time() {
+
+        let mut harness = TestHarness::new("debounce");
+        harness.sensor.add_receptor('a', 1);
+        harness.sensor.set_debounce(Some(Duration::from_millis(100)));
+
+        harness.sensor.send_impulse(&'a').await.unwrap();
+        harness.sensor.send_impulse(&'a').await.unwrap();
+        harness.expect_impulse(1).await;
+        harness.expect_no_impulse_for(Duration::from_millis(500)).await;
+
+        harness.sensor.send_impulse(&'a').await.unwrap();
+        harness.expect_impulse(1).await;
+    }
+}
@@ -0,0 +1,148 @@
+
+//! In-process tracts over tokio channels, for deterministic tests 
+//! without binding real sockets. `ChannelTransport::pair` links two ends;
+//! give one to a `Sensor` and the other to a `Motor`. Both directions 
+//! work, so acks and unrecognized echoes are delivered too.
+
+use std::io;
+use std::hash::Hash;
+use std::net::{ SocketAddr, IpAddr, Ipv4Addr };
+use tokio::sync::{ mpsc, Mutex };
+
+use crate::types::{ Sensor, Motor };
+use super::Transport;
+
+
+fn unspecified() -> SocketAddr { SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0) }
+
+fn disconnected() -> io::Error { io::Error::from(io::ErrorKind::BrokenPipe) }
+
+/// One end of an in-process link carrying whole datagrams.
+/// Sends wait while the other end's queue is full.
+pub struct ChannelTransport {
+    sender: mpsc::Sender<Vec<u8>>,
+    receiver: Mutex<mpsc::Receiver<Vec<u8>>>
+}
+
+impl ChannelTransport {
+
+    /// Two linked ends, each queueing up to `capacity` datagrams.
+    pub fn pair(capacity: usize) -> (Self, Self) {
+
+        let (a_tx, a_rx) = mpsc::channel(capacity.max(1));
+        let (b_tx, b_rx) = mpsc::channel(capacity.max(1));
+        (
+            ChannelTransport { sender: a_tx, receiver: Mutex::new(b_rx) },
+            ChannelTransport { sender: b_tx, receiver: Mutex::new(a_rx) }
+        )
+    }
+}
+
+impl Transport for ChannelTransport {
+
+    async fn send(&self, datagram: &[u8]) -> io::Result<usize> {
+
+        self.sender.send(datagram.to_vec()).await.map_err(|_| disconnected())?;
+        Ok(datagram.len())
+    }
+
+    async fn send_to(&self, datagram: &[u8], _target: SocketAddr) -> io::Result<usize> {
+
+        self.send(datagram).await
+    }
+
+    async fn recv_from(&self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+
+        let datagram = self.receiver.lock().await.recv().await.ok_or_else(disconnected)?;
+        if datagram.len() > buffer.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "datagram larger than buffer"))
+        }
+        buffer[..datagram.len()].copy_from_slice(&datagram);
+        Ok((datagram.len(), unspecified()))
+    }
+
+    fn try_recv_from(&self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+
+        let would_block = || io::Error::from(io::ErrorKind::WouldBlock);
+        let mut receiver = self.receiver.try_lock().map_err(|_| would_block())?;
+        let datagram = receiver.try_recv().map_err(|e| match e {
+            mpsc::error::TryRecvError::Empty => would_block(),
+            mpsc::error::TryRecvError::Disconnected => disconnected()
+        })?;
+
+        if datagram.len() > buffer.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "datagram larger than buffer"))
+        }
+        buffer[..datagram.len()].copy_from_slice(&datagram);
+        Ok((datagram.len(), unspecified()))
+    }
+
+    fn try_send_to(&self, datagram: &[u8], _target: SocketAddr) -> io::Result<usize> {
+
+        self.sender.try_send(datagram.to_vec()).map_err(|_| io::Error::from(io::ErrorKind::WouldBlock))?;
+        Ok(datagram.len())
+    }
+
+    async fn connect(&self, _peer: SocketAddr) -> io::Result<()> { Ok(()) }
+}
+
+
+impl<Q: Hash + Eq> Sensor<Q, ChannelTransport> {
+
+    /// Create a sensor that sends through one end of a `ChannelTransport::pair`.
+    pub fn in_process(tract_name: &str, transport: ChannelTransport) -> Self {
+
+        Sensor::with_transport(tract_name, unspecified(), transport)
+    }
+}
+
+impl<B: Fn(A) -> R, A, R> Motor<B, A, R, ChannelTransport> {
+
+    /// Create a motor that receives from one end of a `ChannelTransport::pair`.
+    pub fn in_process(tract_name: &str, transport: ChannelTransport) -> Self {
+
+        Motor::with_transport(tract_name, unspecified(), transport)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::io;
+
+    use crate::types::{ Sensor, Motor };
+    use crate::wire::ImpulseFrame;
+    use super::{ Transport, ChannelTransport };
+
+    #[tokio::test]
+    async fn in_process_tracts_carry_impulses_and_feedback() {
+
+        let (a, b) = ChannelTransport::pair(4);
+        let sensor: Sensor<char, _> = Sensor::in_process("link", a).with_spectrum([('a', 1), ('z', 9)]);
+        let mut motor: Motor<fn(()) -> u8, (), u8, _> = Motor::in_process("link", b).with_fiber(1, |_| 7);
+        motor.set_echo_unrecognized(true);
+
+        sensor.send_impulse(&'a').await.unwrap();
+        assert_eq!(motor.recv_impulse(()).await.unwrap(), 7);
+
+        sensor.send_impulse(&'z').await.unwrap();
+        assert!(motor.recv_impulse(()).await.is_err());
+        let mut buffer = [0u8; 64];
+        let (n, _) = sensor.socket.recv_from(&mut buffer).await.unwrap();
+        assert!(matches!(ImpulseFrame::decode(&buffer[..n]), Ok(ImpulseFrame::Unrecognized(9))));
+    }
+
+    #[tokio::test]
+    async fn dropped_ends_disconnect() {
+
+        let (a, b) = ChannelTransport::pair(1);
+        a.send(&[1, 2, 3]).await.unwrap();
+        assert_eq!(b.try_recv_from(&mut [0u8; 1]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(b.try_recv_from(&mut [0u8; 1]).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+
+        drop(b);
+        assert_eq!(a.send(&[1]).await.unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    }
+}
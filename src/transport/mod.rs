@@ -0,0 +1,166 @@
+
+//! The medium that carries impulse datagrams for a tract.
+//! `UdpSocket` is the default; other transports let the same 
+//! `Sensor` and `Motor` logic run over a different medium.
+//! Only `send` is required; a transport used by a `Motor` 
+//! should also implement `recv_from`.
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use tokio::net::UdpSocket;
+
+pub mod tcp;
+pub use tcp::TcpTransport;
+pub mod channel;
+pub use channel::ChannelTransport;
+#[cfg(feature = "quic")]
+pub mod quic;
+#[cfg(feature = "quic")]
+pub use quic::QuicTransport;
+#[cfg(all(unix, feature = "unix"))]
+pub mod unix;
+#[cfg(feature = "aead")]
+pub mod aead;
+#[cfg(feature = "aead")]
+pub use aead::AeadTransport;
+
+
+/// Carries whole datagrams, one impulse (or frame) each.
+pub trait Transport {
+
+    /// Send a datagram to the transport's peer.
+    fn send(&self, datagram: &[u8]) -> impl Future<Output = io::Result<usize>> + Send;
+
+    /// Send a datagram to a specific address.
+    /// Transports without addressing return `Unsupported`.
+    fn send_to(
+        &self, 
+        _datagram: &[u8], 
+        _target: SocketAddr
+    ) -> impl Future<Output = io::Result<usize>> + Send {
+
+        std::future::ready(Err(io::Error::from(io::ErrorKind::Unsupported)))
+    }
+
+    /// Receive a datagram and the address it came from.
+    /// Transports that cannot receive return `Unsupported`.
+    fn recv_from(
+        &self, 
+        _buffer: &mut [u8]
+    ) -> impl Future<Output = io::Result<(usize, SocketAddr)>> + Send {
+
+        std::future::ready(Err(io::Error::from(io::ErrorKind::Unsupported)))
+    }
+
+    /// Receive a datagram only if one is already queued,
+    /// returning `WouldBlock` otherwise.
+    fn try_recv_from(&self, _buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+
+        Err(io::Error::from(io::ErrorKind::WouldBlock))
+    }
+
+    /// Send a datagram to `target` only if it can be done without waiting,
+    /// for best-effort feedback such as acks and diagnostics.
+    fn try_send_to(&self, _datagram: &[u8], _target: SocketAddr) -> io::Result<usize> {
+
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    /// (Re)connect the transport to `peer`.
+    /// Transports without addressing return `Unsupported`.
+    fn connect(&self, _peer: SocketAddr) -> impl Future<Output = io::Result<()>> + Send {
+
+        std::future::ready(Err(io::Error::from(io::ErrorKind::Unsupported)))
+    }
+}
+
+impl Transport for UdpSocket {
+
+    async fn send(&self, datagram: &[u8]) -> io::Result<usize> {
+        UdpSocket::send(self, datagram).await
+    }
+
+    async fn send_to(&self, datagram: &[u8], target: SocketAddr) -> io::Result<usize> {
+        UdpSocket::send_to(self, datagram, target).await
+    }
+
+    async fn recv_from(&self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        UdpSocket::recv_from(self, buffer).await
+    }
+
+    fn try_recv_from(&self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        UdpSocket::try_recv_from(self, buffer)
+    }
+
+    fn try_send_to(&self, datagram: &[u8], target: SocketAddr) -> io::Result<usize> {
+        UdpSocket::try_send_to(self, datagram, target)
+    }
+
+    async fn connect(&self, peer: SocketAddr) -> io::Result<()> {
+        UdpSocket::connect(self, peer).await
+    }
+}
+
+
+/// Routes datagrams to a callback instead of a socket,
+/// for embedding in a host that has its own message bus.
+pub struct CallbackTransport {
+    sink: Box<dyn Fn(&[u8]) + Send + Sync>
+}
+
+impl CallbackTransport {
+
+    /// Every datagram sent will be passed to `sink`.
+    pub fn new(sink: impl Fn(&[u8]) + Send + Sync + 'static) -> Self {
+
+        CallbackTransport { sink: Box::new(sink) }
+    }
+}
+
+impl Transport for CallbackTransport {
+
+    async fn send(&self, datagram: &[u8]) -> io::Result<usize> {
+        (self.sink)(datagram);
+        Ok(datagram.len())
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use tokio::net::UdpSocket;
+
+    use crate::error::CommunicationError;
+    use crate::types::{ Sensor, Motor };
+    use super::CallbackTransport;
+
+    #[tokio::test]
+    async fn motors_receive_over_any_transport() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let socket = UdpSocket::bind(any).await.unwrap();
+        let address = socket.local_addr().unwrap();
+        let motor: Motor<fn(()) -> u8, (), u8, _> = Motor::with_transport("generic", address, socket)
+            .with_fiber(1, |_| 7);
+        let sensor: Sensor<u8> = Sensor::connected("generic", any, address).await.unwrap()
+            .with_spectrum([(0, 1)]);
+
+        sensor.send_impulse(&0).await.unwrap();
+        assert_eq!(motor.recv_impulse(()).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn send_only_transports_cannot_receive() {
+
+        let callback = CallbackTransport::new(|_| ());
+        let motor: Motor<fn(()), (), (), _> = Motor::with_transport("mute", "127.0.0.1:0".parse().unwrap(), callback);
+
+        match motor.recv_impulse(()).await {
+            Err(CommunicationError::SocketFailed(e)) => assert_eq!(e.kind(), std::io::ErrorKind::Unsupported),
+            other => panic!("unexpected {other:?}")
+        }
+    }
+}
@@ -0,0 +1,128 @@
+
+//! Encrypted tracts over QUIC, using unreliable QUIC datagrams
+//! so impulses keep UDP semantics (no head-of-line blocking) while gaining
+//! encryption and congestion control over one multiplexed connection.
+//! Endpoint setup (certificates, server and client configs) is left to
+//! the caller; see the `quinn` documentation.
+//! NOTE: The peer must enable datagram support in its transport config.
+
+use std::io;
+use std::hash::Hash;
+use std::net::SocketAddr;
+use bytes::Bytes;
+use quinn::{ Connection, Endpoint };
+
+use crate::error::BuildError;
+use crate::types::{ Sensor, Motor };
+use super::Transport;
+
+
+/// A QUIC connection carrying one impulse per datagram.
+pub struct QuicTransport {
+    connection: Connection
+}
+
+impl QuicTransport {
+
+    /// Wrap an established connection.
+    pub fn new(connection: Connection) -> Self { QuicTransport { connection } }
+
+    /// The underlying connection, e.g. to inspect its statistics.
+    pub fn connection(&self) -> &Connection { &self.connection }
+}
+
+impl Transport for QuicTransport {
+
+    async fn send(&self, datagram: &[u8]) -> io::Result<usize> {
+
+        self.connection.send_datagram(Bytes::copy_from_slice(datagram))
+            .map_err(io::Error::other)?;
+        Ok(datagram.len())
+    }
+
+    async fn recv_from(&self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+
+        let datagram = self.connection.read_datagram().await.map_err(io::Error::other)?;
+        if datagram.len() > buffer.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "datagram larger than buffer"))
+        }
+        buffer[..datagram.len()].copy_from_slice(&datagram);
+        Ok((datagram.len(), self.connection.remote_address()))
+    }
+}
+
+
+impl<Q: Hash + Eq> Sensor<Q, QuicTransport> {
+
+    /// Connect a sensor through `endpoint` to a motor accepting with 
+    /// `Motor::accept_quic`. `server_name` must match the motor's certificate.
+    pub async fn connect_quic(
+        tract_name: &str, 
+        endpoint: &Endpoint, 
+        peer: SocketAddr, 
+        server_name: &str
+    ) -> Result<Self, BuildError> {
+
+        let connection = endpoint.connect(peer, server_name).map_err(io::Error::other)?
+            .await.map_err(io::Error::other)?;
+        let mut sensor = Sensor::with_transport(tract_name, peer, QuicTransport::new(connection));
+        sensor.peer = Some(peer);
+        Ok(sensor)
+    }
+}
+
+impl<B: Fn(A) -> R, A, R> Motor<B, A, R, QuicTransport> {
+
+    /// Accept the next incoming connection on `endpoint`.
+    /// The motor's address is the endpoint's local address.
+    pub async fn accept_quic(
+        tract_name: &str, 
+        endpoint: &Endpoint
+    ) -> Result<Self, BuildError> {
+
+        let closed = || io::Error::new(io::ErrorKind::NotConnected, "endpoint closed");
+        let incoming = endpoint.accept().await.ok_or_else(closed)?;
+        let connection = incoming.await.map_err(io::Error::other)?;
+        let address = endpoint.local_addr()?;
+        Ok(Motor::with_transport(tract_name, address, QuicTransport::new(connection)))
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use quinn::{ ClientConfig, Endpoint, ServerConfig };
+    use quinn::rustls::RootCertStore;
+    use quinn::rustls::pki_types::PrivatePkcs8KeyDer;
+
+    use crate::types::{ Sensor, Motor };
+
+    #[tokio::test]
+    async fn quic_tracts_deliver_impulses() {
+
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let cert = certified.cert.der().clone();
+        let key = PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der());
+
+        let server_config = ServerConfig::with_single_cert(vec![cert.clone()], key.into()).unwrap();
+        let server = Endpoint::server(server_config, "127.0.0.1:0".parse().unwrap()).unwrap();
+        let mut roots = RootCertStore::empty();
+        roots.add(cert).unwrap();
+        let mut client = Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        client.set_default_client_config(ClientConfig::with_root_certificates(Arc::new(roots)).unwrap());
+
+        let peer = server.local_addr().unwrap();
+        let (sensor, motor) = tokio::join!(
+            Sensor::<u8, _>::connect_quic("quic", &client, peer, "localhost"),
+            Motor::<fn(()) -> u8, (), u8, _>::accept_quic("quic", &server)
+        );
+        let sensor = sensor.unwrap().with_spectrum([(0, 1)]);
+        let motor = motor.unwrap().with_fiber(1, |_| 7);
+
+        sensor.send_impulse(&0).await.unwrap();
+        assert_eq!(motor.recv_impulse(()).await.unwrap(), 7);
+    }
+}
@@ -0,0 +1,249 @@
+
+//! Lossless, ordered tracts over TCP.
+//! Each datagram is framed with a 4-byte big-endian length prefix.
+//! NOTE: Best-effort feedback (acks, unrecognized echoes) is not sent 
+//! over TCP, and a broken connection cannot be re-established in place;
+//! build a new tract instead.
+
+use std::io;
+use std::hash::Hash;
+use std::net::SocketAddr;
+use tokio::io::{ AsyncReadExt, AsyncWriteExt };
+use tokio::net::{ TcpListener, TcpStream };
+use tokio::net::tcp::{ OwnedReadHalf, OwnedWriteHalf };
+use tokio::sync::{ mpsc, oneshot, Mutex };
+use tokio::task::AbortHandle;
+
+use crate::error::BuildError;
+use crate::types::{ Sensor, Motor };
+use super::Transport;
+
+
+/// Frames larger than this are rejected rather than allocated for.
+pub const MAX_TCP_FRAME: usize = 1 << 16;
+
+/// Frames queued in each direction between the transport and its stream.
+const TCP_QUEUE_LEN: usize = 64;
+
+type Outgoing = (Vec<u8>, oneshot::Sender<io::Result<()>>);
+
+fn disconnected() -> io::Error { io::Error::from(io::ErrorKind::BrokenPipe) }
+
+/// A connected TCP stream carrying length-prefixed datagrams.
+/// The stream is read and written by background tasks that handle
+/// whole frames, so a cancelled `send` or `recv_from` never leaves 
+/// the stream part-way through a frame.
+pub struct TcpTransport {
+    incoming: Mutex<mpsc::Receiver<io::Result<Vec<u8>>>>,
+    outgoing: mpsc::Sender<Outgoing>,
+    reader: AbortHandle,
+    peer: SocketAddr
+}
+
+impl TcpTransport {
+
+    /// Wrap an established stream. Must be called within a tokio runtime.
+    pub fn from_stream(stream: TcpStream) -> io::Result<Self> {
+
+        stream.set_nodelay(true)?;
+        let peer = stream.peer_addr()?;
+        let (reader, writer) = stream.into_split();
+
+        let (frames, incoming) = mpsc::channel(TCP_QUEUE_LEN);
+        let (outgoing, queued) = mpsc::channel(TCP_QUEUE_LEN);
+        let reader = tokio::spawn(read_frames(reader, frames)).abort_handle();
+        tokio::spawn(write_frames(writer, queued));
+
+        Ok(TcpTransport { incoming: Mutex::new(incoming), outgoing, reader, peer })
+    }
+
+    /// Open a stream to `peer`.
+    pub async fn connect(peer: SocketAddr) -> io::Result<Self> {
+
+        Self::from_stream(TcpStream::connect(peer).await?)
+    }
+
+    /// Address of the other end of the stream.
+    pub fn peer_addr(&self) -> SocketAddr { self.peer }
+}
+
+impl Drop for TcpTransport {
+    fn drop(&mut self) {
+        // The writer ends by itself once queued frames are written.
+        self.reader.abort();
+    }
+}
+
+/// Read one whole frame from the stream.
+async fn read_frame(reader: &mut OwnedReadHalf) -> io::Result<Vec<u8>> {
+
+    let len = reader.read_u32().await? as usize;
+    if len > MAX_TCP_FRAME {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too large"))
+    }
+    let mut frame = vec![0u8; len];
+    reader.read_exact(&mut frame).await?;
+    Ok(frame)
+}
+
+/// Forward frames until the stream fails or the transport is dropped.
+async fn read_frames(mut reader: OwnedReadHalf, frames: mpsc::Sender<io::Result<Vec<u8>>>) {
+
+    loop {
+        let frame = read_frame(&mut reader).await;
+        let failed = frame.is_err();
+        if frames.send(frame).await.is_err() || failed { return }
+    }
+}
+
+/// Write queued frames in order, reporting each result to its sender.
+async fn write_frames(mut writer: OwnedWriteHalf, mut queued: mpsc::Receiver<Outgoing>) {
+
+    while let Some((frame, written)) = queued.recv().await {
+        let _ = written.send(writer.write_all(&frame).await);
+    }
+}
+
+/// Copy a received frame into `buffer`. An oversized frame is discarded whole,
+/// so the stream stays aligned on frame boundaries.
+fn deliver(frame: Vec<u8>, buffer: &mut [u8], peer: SocketAddr) -> io::Result<(usize, SocketAddr)> {
+
+    if frame.len() > buffer.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame larger than buffer"))
+    }
+    buffer[..frame.len()].copy_from_slice(&frame);
+    Ok((frame.len(), peer))
+}
+
+impl Transport for TcpTransport {
+
+    async fn send(&self, datagram: &[u8]) -> io::Result<usize> {
+
+        if datagram.len() > MAX_TCP_FRAME {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "frame too large"))
+        }
+
+        let mut frame = Vec::with_capacity(4 + datagram.len());
+        frame.extend_from_slice(&(datagram.len() as u32).to_be_bytes());
+        frame.extend_from_slice(datagram);
+
+        let (written, result) = oneshot::channel();
+        self.outgoing.send((frame, written)).await.map_err(|_| disconnected())?;
+        result.await.map_err(|_| disconnected())??;
+        Ok(datagram.len())
+    }
+
+    async fn recv_from(&self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+
+        let frame = self.incoming.lock().await.recv().await.ok_or_else(disconnected)??;
+        deliver(frame, buffer, self.peer)
+    }
+
+    fn try_recv_from(&self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+
+        let would_block = || io::Error::from(io::ErrorKind::WouldBlock);
+        let mut incoming = self.incoming.try_lock().map_err(|_| would_block())?;
+        let frame = incoming.try_recv().map_err(|e| match e {
+            mpsc::error::TryRecvError::Empty => would_block(),
+            mpsc::error::TryRecvError::Disconnected => disconnected()
+        })??;
+        deliver(frame, buffer, self.peer)
+    }
+}
+
+
+impl<Q: Hash + Eq> Sensor<Q, TcpTransport> {
+
+    /// Connect a sensor to a motor listening with `Motor::accept_tcp`.
+    pub async fn connect_tcp(
+        tract_name: &str, 
+        peer: SocketAddr
+    ) -> Result<Self, BuildError> {
+
+        let transport = TcpTransport::connect(peer).await?;
+        let mut sensor = Sensor::with_transport(tract_name, peer, transport);
+        sensor.peer = Some(peer);
+        Ok(sensor)
+    }
+}
+
+impl<B: Fn(A) -> R, A, R> Motor<B, A, R, TcpTransport> {
+
+    /// Listen on `bind` and accept one sensor's connection.
+    /// The motor's address is the listening address.
+    pub async fn accept_tcp(
+        tract_name: &str, 
+        bind: SocketAddr
+    ) -> Result<Self, BuildError> {
+
+        let listener = TcpListener::bind(bind).await?;
+        Self::accept_tcp_from(tract_name, &listener).await
+    }
+
+    /// Accept one sensor's connection on a listener that is already bound.
+    /// The motor's address is the listening address.
+    pub async fn accept_tcp_from(
+        tract_name: &str, 
+        listener: &TcpListener
+    ) -> Result<Self, BuildError> {
+
+        let address = listener.local_addr()?;
+        let (stream, _) = listener.accept().await?;
+        Ok(Motor::with_transport(tract_name, address, TcpTransport::from_stream(stream)?))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+    use tokio::io::{ AsyncReadExt, AsyncWriteExt };
+    use tokio::net::TcpListener;
+
+    use crate::transport::Transport;
+    use crate::types::{ Sensor, Motor };
+    use super::TcpTransport;
+
+    #[tokio::test]
+    async fn tcp_tracts_deliver_impulses() {
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (motor, sensor) = tokio::join!(
+            Motor::<fn(()) -> u8, (), u8, _>::accept_tcp_from("tcp", &listener),
+            Sensor::<u8, _>::connect_tcp("tcp", address)
+        );
+        let motor = motor.unwrap().with_fiber(1, |_| 7);
+        let sensor = sensor.unwrap().with_spectrum([(0, 1)]);
+        assert_eq!(motor.address, address);
+
+        sensor.send_impulse(&0).await.unwrap();
+        assert_eq!(motor.recv_impulse(()).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn cancelled_receives_keep_frames_whole() {
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (transport, accepted) = tokio::join!(TcpTransport::connect(address), listener.accept());
+        let transport = transport.unwrap();
+        let (mut raw, _) = accepted.unwrap();
+
+        // Half a frame arrives, then the receive is cancelled.
+        raw.write_all(&[0, 0, 0, 2, 9]).await.unwrap();
+        let mut buffer = [0u8; 8];
+        let waited = tokio::time::timeout(Duration::from_millis(50), transport.recv_from(&mut buffer)).await;
+        assert!(waited.is_err());
+
+        raw.write_all(&[8]).await.unwrap();
+        let (n, _) = transport.recv_from(&mut buffer).await.unwrap();
+        assert_eq!(&buffer[..n], &[9, 8]);
+
+        transport.send(&[1, 2, 3]).await.unwrap();
+        let mut frame = [0u8; 7];
+        raw.read_exact(&mut frame).await.unwrap();
+        assert_eq!(frame, [0, 0, 0, 3, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,89 @@
+
+//! Same-host tracts over Unix domain datagram sockets,
+//! addressed by filesystem path instead of `SocketAddr`.
+//! Tract addresses are reported as unspecified (`0.0.0.0:0`),
+//! and best-effort feedback (acks, unrecognized echoes) is not sent.
+//! NOTE: Binding fails if the path exists; remove stale socket files first.
+
+use std::io;
+use std::hash::Hash;
+use std::net::{ SocketAddr, IpAddr, Ipv4Addr };
+use std::path::Path;
+use tokio::net::UnixDatagram;
+
+use crate::error::BuildError;
+use crate::types::{ Sensor, Motor };
+use super::Transport;
+
+
+fn unspecified() -> SocketAddr { SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0) }
+
+impl Transport for UnixDatagram {
+
+    async fn send(&self, datagram: &[u8]) -> io::Result<usize> {
+        UnixDatagram::send(self, datagram).await
+    }
+
+    async fn recv_from(&self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let n_bytes = UnixDatagram::recv(self, buffer).await?;
+        Ok((n_bytes, unspecified()))
+    }
+
+    fn try_recv_from(&self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let n_bytes = UnixDatagram::try_recv(self, buffer)?;
+        Ok((n_bytes, unspecified()))
+    }
+}
+
+
+impl<Q: Hash + Eq> Sensor<Q, UnixDatagram> {
+
+    /// Create a sensor bound to the socket file at `path`.
+    pub fn bind_unix(tract_name: &str, path: impl AsRef<Path>) -> Result<Self, BuildError> {
+
+        let socket = UnixDatagram::bind(path)?;
+        Ok(Sensor::with_transport(tract_name, unspecified(), socket))
+    }
+
+    /// Create a sensor with an unnamed socket connected to the motor at `path`.
+    pub fn connect_unix(tract_name: &str, path: impl AsRef<Path>) -> Result<Self, BuildError> {
+
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Sensor::with_transport(tract_name, unspecified(), socket))
+    }
+}
+
+impl<B: Fn(A) -> R, A, R> Motor<B, A, R, UnixDatagram> {
+
+    /// Create a motor bound to the socket file at `path`.
+    pub fn bind_unix(tract_name: &str, path: impl AsRef<Path>) -> Result<Self, BuildError> {
+
+        let socket = UnixDatagram::bind(path)?;
+        Ok(Motor::with_transport(tract_name, unspecified(), socket))
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::types::{ Sensor, Motor };
+
+    #[tokio::test]
+    async fn unix_tracts_deliver_impulses() {
+
+        let path = std::env::temp_dir().join(format!("phantom_limb_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let motor: Motor<fn(()) -> u8, (), u8, _> = Motor::bind_unix("unix", &path).unwrap()
+            .with_fiber(1, |_| 7);
+        let sensor: Sensor<u8, _> = Sensor::connect_unix("unix", &path).unwrap()
+            .with_spectrum([(0, 1)]);
+
+        sensor.send_impulse(&0).await.unwrap();
+        assert_eq!(motor.recv_impulse(()).await.unwrap(), 7);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
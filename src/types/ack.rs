@@ -0,0 +1,147 @@
+
+//! Cumulative acknowledgement of sequenced impulses.
+//! With it enabled, the `Motor` periodically answers the sender with an
+//! `ImpulseFrame::Ack` carrying the highest sequence number up to which 
+//! every impulse has arrived (TCP-style), every `every` impulses or once 
+//! `interval` has passed, whichever comes first. Both are checked only 
+//! as impulses arrive. A missing sequence number holds the ack back 
+//! until it is filled in; gaps too wide to track (see `loss`) are given up.
+//! The `Sensor` treats an ack as confirming everything up to that number.
+
+use std::hash::Hash;
+use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use crate::wire::ImpulseFrame;
+use super::{ Sensor, Motor };
+
+
+#[derive(Debug, Clone)]
+pub(crate) struct CumulativeAck {
+    every: usize,
+    interval: Duration,
+    pending: usize,
+    last_sent: Instant,
+    last_acked: Option<u64>
+}
+
+impl CumulativeAck {
+
+    /// Start over, as if no impulses had been acked.
+    pub(crate) fn reset(&mut self) {
+
+        self.pending = 0;
+        self.last_sent = Instant::now();
+        self.last_acked = None;
+    }
+
+    /// Count an arrival, returning the sequence number to ack if one is due.
+    fn due(&mut self, contiguous: Option<u64>) -> Option<u64> {
+
+        self.pending += 1;
+        let due = self.pending >= self.every || self.last_sent.elapsed() >= self.interval;
+        if !due || contiguous.is_none() || contiguous == self.last_acked { return None }
+
+        self.pending = 0;
+        self.last_sent = Instant::now();
+        self.last_acked = contiguous;
+        contiguous
+    }
+}
+
+impl<B: Fn(A) -> R, A, R, T: Transport> Motor<B, A, R, T> {
+
+    /// Acknowledge sequenced impulses cumulatively, every `every` impulses 
+    /// or after `interval`, whichever comes first. See the module docs.
+    pub fn set_cumulative_ack(&mut self, every: usize, interval: Duration) {
+
+        let ack = CumulativeAck { 
+            every: every.max(1), 
+            interval, 
+            pending: 0, 
+            last_sent: Instant::now(), 
+            last_acked: None 
+        };
+        *self.acks.get_mut().unwrap_or_else(|e| e.into_inner()) = Some(ack);
+    }
+
+    /// Stop acknowledging impulses.
+    pub fn disable_cumulative_ack(&mut self) {
+
+        *self.acks.get_mut().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Send a cumulative ack to `source` if one is due. Best-effort.
+    pub(crate) fn acknowledge(&self, source: SocketAddr) {
+
+        let mut acks = self.acks.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(ack) = acks.as_mut() else { return };
+
+        if let Some(seq) = ack.due(self.loss().contiguous_through()) {
+            if let Ok(frame) = self.codec.encode(&ImpulseFrame::Ack(seq)) {
+                let _ = self.socket.try_send_to(&frame, source);
+            }
+        }
+    }
+}
+
+impl<Q: Hash + Eq> Sensor<Q> {
+
+    /// Wait for the next ack from the motor, returning the highest 
+    /// sequence number confirmed so far. Other datagrams are discarded.
+    pub async fn recv_ack(&self) -> Result<u64, CommunicationError> {
+
+        let mut buffer = [0u8; 64];
+        loop {
+            let n_bytes = self.socket.recv(&mut buffer).await?;
+            if let Ok(ImpulseFrame::Ack(seq)) = self.codec.decode(&buffer[..n_bytes]) {
+                self.acked.fetch_max(seq + 1, Ordering::Relaxed);
+                return Ok(self.acked.load(Ordering::Relaxed) - 1)
+            }
+        }
+    }
+
+    /// Highest sequence number confirmed by an ack, if any.
+    pub fn acked_through(&self) -> Option<u64> {
+
+        self.acked.load(Ordering::Relaxed).checked_sub(1)
+    }
+
+    /// Sequenced impulses sent but not yet confirmed.
+    pub fn unacked_count(&self) -> u64 {
+
+        self.sent_count().saturating_sub(self.acked.load(Ordering::Relaxed))
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+    use crate::types::{ Sensor, FnMotor };
+
+    #[tokio::test]
+    async fn acks_confirm_contiguous_impulses() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let mut motor = FnMotor::new("ack", any).await.unwrap().with_fiber(1, |_: ()| ());
+        motor.set_cumulative_ack(2, Duration::from_secs(60));
+        let mut sensor: Sensor<u8> = Sensor::connected("ack", any, motor.address).await.unwrap()
+            .with_spectrum([(0, 1)]);
+        sensor.set_sequenced(true);
+
+        for _ in 0..3 { sensor.send_impulse(&0).await.unwrap() }
+        assert_eq!(sensor.acked_through(), None);
+        for _ in 0..3 { motor.recv_impulse(()).await.unwrap() }
+
+        assert_eq!(sensor.recv_ack().await.unwrap(), 1);
+        assert_eq!(sensor.acked_through(), Some(1));
+        assert_eq!(sensor.unacked_count(), 1);
+    }
+}
@@ -0,0 +1,197 @@
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::time::Duration;
+
+use crate::error::{ BuildError, CommunicationError };
+use super::Motor;
+
+
+/// A boxed future returned by an async behavior.
+pub type BehaviorFuture<R> = Pin<Box<dyn Future<Output = R> + Send>>;
+
+/// An async behavior, boxed so different closures can share one motor.
+pub type AsyncBehavior<A, R> = Box<dyn Fn(A) -> BehaviorFuture<R> + Send + Sync>;
+
+/// A `Motor` whose behaviors are async, for behaviors that do I/O.
+/// Each behavior's future is awaited by `recv_impulse`, optionally bounded
+/// by a per-fiber timeout; on expiry the future is dropped (cancelled).
+pub struct AsyncMotor<A, R> {
+    pub motor: Motor<AsyncBehavior<A, R>, A, BehaviorFuture<R>>,
+    timeouts: HashMap<u16, Duration>
+}
+
+impl<A, R: 'static> AsyncMotor<A, R> {
+
+    /// Create a motor socket. Use port '0' to have the system assign a port.
+    pub async fn new(
+        tract_name: &str,
+        address: SocketAddr
+    ) -> Result<Self, BuildError> {
+
+        let motor = Motor::new(tract_name, address).await?;
+        Ok(AsyncMotor { motor, timeouts: HashMap::new() })
+    }
+
+    /// Maps a fiber ID to an async behavior.
+    /// NOTE: Overwrites existing fiber ID keys without checking.
+    pub fn add_fiber<F, Fut>(&mut self, impulse: u16, behavior: F) 
+    where
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = R> + Send + 'static
+    {
+
+        let behavior: AsyncBehavior<A, R> = Box::new(move |args| Box::pin(behavior(args)));
+        self.motor.add_fiber(impulse, behavior);
+    }
+
+    /// Cancel the behavior for `impulse` if it runs longer than `timeout`,
+    /// returning `BehaviorTimedOut` instead.
+    /// NOTE: Sync behaviors (on `Motor`) cannot be cancelled, 
+    /// so timeouts are only available on the async motor.
+    pub fn set_behavior_timeout(&mut self, impulse: u16, timeout: Duration) {
+
+        self.timeouts.insert(impulse, timeout);
+    }
+
+    /// Remove the timeout for a fiber, letting its behavior run to completion.
+    pub fn clear_behavior_timeout(&mut self, impulse: u16) {
+
+        self.timeouts.remove(&impulse);
+    }
+
+    /// Receives an impulse and awaits the corresponding behavior.
+    pub async fn recv_impulse(&self, args: A) -> Result<R, CommunicationError> {
+
+        let fiber = self.motor.recv_fiber().await?;
+        self.dispatch(fiber, args).await
+    }
+
+    /// Awaits the behavior for a fiber ID as if its impulse was received.
+    /// NOTE: A panic while the behavior's future is awaited here is not caught;
+    /// use `run_spawned` to isolate panicking async behaviors.
+    pub async fn dispatch(&self, fiber: u16, args: A) -> Result<R, CommunicationError> {
+
+        let behavior = self.motor.dispatch(fiber, args)?;
+        with_timeout(fiber, behavior, self.timeouts.get(&fiber).copied()).await
+    }
+
+    /// Receives impulses indefinitely, spawning each behavior as its own task
+    /// so the socket keeps draining while behaviors do I/O.
+    /// Results are delivered to `sink` in the order the behaviors finish.
+    /// At most `max_in_flight` behaviors run at once; after that, receiving pauses.
+    /// A panicking behavior is delivered as `BehaviorPanicked`.
+    /// The loop only ends on a socket or deserialization error.
+    pub async fn run_spawned<F, S>(
+        &self,
+        max_in_flight: usize,
+        mut args: F,
+        mut sink: S
+    ) -> Result<(), CommunicationError>
+    where
+        R: Send,
+        F: FnMut() -> A,
+        S: FnMut(Result<R, CommunicationError>)
+    {
+
+        let max_in_flight = max_in_flight.max(1);
+        let mut in_flight = tokio::task::JoinSet::new();
+
+        loop {
+            tokio::select! {
+                biased;
+
+                Some(joined) = in_flight.join_next() => {
+                    sink(joined.expect("Behavior panics are caught by the inner task"))
+                },
+
+                received = self.motor.recv_fiber(), if in_flight.len() < max_in_flight => {
+                    let fiber = received?;
+                    let behavior = match self.motor.dispatch(fiber, args()) {
+                        Ok(behavior) => behavior,
+                        Err(e) => { sink(Err(e)); continue }
+                    };
+                    let limit = self.timeouts.get(&fiber).copied();
+                    let task = tokio::spawn(with_timeout(fiber, behavior, limit));
+                    in_flight.spawn(async move {
+                        task.await.unwrap_or(Err(CommunicationError::BehaviorPanicked(fiber)))
+                    });
+                }
+            }
+        }
+    }
+
+}
+
+/// Await a behavior, cancelling it after `limit` if one is set.
+async fn with_timeout<R>(
+    fiber: u16,
+    behavior: BehaviorFuture<R>,
+    limit: Option<Duration>
+) -> Result<R, CommunicationError> {
+
+    match limit {
+        Some(limit) => tokio::time::timeout(limit, behavior).await
+            .map_err(|_| CommunicationError::BehaviorTimedOut { fiber }),
+        None => Ok(behavior.await)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use crate::error::CommunicationError;
+    use crate::types::Sensor;
+    use super::AsyncMotor;
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_behavior_is_cancelled_after_its_timeout() {
+
+        let mut motor: AsyncMotor<u64, u64> = AsyncMotor::new("slow", "127.0.0.1:0".parse().unwrap())
+            .await.unwrap();
+        motor.add_fiber(1, |secs| async move {
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+            secs
+        });
+        motor.set_behavior_timeout(1, Duration::from_secs(5));
+
+        assert_eq!(motor.dispatch(1, 2).await.unwrap(), 2);
+        assert!(matches!(
+            motor.dispatch(1, 10).await, 
+            Err(CommunicationError::BehaviorTimedOut { fiber: 1 })
+        ));
+
+        motor.clear_behavior_timeout(1);
+        assert_eq!(motor.dispatch(1, 10).await.unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn spawned_behaviors_finish_out_of_order() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let mut motor: AsyncMotor<(), u16> = AsyncMotor::new("spawned", any).await.unwrap();
+        motor.add_fiber(1, |_| async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            1
+        });
+        motor.add_fiber(2, |_| async { 2 });
+        motor.add_fiber(3, |_| async { Option::<u16>::None.expect("behavior panicked") });
+        let sensor: Sensor<u8> = Sensor::connected("spawned", any, motor.motor.address).await.unwrap()
+            .with_spectrum([(1, 1), (2, 2), (3, 3)]);
+
+        for quantum in 1..=3 { sensor.send_impulse(&quantum).await.unwrap() }
+        let mut results = Vec::new();
+        let run = motor.run_spawned(4, || (), |result| results.push(result));
+        assert!(tokio::time::timeout(Duration::from_secs(1), run).await.is_err());
+
+        assert_eq!(results.len(), 3);
+        assert!(results[..2].iter().any(|r| matches!(r, Ok(2))));
+        assert!(results[..2].iter().any(|r| matches!(r, Err(CommunicationError::BehaviorPanicked(3)))));
+        assert!(matches!(results[2], Ok(1)));
+    }
+}
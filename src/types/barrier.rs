@@ -0,0 +1,100 @@
+
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Barrier;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use super::Sensor;
+
+
+/// Releases a fixed number of sensors together, for synchronized ticks.
+/// Every wait is bounded by a timeout, so a sensor that never arrives
+/// fails the others with `Timeout` instead of deadlocking them.
+/// NOTE: A timed-out wait still counts towards its tick, 
+/// so replace the barrier after a timeout rather than reusing it.
+pub struct TickBarrier {
+    barrier: Arc<Barrier>,
+    timeout: Duration
+}
+
+impl Clone for TickBarrier {
+    fn clone(&self) -> Self {
+        TickBarrier { barrier: self.barrier.clone(), timeout: self.timeout }
+    }
+}
+
+impl TickBarrier {
+
+    /// A barrier for `sensors` participants, each waiting at most `timeout`.
+    pub fn new(sensors: usize, timeout: Duration) -> Self {
+
+        TickBarrier { barrier: Arc::new(Barrier::new(sensors)), timeout }
+    }
+
+    /// Wait until every participant has arrived.
+    pub async fn wait(&self) -> Result<(), CommunicationError> {
+
+        tokio::time::timeout(self.timeout, self.barrier.wait()).await
+            .map(|_| ())
+            .map_err(|_| CommunicationError::Timeout)
+    }
+}
+
+impl<Q: Hash + Eq, T: Transport> Sensor<Q, T> {
+
+    /// Wait at `barrier`, then send the impulse as soon as it releases.
+    /// The quantum is resolved after the barrier, so an unrecognized 
+    /// quantum never holds back the other sensors.
+    pub async fn send_impulse_synced(
+        &self, 
+        quantum: &Q, 
+        barrier: &TickBarrier
+    ) -> Result<(), CommunicationError> {
+
+        barrier.wait().await?;
+        self.send_impulse(quantum).await
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use crate::error::CommunicationError;
+    use crate::transport::{ Transport, ChannelTransport };
+    use crate::types::Sensor;
+    use super::TickBarrier;
+
+    #[tokio::test(start_paused = true)]
+    async fn sensors_fire_together_or_time_out() {
+
+        let (a, a_end) = ChannelTransport::pair(8);
+        let (b, b_end) = ChannelTransport::pair(8);
+        let left: Sensor<char, _> = Sensor::in_process("left", a).with_spectrum([('l', 1)]);
+        let right: Sensor<char, _> = Sensor::in_process("right", b).with_spectrum([('r', 2)]);
+
+        let alone = TickBarrier::new(2, Duration::from_millis(50));
+        let sent = left.send_impulse_synced(&'l', &alone).await;
+        assert!(matches!(sent, Err(CommunicationError::Timeout)));
+        assert!(a_end.try_recv_from(&mut [0u8; 64]).is_err());
+
+        let tick = TickBarrier::new(2, Duration::from_millis(50));
+        let (l, r) = tokio::join!(
+            left.send_impulse_synced(&'l', &tick),
+            right.send_impulse_synced(&'r', &tick)
+        );
+        l.unwrap();
+        r.unwrap();
+
+        let mut buf = [0u8; 64];
+        let (n, _) = a_end.recv_from(&mut buf).await.unwrap();
+        assert_eq!(crate::wire::decode_impulse(&buf[..n]).unwrap(), 1);
+        let (n, _) = b_end.recv_from(&mut buf).await.unwrap();
+        assert_eq!(crate::wire::decode_impulse(&buf[..n]).unwrap(), 2);
+    }
+}
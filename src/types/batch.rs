@@ -0,0 +1,90 @@
+
+use std::hash::Hash;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use crate::wire::ImpulseFrame;
+use super::Sensor;
+
+
+impl<Q: Hash + Eq, T: Transport> Sensor<Q, T> {
+
+    /// Resolve every quantum and send the fiber IDs packed into as few
+    /// datagrams as possible (see `recommended_batch_size`).
+    /// Returns one result per quantum, in order: the fiber ID sent, or
+    /// `UnrecognizedTrigger` for a quantum with no receptor. 
+    /// Fails as a whole if the sensor is quiescing or a send fails.
+    /// NOTE: Batches are not sequenced or edge-triggered.
+    pub async fn send_impulses(
+        &self, 
+        quanta: &[Q]
+    ) -> Result<Vec<Result<u16, CommunicationError>>, CommunicationError> {
+
+        if self.is_quiescing() { 
+            quanta.iter().for_each(|_| self.metrics.record_dropped());
+            return Err(CommunicationError::Quiescing) 
+        }
+
+        let results: Vec<Result<u16, CommunicationError>> = quanta.iter()
+            .map(|quantum| {
+                self.spectrum.get(quantum).copied()
+                    .or_else(|| self.sample_weighted(quantum))
+                    .ok_or_else(|| {
+                        self.metrics.record_unrecognized();
+                        CommunicationError::UnrecognizedTrigger(self.tract_name.clone())
+                    })
+            })
+            .collect();
+
+        let fibers: Vec<u16> = results.iter().filter_map(|r| r.as_ref().ok().copied()).collect();
+        self.transmit_batch(&fibers).await?;
+        Ok(results)
+    }
+
+    /// Sends fiber IDs that have already been resolved, packed into batches.
+    pub(crate) async fn transmit_batch(&self, fibers: &[u16]) -> Result<(), CommunicationError> {
+
+        if self.is_quiescing() { 
+            fibers.iter().for_each(|_| self.metrics.record_dropped());
+            return Err(CommunicationError::Quiescing) 
+        }
+
+        for chunk in fibers.chunks(self.recommended_batch_size().max(1)) {
+            let datagram = self.codec.encode(&ImpulseFrame::Batch(chunk.to_vec()))?;
+            if let Err(e) = self.deliver(&datagram).await {
+                chunk.iter().for_each(|_| self.metrics.record_dropped());
+                return Err(e)
+            }
+            chunk.iter().for_each(|_| self.metrics.record_sent());
+        }
+        Ok(())
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::error::CommunicationError;
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+
+    #[tokio::test]
+    async fn batches_carry_every_recognized_fiber() {
+
+        let (a, b) = ChannelTransport::pair(8);
+        let sensor: Sensor<char, _> = Sensor::in_process("batch", a).with_spectrum([('a', 1), ('b', 2)]);
+        let motor: Motor<fn(()), (), (), _> = Motor::in_process("batch", b);
+
+        let results = sensor.send_impulses(&['a', 'x', 'b']).await.unwrap();
+        assert_eq!(results[0].as_ref().unwrap(), &1);
+        assert!(matches!(results[1], Err(CommunicationError::UnrecognizedTrigger(_))));
+        assert_eq!(results[2].as_ref().unwrap(), &2);
+
+        assert_eq!(motor.recv_fiber().await.unwrap(), 1);
+        assert_eq!(motor.recv_fiber().await.unwrap(), 2);
+        let metrics = sensor.metrics();
+        assert_eq!((metrics.impulses_sent, metrics.unrecognized), (2, 1));
+    }
+}
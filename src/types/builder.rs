@@ -0,0 +1,293 @@
+
+//! Validated, one-step construction of tracts.
+
+use std::collections::{ HashMap, HashSet };
+use std::hash::Hash;
+use std::net::{ SocketAddr, IpAddr, Ipv4Addr };
+
+use crate::error::{ BuildError, ConfigError };
+use super::{ Sensor, Motor };
+
+
+/// Collects the configuration of a UDP `Sensor` and checks it 
+/// before anything is bound, so a mistake fails `build` instead of
+/// surfacing later as impulses on the wrong fiber.
+pub struct SensorBuilder<Q: Hash + Eq> {
+    tract_name: String,
+    bind: SocketAddr,
+    receptors: Vec<(Q, u16)>,
+    remote: Option<SocketAddr>
+}
+
+impl<Q: Hash + Eq> Sensor<Q> {
+
+    /// Start building a sensor, bound to any port on all interfaces by default.
+    pub fn builder(tract_name: &str) -> SensorBuilder<Q> { SensorBuilder::new(tract_name) }
+}
+
+impl<Q: Hash + Eq> SensorBuilder<Q> {
+
+    /// Start building a sensor, bound to any port on all interfaces by default.
+    pub fn new(tract_name: &str) -> Self {
+
+        SensorBuilder {
+            tract_name: tract_name.to_owned(),
+            bind: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+            receptors: Vec::new(),
+            remote: None
+        }
+    }
+
+    /// Address to bind the socket to.
+    pub fn bind(mut self, address: SocketAddr) -> Self {
+
+        self.bind = address;
+        self
+    }
+
+    /// Map a quantum to a fiber ID.
+    pub fn receptor(mut self, quantum: Q, fid: u16) -> Self {
+
+        self.receptors.push((quantum, fid));
+        self
+    }
+
+    /// Map every quantum to its fiber ID.
+    pub fn receptors(mut self, receptors: impl IntoIterator<Item = (Q, u16)>) -> Self {
+
+        self.receptors.extend(receptors);
+        self
+    }
+
+    /// Connect to the corresponding Input once bound.
+    pub fn connect(mut self, remote: SocketAddr) -> Self {
+
+        self.remote = Some(remote);
+        self
+    }
+
+    /// Check the spectrum: every quantum and every fiber ID may appear once.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+
+        let mut fibers = HashSet::new();
+        let mut duplicates: Vec<u16> = self.receptors.iter()
+            .filter(|(_, fid)| !fibers.insert(*fid))
+            .map(|(_, fid)| *fid)
+            .collect();
+
+        if !duplicates.is_empty() {
+            duplicates.sort_unstable();
+            duplicates.dedup();
+            return Err(ConfigError::DuplicateFiberIds(duplicates))
+        }
+
+        let mut quanta = HashSet::new();
+        if !self.receptors.iter().all(|(quantum, _)| quanta.insert(quantum)) {
+            return Err(ConfigError::DuplicateQuantum)
+        }
+        Ok(())
+    }
+
+    /// Validate, bind, load the spectrum and connect (if a remote was given).
+    pub async fn build(self) -> Result<Sensor<Q>, BuildError> {
+
+        self.validate()?;
+
+        let mut sensor = Sensor::new(&self.tract_name, self.bind).await?;
+        sensor.spectrum = self.receptors.into_iter().collect::<HashMap<Q, u16>>();
+        if let Some(remote) = self.remote {
+            sensor.connect(&remote).await?;
+        }
+        Ok(sensor)
+    }
+}
+
+
+/// Collects the wiring of a UDP `Motor` and checks it before 
+/// anything is bound: every fiber ID may have one behavior, and 
+/// if the expected fibers are known, every one must be covered.
+pub struct MotorBuilder<B: Fn(A) -> R, A, R> {
+    tract_name: String,
+    bind: SocketAddr,
+    fibers: Vec<(u16, B)>,
+    expected: Option<Vec<u16>>,
+    peer: Option<SocketAddr>,
+    recv_buffer_size: Option<usize>,
+    recv_buffer_len: Option<usize>,
+    phantom_data: std::marker::PhantomData<fn(A) -> R>
+}
+
+impl<B: Fn(A) -> R, A, R> Motor<B, A, R> {
+
+    /// Start building a motor, bound to any port on all interfaces by default.
+    pub fn builder(tract_name: &str) -> MotorBuilder<B, A, R> { MotorBuilder::new(tract_name) }
+}
+
+impl<B: Fn(A) -> R, A, R> MotorBuilder<B, A, R> {
+
+    /// Start building a motor, bound to any port on all interfaces by default.
+    pub fn new(tract_name: &str) -> Self {
+
+        MotorBuilder {
+            tract_name: tract_name.to_owned(),
+            bind: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+            fibers: Vec::new(),
+            expected: None,
+            peer: None,
+            recv_buffer_size: None,
+            recv_buffer_len: None,
+            phantom_data: std::marker::PhantomData
+        }
+    }
+
+    /// Address to bind the socket to.
+    pub fn bind(mut self, address: SocketAddr) -> Self {
+
+        self.bind = address;
+        self
+    }
+
+    /// Map a fiber ID to a behavior.
+    pub fn fiber(mut self, impulse: u16, behavior: B) -> Self {
+
+        self.fibers.push((impulse, behavior));
+        self
+    }
+
+    /// Map every fiber ID to its behavior.
+    pub fn fibers(mut self, fibers: impl IntoIterator<Item = (u16, B)>) -> Self {
+
+        self.fibers.extend(fibers);
+        self
+    }
+
+    /// Require a behavior for every fiber ID the Output sends,
+    /// e.g. from `Output::sender_ids`.
+    pub fn expect_fibers(mut self, fiber_ids: impl IntoIterator<Item = u16>) -> Self {
+
+        self.expected = Some(fiber_ids.into_iter().collect());
+        self
+    }
+
+    /// Only accept datagrams from `peer` (see `Motor::listening_from`).
+    pub fn allow_only(mut self, peer: SocketAddr) -> Self {
+
+        self.peer = Some(peer);
+        self
+    }
+
+    /// Request a kernel receive buffer size, in bytes.
+    pub fn recv_buffer_size(mut self, size: usize) -> Self {
+
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Length of the buffer datagrams are received into 
+    /// (see `Motor::set_recv_buffer_len`).
+    pub fn recv_buffer_len(mut self, len: usize) -> Self {
+
+        self.recv_buffer_len = Some(len);
+        self
+    }
+
+    /// Check the wiring: no fiber ID may have two behaviors, 
+    /// and every expected fiber ID must have one.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+
+        let mut seen = HashSet::new();
+        let mut duplicates: Vec<u16> = self.fibers.iter()
+            .filter(|(fid, _)| !seen.insert(*fid))
+            .map(|(fid, _)| *fid)
+            .collect();
+
+        if !duplicates.is_empty() {
+            duplicates.sort_unstable();
+            duplicates.dedup();
+            return Err(ConfigError::DuplicateFiberIds(duplicates))
+        }
+
+        if let Some(expected) = &self.expected {
+            let mut missing: Vec<u16> = expected.iter()
+                .filter(|fid| !seen.contains(*fid))
+                .copied()
+                .collect();
+
+            if !missing.is_empty() {
+                missing.sort_unstable();
+                missing.dedup();
+                return Err(ConfigError::FiberIdMismatch(missing))
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate, bind (and connect, if a peer was allowlisted) 
+    /// and wire every fiber.
+    pub async fn build(self) -> Result<Motor<B, A, R>, BuildError> {
+
+        self.validate()?;
+
+        let mut motor = match self.peer {
+            Some(peer) => Motor::listening_from(&self.tract_name, self.bind, peer).await?,
+            None => Motor::new(&self.tract_name, self.bind).await?
+        };
+        if let Some(size) = self.recv_buffer_size {
+            motor.set_recv_buffer_size(size)?;
+        }
+        if let Some(len) = self.recv_buffer_len {
+            motor.set_recv_buffer_len(len);
+        }
+        for (impulse, behavior) in self.fibers {
+            motor.add_fiber(impulse, behavior);
+        }
+        Ok(motor)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::error::{ BuildError, ConfigError };
+    use crate::types::{ Sensor, Motor };
+
+    #[tokio::test]
+    async fn sensor_spectra_are_validated_before_binding() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let clash = Sensor::builder("clash").bind(any).receptors([('a', 1), ('b', 1), ('c', 1)]).build().await;
+        assert!(matches!(clash, Err(BuildError::Misconfigured(ConfigError::DuplicateFiberIds(f))) if f == vec![1]));
+
+        let twice = Sensor::builder("twice").bind(any).receptor('a', 1).receptor('a', 2).validate();
+        assert!(matches!(twice, Err(ConfigError::DuplicateQuantum)));
+
+        let peer = "127.0.0.1:4001".parse().unwrap();
+        let sensor = Sensor::builder("built").bind(any).receptors([('a', 1), ('b', 2)]).connect(peer)
+            .build().await.unwrap();
+        assert_eq!(sensor.peer(), Some(peer));
+        assert_eq!(sensor.spectrum.get(&'b'), Some(&2));
+    }
+
+    #[tokio::test]
+    async fn motor_wiring_must_cover_expected_fibers_once() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let twice = Motor::<fn(()), (), ()>::builder("twice").fiber(1, |_| ()).fiber(1, |_| ()).validate();
+        assert!(matches!(twice, Err(ConfigError::DuplicateFiberIds(f)) if f == vec![1]));
+
+        let short = Motor::<fn(()), (), ()>::builder("short").bind(any)
+            .fiber(1, |_| ())
+            .expect_fibers([1, 2, 3])
+            .build().await;
+        assert!(matches!(short, Err(BuildError::Misconfigured(ConfigError::FiberIdMismatch(f))) if f == vec![2, 3]));
+
+        let motor = Motor::<fn(()) -> u8, (), u8>::builder("built").bind(any)
+            .fibers([(1, (|_| 1) as fn(()) -> u8), (2, |_| 2)])
+            .expect_fibers([2, 1])
+            .recv_buffer_len(64)
+            .build().await.unwrap();
+        assert_eq!(motor.dispatch(2, ()).unwrap(), 2);
+    }
+}
@@ -0,0 +1,111 @@
+
+use std::hash::Hash;
+use std::sync::atomic::Ordering;
+use tokio::sync::{ mpsc, oneshot, oneshot::error::TryRecvError };
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use crate::wire::ImpulseFrame;
+use super::{ Sensor, Motor };
+
+
+impl<Q: Hash + Eq, T: Transport> Sensor<Q, T> {
+
+    /// Stop the sensor: its background tasks are cancelled between impulses,
+    /// and further sends fail with `Quiescing`. Quanta already buffered by 
+    /// the sensor's `channel` drivers are sent first. With `notify`, the peer 
+    /// (and any extra targets) is then sent a `Closing` frame so the motor 
+    /// can tell a closed sensor from a silent one.
+    /// Only this sensor's token is cancelled, never the one it was given.
+    /// NOTE: The socket stays open until the sensor is dropped.
+    pub async fn close(&self, notify: bool) -> Result<(), CommunicationError> {
+
+        self.closing.store(true, Ordering::Release);
+        self.cancel.cancel();
+        let drivers = std::mem::take(&mut *self.drivers.lock().unwrap_or_else(|e| e.into_inner()));
+        for driver in drivers { let _ = driver.await; }
+
+        self.quiesce();
+        if notify {
+            let closing = self.codec.encode(&ImpulseFrame::Closing)?;
+            self.deliver(&closing).await?;
+        }
+        Ok(())
+    }
+
+    /// Register a queue driver, which must hold the returned sender until it ends,
+    /// so `close` can wait for it to flush.
+    pub(crate) fn track_driver(&self) -> oneshot::Sender<()> {
+
+        let (running, ended) = oneshot::channel();
+        let mut drivers = self.drivers.lock().unwrap_or_else(|e| e.into_inner());
+        drivers.retain_mut(|driver| matches!(driver.try_recv(), Err(TryRecvError::Empty)));
+        drivers.push(ended);
+        running
+    }
+
+    /// What a queue driver sends once the token is cancelled:
+    /// the next buffered item while `close` is flushing, otherwise nothing.
+    pub(crate) fn flush_next<M>(&self, receiver: &mut mpsc::Receiver<M>) -> Option<M> {
+
+        if self.closing.load(Ordering::Acquire) { receiver.try_recv().ok() } else { None }
+    }
+}
+
+impl<B: Fn(A) -> R, A, R, T: Transport> Motor<B, A, R, T> {
+
+    /// Stop the motor's `serve` and `run_local` loops (see `set_cancellation_token`),
+    /// returning the fibers left over from a batch that were never dispatched.
+    pub fn close(&self) -> Vec<u16> {
+
+        self.cancel.cancel();
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).drain(..).collect()
+    }
+
+    /// Whether a sensor has announced that it is closing (see `Sensor::close`).
+    pub fn peer_closed(&self) -> bool { self.peer_closed.load(Ordering::Acquire) }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::limb::PhantomLimb;
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+
+    #[tokio::test(start_paused = true)]
+    async fn close_flushes_its_queue_and_spares_its_siblings() {
+
+        let limb = PhantomLimb::new();
+        let (a, b) = ChannelTransport::pair(8);
+        let (c, _d) = ChannelTransport::pair(8);
+        let mut sensor: Sensor<char, _> = Sensor::in_process("closing", a).with_spectrum([('a', 1), ('b', 2)]);
+        let mut sibling: Sensor<char, _> = Sensor::in_process("sibling", c);
+        sensor.set_cancellation_token(limb.cancellation_token());
+        sibling.set_cancellation_token(limb.cancellation_token());
+        let motor: Motor<fn(()) -> u8, (), u8, _> = Motor::in_process("closing", b)
+            .with_fiber(1, |_| 1)
+            .with_fiber(2, |_| 2);
+
+        let sensor = Arc::new(sensor);
+        let (quanta, _driver) = sensor.channel(4);
+        quanta.try_send('a').unwrap();
+        quanta.try_send('b').unwrap();
+        sensor.close(true).await.unwrap();
+
+        assert_eq!(motor.recv_impulse(()).await.unwrap(), 1);
+        assert_eq!(motor.recv_impulse(()).await.unwrap(), 2);
+        assert!(motor.recv_impulse_timeout((), Duration::from_secs(1)).await.is_err());
+        assert!(motor.peer_closed());
+
+        assert!(sensor.cancellation_token().is_cancelled());
+        assert!(!sibling.cancellation_token().is_cancelled());
+        assert!(!limb.is_shut_down());
+        assert!(motor.close().is_empty());
+        assert!(!sibling.cancellation_token().is_cancelled());
+    }
+}
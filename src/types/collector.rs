@@ -0,0 +1,105 @@
+
+use std::collections::HashMap;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use super::Motor;
+
+
+/// Aggregates the results of a `Motor`'s behaviors.
+/// See `Motor::run_collecting`.
+pub trait Collector<R> {
+
+    /// Called after each dispatch with the fiber ID and the behavior's result.
+    fn collect(&mut self, fiber: u16, result: R);
+}
+
+/// Accumulates every result in order of dispatch.
+impl<R> Collector<R> for Vec<R> {
+    fn collect(&mut self, _fiber: u16, result: R) { self.push(result) }
+}
+
+/// Counts how many times each fiber has been dispatched.
+#[derive(Debug, Default, Clone)]
+pub struct FiberCount(pub HashMap<u16, usize>);
+
+impl FiberCount {
+
+    /// Number of dispatches recorded for a fiber.
+    pub fn get(&self, fiber: u16) -> usize {
+        self.0.get(&fiber).copied().unwrap_or(0)
+    }
+
+    /// Total dispatches across all fibers.
+    pub fn total(&self) -> usize { self.0.values().sum() }
+}
+
+impl<R> Collector<R> for FiberCount {
+    fn collect(&mut self, fiber: u16, _result: R) { 
+        *self.0.entry(fiber).or_insert(0) += 1 
+    }
+}
+
+
+impl<B: Fn(A) -> R, A, R, T: Transport> Motor<B, A, R, T> {
+
+    /// Receives impulses indefinitely, passing every result to `collector`.
+    /// `args` is called to produce the argument for each impulse.
+    /// Unrecognized impulses are skipped; the loop only ends on a socket
+    /// or deserialization error, which is returned.
+    /// The collector is borrowed, so it can be inspected after
+    /// the future is cancelled (e.g. by `tokio::time::timeout`).
+    pub async fn run_collecting<C, F>(
+        &self,
+        collector: &mut C,
+        mut args: F
+    ) -> Result<(), CommunicationError> 
+    where
+        C: Collector<R>,
+        F: FnMut() -> A
+    {
+
+        loop {
+            let impulse = self.recv_fiber().await?;
+
+            match self.dispatch(impulse, args()) {
+                Ok(result) => collector.collect(impulse, result),
+                Err(CommunicationError::UnrecognizedImpulse(_)) => continue,
+                Err(e) => return Err(e)
+            }
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+    use super::FiberCount;
+
+    #[tokio::test(start_paused = true)]
+    async fn collects_recognized_results() {
+
+        let (a, b) = ChannelTransport::pair(8);
+        let sensor: Sensor<char, _> = Sensor::in_process("collect", a)
+            .with_spectrum([('a', 1), ('b', 2), ('z', 9)]);
+        let motor: Motor<fn(()), (), (), _> = Motor::in_process("collect", b)
+            .with_fiber(1, |_| ())
+            .with_fiber(2, |_| ());
+
+        for quantum in ['a', 'b', 'z', 'a'] { sensor.send_impulse(&quantum).await.unwrap() }
+
+        let mut count = FiberCount::default();
+        let run = motor.run_collecting(&mut count, || ());
+        assert!(tokio::time::timeout(Duration::from_secs(1), run).await.is_err());
+
+        assert_eq!(count.get(1), 2);
+        assert_eq!(count.get(2), 1);
+        assert_eq!(count.total(), 3);
+    }
+}
@@ -0,0 +1,175 @@
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::{ mpsc, Semaphore };
+use tokio::task::JoinHandle;
+
+use crate::error::CommunicationError;
+use super::Motor;
+
+
+impl<B, A, R> Motor<B, A, R> 
+where
+    B: Fn(A) -> R + Send + Sync + 'static,
+    A: Send + 'static,
+    R: Send + 'static
+{
+
+    /// Receives impulses indefinitely, running each behavior on the blocking
+    /// thread pool so slow behaviors overlap, but delivering the results 
+    /// to `sink` in the order the impulses were received.
+    /// At most `max_concurrent` impulses are running or awaiting delivery.
+    /// NOTE: Head-of-line blocking: one slow behavior holds back 
+    /// the results of every impulse received after it, and once 
+    /// `max_concurrent` results are held back, receiving pauses too.
+    /// A panicking behavior is delivered as `BehaviorPanicked`.
+    /// The loop only ends on a socket or deserialization error.
+    pub async fn run_concurrent_ordered<F, S>(
+        self: Arc<Self>,
+        max_concurrent: usize,
+        mut args: F,
+        mut sink: S
+    ) -> Result<(), CommunicationError>
+    where
+        F: FnMut() -> A,
+        S: FnMut(Result<R, CommunicationError>)
+    {
+
+        let max_concurrent = max_concurrent.max(1);
+        let mut in_flight: VecDeque<(u16, JoinHandle<Result<R, CommunicationError>>)> 
+            = VecDeque::new();
+
+        loop {
+            tokio::select! {
+                biased;
+
+                joined = async {
+                    match in_flight.front_mut() {
+                        Some((_, handle)) => handle.await,
+                        None => std::future::pending().await
+                    }
+                } => {
+                    let (fiber, _) = in_flight.pop_front()
+                        .expect("Front of the reorder buffer was awaited");
+                    sink(joined.unwrap_or(Err(CommunicationError::BehaviorPanicked(fiber))))
+                },
+
+                received = self.recv_fiber(), 
+                    if in_flight.len() < max_concurrent => {
+                    let fiber = received?;
+                    let motor = self.clone();
+                    let args = args();
+                    let handle = tokio::task::spawn_blocking(move || {
+                        motor.dispatch(fiber, args)
+                    });
+                    in_flight.push_back((fiber, handle));
+                }
+            }
+        }
+    }
+
+    /// Receives impulses indefinitely, running each behavior on the blocking
+    /// thread pool so a slow behavior does not stop the socket from draining.
+    /// At most `max_concurrent_behaviors` behaviors run at once;
+    /// once that many are running, receiving waits for one to finish.
+    /// Results are delivered to `sink` in the order the behaviors finish.
+    /// A panicking behavior is delivered as `BehaviorPanicked`.
+    /// The loop only ends on a socket or deserialization error.
+    pub async fn run_concurrent<F, S>(
+        self: Arc<Self>,
+        max_concurrent_behaviors: usize,
+        mut args: F,
+        mut sink: S
+    ) -> Result<(), CommunicationError>
+    where
+        F: FnMut() -> A,
+        S: FnMut(Result<R, CommunicationError>)
+    {
+
+        let permits = Arc::new(Semaphore::new(max_concurrent_behaviors.max(1)));
+        let (results_tx, mut results) = mpsc::unbounded_channel();
+
+        loop {
+            tokio::select! {
+                biased;
+
+                Some(result) = results.recv() => sink(result),
+
+                (permit, received) = async {
+                    let permit = permits.clone().acquire_owned().await
+                        .expect("Behavior semaphore is never closed");
+                    (permit, self.recv_fiber().await)
+                } => {
+                    let fiber = received?;
+                    let motor = self.clone();
+                    let results_tx = results_tx.clone();
+                    let args = args();
+                    tokio::task::spawn_blocking(move || {
+                        let result = motor.dispatch(fiber, args);
+                        drop(permit);
+                        let _ = results_tx.send(result);
+                    });
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::types::{ Sensor, Motor };
+
+    #[tokio::test]
+    async fn ordered_results_follow_arrival_order() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let motor: Motor<fn(()) -> u16, (), u16> = Motor::new("ordered", any).await.unwrap()
+            .with_fiber(1, |_| { std::thread::sleep(Duration::from_millis(50)); 1 })
+            .with_fiber(2, |_| 2)
+            .with_fiber(3, |_| 3);
+
+        let mut sensor: Sensor<u16> = Sensor::new("ordered", any).await.unwrap()
+            .with_spectrum([(1, 1), (2, 2), (3, 3)]);
+        sensor.connect(&motor.address).await.unwrap();
+
+        let (sink, mut results) = tokio::sync::mpsc::unbounded_channel();
+        let running = tokio::spawn(Arc::new(motor).run_concurrent_ordered(4, || (), move |result| {
+            let _ = sink.send(result);
+        }));
+
+        for quantum in [1, 2, 3] { sensor.send_impulse(&quantum).await.unwrap() }
+
+        let mut order = Vec::new();
+        for _ in 0..3 { order.push(results.recv().await.unwrap().unwrap()) }
+        assert_eq!(order, vec![1, 2, 3]);
+        running.abort();
+    }
+
+    #[tokio::test]
+    async fn unordered_results_follow_completion_order() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let motor: Motor<fn(()) -> u16, (), u16> = Motor::new("unordered", any).await.unwrap()
+            .with_fiber(1, |_| { std::thread::sleep(Duration::from_millis(200)); 1 })
+            .with_fiber(2, |_| 2);
+        let sensor: Sensor<u16> = Sensor::connected("unordered", any, motor.address).await.unwrap()
+            .with_spectrum([(1, 1), (2, 2)]);
+
+        let (sink, mut results) = tokio::sync::mpsc::unbounded_channel();
+        let running = tokio::spawn(Arc::new(motor).run_concurrent(2, || (), move |result| {
+            let _ = sink.send(result);
+        }));
+
+        for quantum in [1, 2] { sensor.send_impulse(&quantum).await.unwrap() }
+
+        let mut order = Vec::new();
+        for _ in 0..2 { order.push(results.recv().await.unwrap().unwrap()) }
+        assert_eq!(order, vec![2, 1]);
+        running.abort();
+    }
+}
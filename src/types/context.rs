@@ -0,0 +1,85 @@
+
+use std::hash::Hash;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use super::{ Sensor, QueuedSensor };
+
+
+/// Lightweight handle that lets a `Motor` behavior send impulses back out
+/// (a reflex arc) without capturing the `Sensor` itself.
+/// Pass it to behaviors as (part of) their argument `A`,
+/// cloning it in the args factory for each impulse.
+///
+/// `fire` never awaits and never borrows the sensor: the impulse is queued 
+/// and sent by the sensor's driver task after the behavior returns,
+/// so a behavior can fire from inside `recv_impulse` without re-entering
+/// the sensor. If the queue is full the impulse is refused with `QueueFull`
+/// rather than blocking the behavior.
+pub struct TractContext<Q> {
+    queue: QueuedSensor<Q>
+}
+
+impl<Q> Clone for TractContext<Q> {
+    fn clone(&self) -> Self { TractContext { queue: self.queue.clone() } }
+}
+
+impl<Q> TractContext<Q> {
+
+    /// Use an existing queued sensor as the context's outlet.
+    pub fn new(queue: QueuedSensor<Q>) -> Self { TractContext { queue } }
+
+    /// Queue an impulse to be sent by the paired sensor.
+    pub fn fire(&self, quantum: Q) -> Result<(), CommunicationError> {
+
+        self.queue.try_enqueue(quantum)
+    }
+
+    /// The queued sensor behind this context.
+    pub fn queue(&self) -> &QueuedSensor<Q> { &self.queue }
+}
+
+impl<Q, T> Sensor<Q, T> 
+where
+    Q: Hash + Eq + Send + Sync + 'static,
+    T: Transport + Send + Sync + 'static
+{
+
+    /// Move the sensor into a background task and return a context 
+    /// for firing it from behaviors. See `Sensor::queued`.
+    pub fn into_context(self, capacity: usize) -> TractContext<Q> {
+
+        TractContext::new(self.queued(capacity))
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+    use super::TractContext;
+
+    #[tokio::test]
+    async fn behavior_fires_through_its_context() {
+
+        let (out_a, out_b) = ChannelTransport::pair(4);
+        let context = Sensor::in_process("reflex", out_a)
+            .with_spectrum([('!', 9)])
+            .into_context(4);
+        let outlet: Motor<fn(()), (), (), _> = Motor::in_process("reflex", out_b);
+
+        let (in_a, in_b) = ChannelTransport::pair(4);
+        let input: Sensor<char, _> = Sensor::in_process("stimulus", in_a).with_spectrum([('a', 1)]);
+        let motor: Motor<fn(TractContext<char>), TractContext<char>, (), _> = Motor::in_process("stimulus", in_b)
+            .with_fiber(1, |context| context.fire('!').unwrap());
+
+        input.send_impulse(&'a').await.unwrap();
+        motor.recv_impulse(context.clone()).await.unwrap();
+
+        assert_eq!(outlet.recv_fiber().await.unwrap(), 9);
+        assert_eq!(context.queue().queue_stats().sent, 1);
+    }
+}
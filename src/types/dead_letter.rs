@@ -0,0 +1,125 @@
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::time::SystemTime;
+
+use crate::transport::Transport;
+use super::Motor;
+
+
+/// Why an impulse ended up in the dead-letter queue.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeadLetterReason {
+    /// No behavior (and no fallback) for the fiber ID.
+    Unrecognized(u16),
+    /// A datagram that could not be decoded, or a plain impulse 
+    /// while plain impulses are rejected.
+    Malformed { source: SocketAddr, len: usize },
+    /// The behavior panicked.
+    BehaviorPanicked(u16),
+    /// The behavior returned an error (see `Motor::try_dispatch`).
+    BehaviorFailed { fiber: u16, message: String }
+}
+
+/// An impulse the motor could not handle, and when it happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadLetter {
+    pub at: SystemTime,
+    pub reason: DeadLetterReason
+}
+
+/// Bounded buffer of dead letters; the oldest are evicted when full.
+#[derive(Debug)]
+pub(crate) struct DeadLetterQueue {
+    letters: VecDeque<DeadLetter>,
+    capacity: usize,
+    evicted: u64
+}
+
+impl DeadLetterQueue {
+
+    fn push(&mut self, reason: DeadLetterReason) {
+
+        if self.letters.len() == self.capacity {
+            self.letters.pop_front();
+            self.evicted += 1;
+        }
+        self.letters.push_back(DeadLetter { at: SystemTime::now(), reason });
+    }
+}
+
+impl<B: Fn(A) -> R, A, R, T: Transport> Motor<B, A, R, T> {
+
+    /// Keep the last `capacity` unhandled or failed impulses 
+    /// for later inspection with `drain_dead_letters`.
+    /// The errors are still returned as usual.
+    /// NOTE: Replaces (and empties) any existing queue.
+    pub fn enable_dead_letters(&mut self, capacity: usize) {
+
+        *self.dead_letters.get_mut().unwrap_or_else(|e| e.into_inner()) = Some(DeadLetterQueue {
+            letters: VecDeque::with_capacity(capacity.max(1)),
+            capacity: capacity.max(1),
+            evicted: 0
+        });
+    }
+
+    /// Stop capturing dead letters, discarding any that were not drained.
+    pub fn disable_dead_letters(&mut self) {
+
+        *self.dead_letters.get_mut().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Take every captured dead letter, oldest first.
+    pub fn drain_dead_letters(&self) -> Vec<DeadLetter> {
+
+        self.dead_letters.lock().unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+            .map_or_else(Vec::new, |queue| queue.letters.drain(..).collect())
+    }
+
+    /// Number of dead letters evicted because the queue was full.
+    pub fn dead_letters_evicted(&self) -> u64 {
+
+        self.dead_letters.lock().unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map_or(0, |queue| queue.evicted)
+    }
+
+    /// Capture a dead letter, if the queue is enabled.
+    pub(crate) fn dead_letter(&self, reason: DeadLetterReason) {
+
+        if let Some(queue) = self.dead_letters.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            queue.push(reason)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::transport::ChannelTransport;
+    use crate::types::Motor;
+    use super::DeadLetterReason;
+
+    #[tokio::test]
+    async fn dead_letters_keep_the_newest_failures() {
+
+        let (_, b) = ChannelTransport::pair(1);
+        let mut motor: Motor<fn(u8) -> u8, u8, u8, _> = Motor::in_process("dead", b)
+            .with_fiber(1, |x| 10 / x);
+        motor.enable_dead_letters(2);
+
+        let _ = motor.dispatch(7, 1);
+        let _ = motor.dispatch(1, 0);
+        let _ = motor.dispatch(8, 1);
+        motor.dispatch(1, 5).unwrap();
+
+        let reasons: Vec<DeadLetterReason> = motor.drain_dead_letters().into_iter()
+            .map(|letter| letter.reason)
+            .collect();
+        assert_eq!(reasons, vec![DeadLetterReason::BehaviorPanicked(1), DeadLetterReason::Unrecognized(8)]);
+        assert_eq!(motor.dead_letters_evicted(), 1);
+        assert!(motor.drain_dead_letters().is_empty());
+    }
+}
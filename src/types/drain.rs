@@ -0,0 +1,87 @@
+
+use std::io;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use super::Motor;
+
+
+/// Most datagrams `recv_impulses` drains in one call, 
+/// so a sustained flood cannot keep it from returning.
+pub const MAX_DRAIN: usize = 1024;
+
+impl<B: Fn(A) -> R, A, R, T: Transport> Motor<B, A, R, T> {
+
+    /// Waits for an impulse, then drains every datagram already queued 
+    /// (up to `MAX_DRAIN`) without waiting again, dispatching each impulse 
+    /// with an argument from `args`. Returns one result per impulse in 
+    /// arrival order, including behaviors that failed or panicked.
+    /// Unrecognized impulses are skipped (and counted in the metrics).
+    /// A datagram that cannot be read adds its error to the results,
+    /// after which a socket failure ends the drain; impulses collected 
+    /// before it are still dispatched. Fails only if the first receive does.
+    /// NOTE: Transports without `try_recv_from` return one impulse per call.
+    pub async fn recv_impulses<F>(
+        &self, 
+        mut args: F
+    ) -> Result<Vec<Result<R, CommunicationError>>, CommunicationError> 
+    where F: FnMut() -> A {
+
+        let mut buffer = self.recv_buffer.lock().await;
+        let mut arrivals = vec![Ok(self.recv_fiber_into(&mut buffer).await?.0)];
+        while let Some(fiber) = self.next_pending() { arrivals.push(Ok(fiber)) }
+
+        for _ in 0..MAX_DRAIN {
+            match self.socket.try_recv_from(&mut buffer) {
+                Ok((n_bytes, source)) => match self.accept_datagram(&buffer[..n_bytes], source) {
+                    Ok(Some(fiber)) => {
+                        arrivals.push(Ok(fiber));
+                        while let Some(fiber) = self.next_pending() { arrivals.push(Ok(fiber)) }
+                    },
+                    Ok(None) => {},
+                    Err(e) => arrivals.push(Err(e))
+                },
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => { arrivals.push(Err(e.into())); break }
+            }
+        }
+
+        let results = arrivals.into_iter()
+            .map(|arrival| arrival.and_then(|fiber| self.dispatch(fiber, args())))
+            .filter(|result| !matches!(result, Err(CommunicationError::UnrecognizedImpulse(_))))
+            .collect();
+        Ok(results)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::error::CommunicationError;
+    use crate::transport::{ Transport, ChannelTransport };
+    use crate::types::{ Sensor, Motor };
+
+    #[tokio::test]
+    async fn drained_errors_are_reported_in_order() {
+
+        let (a, b) = ChannelTransport::pair(8);
+        let sensor: Sensor<char, _> = Sensor::in_process("drain", a)
+            .with_spectrum([('a', 1), ('b', 2), ('x', 9)]);
+        let motor: Motor<fn(u8) -> u8, u8, u8, _> = Motor::in_process("drain", b)
+            .with_fiber(1, |x| x + 1)
+            .with_fiber(2, |x| x / (x - 3));
+
+        sensor.send_impulse(&'a').await.unwrap();
+        sensor.send_impulse(&'x').await.unwrap();
+        sensor.socket.send(&[0xff; 5]).await.unwrap();
+        sensor.send_impulse(&'b').await.unwrap();
+
+        let results = motor.recv_impulses(|| 3).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap(), &4);
+        assert!(results[1].is_err());
+        assert!(matches!(results[2], Err(CommunicationError::BehaviorPanicked(_))));
+        assert_eq!(motor.metrics().unrecognized, 1);
+    }
+}
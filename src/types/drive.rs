@@ -0,0 +1,94 @@
+
+use std::hash::Hash;
+use std::pin::pin;
+use std::sync::Arc;
+use futures_core::Stream;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use super::Sensor;
+use super::schedule::TrainHandle;
+
+
+impl<Q, T> Sensor<Q, T> 
+where
+    Q: Hash + Eq + Send + Sync + 'static,
+    T: Transport + Send + Sync + 'static
+{
+
+    /// Spawn a task that sends every item of `stream` as an impulse, 
+    /// until the stream ends, the handle is stopped or dropped,
+    /// or the sensor's cancellation token is cancelled.
+    /// Failed sends (e.g. `UnrecognizedTrigger`) are passed to `on_error` 
+    /// with the quantum, and do not end the task.
+    /// Joining the handle returns the number of impulses sent.
+    pub fn drive_from<S, E>(self: &Arc<Self>, stream: S, mut on_error: E) -> TrainHandle
+    where
+        S: Stream<Item = Q> + Send + 'static,
+        E: FnMut(&Q, CommunicationError) + Send + 'static
+    {
+
+        let sensor = self.clone();
+        let task = tokio::spawn(async move {
+            let mut stream = pin!(stream);
+            let mut sent = 0;
+            loop {
+                let quantum = tokio::select! {
+                    _ = sensor.cancel.cancelled() => break,
+                    next = std::future::poll_fn(|cx| stream.as_mut().poll_next(cx)) => match next {
+                        Some(quantum) => quantum,
+                        None => break
+                    }
+                };
+                match sensor.send_impulse(&quantum).await {
+                    Ok(()) => sent += 1,
+                    Err(e) => on_error(&quantum, e)
+                }
+            }
+            Ok(sent)
+        });
+
+        TrainHandle { task }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::pin::Pin;
+    use std::sync::{ Arc, Mutex };
+    use std::task::{ Context, Poll };
+    use futures_core::Stream;
+
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+
+    struct Readings(std::vec::IntoIter<char>);
+
+    impl Stream for Readings {
+        type Item = char;
+        fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<char>> {
+            Poll::Ready(self.0.next())
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_drive_impulses_and_report_failures() {
+
+        let (a, b) = ChannelTransport::pair(8);
+        let sensor = Arc::new(Sensor::in_process("drive", a).with_spectrum([('a', 1), ('b', 2)]));
+        let motor: Motor<fn(()), (), (), _> = Motor::in_process("drive", b);
+
+        let failed = Arc::new(Mutex::new(Vec::new()));
+        let report = failed.clone();
+        let readings = Readings(vec!['a', 'x', 'b'].into_iter());
+        let handle = sensor.drive_from(readings, move |q, _| report.lock().unwrap().push(*q));
+
+        assert_eq!(handle.join().await.unwrap().unwrap(), 2);
+        assert_eq!(*failed.lock().unwrap(), vec!['x']);
+        assert_eq!(motor.recv_fiber().await.unwrap(), 1);
+        assert_eq!(motor.recv_fiber().await.unwrap(), 2);
+    }
+}
@@ -0,0 +1,233 @@
+
+use std::net::SocketAddr;
+use tokio::net::UdpSocket;
+use std::collections::HashMap;
+use std::any::{ Any, TypeId };
+use std::panic::{ catch_unwind, AssertUnwindSafe };
+
+use crate::error::{ BuildError, CommunicationError, ConfigError };
+use super::id::TractId;
+use super::motor::DEFAULT_RECV_BUFFER_LEN;
+
+
+/// Type-erased behavior, paired with the argument type it expects.
+struct DynFiber<R> {
+    arg_type: TypeId,
+    behavior: Box<dyn Fn(Box<dyn Any>) -> Option<R>>
+}
+
+/// Type-erased argument builder, paired with the type it produces.
+struct ArgSource {
+    arg_type: TypeId,
+    build: Box<dyn Fn() -> Box<dyn Any>>
+}
+
+/// A `Motor` whose fibers may each take a different argument type.
+/// Every fiber needs an argument source that builds its argument 
+/// when an impulse arrives; use `validate` after wiring the fibers
+/// to catch any fiber that has no source (or a source of the wrong type).
+/// `R` is the value returned by every behavior.
+pub struct DynMotor<R> {
+
+    /// The corresponding `cajal::io::Output` should be set to share this name.
+    pub tract_name: String,
+    pub(crate) id: TractId,
+
+    /// This should be set up to match the address of the 
+    /// corresponding `Output` to be read from.
+    pub address: SocketAddr,
+    pub(crate) socket: UdpSocket,
+
+    fibers: HashMap<u16, DynFiber<R>>,
+    sources: HashMap<u16, ArgSource>,
+    recv_buffer: tokio::sync::Mutex<Vec<u8>>
+}
+
+impl<R: 'static> DynMotor<R> {
+
+    /// Create a motor socket. Use port '0' to have the system assign a port.
+    /// The socket address will be recorded in the address field.
+    pub async fn new(
+        tract_name: &str,
+        address: SocketAddr
+    ) -> Result<Self, BuildError> {
+
+        let mut motor = DynMotor {
+            tract_name: tract_name.to_owned(),
+            id: TractId::next(),
+            address,
+            socket: UdpSocket::bind(address).await?,
+            fibers: HashMap::new(),
+            sources: HashMap::new(),
+            recv_buffer: tokio::sync::Mutex::new(vec![0; DEFAULT_RECV_BUFFER_LEN])
+        };
+
+        motor.address = motor.socket.local_addr()?;
+        Ok(motor)
+    }
+
+    /// Unique ID of this tract, distinct from the human-readable name.
+    pub fn tract_id(&self) -> TractId { self.id }
+
+    /// Maps a neurotransmission signal to a behavior taking argument `A`.
+    /// NOTE: Overwrites existing impulse (fiber ID) key without checking.
+    pub fn add_fiber<A: 'static>(
+        &mut self, 
+        impulse: u16, 
+        behavior: impl Fn(A) -> R + 'static
+    ) {
+
+        let behavior = move |args: Box<dyn Any>| {
+            args.downcast::<A>().ok().map(|args| behavior(*args))
+        };
+
+        self.fibers.insert(impulse, DynFiber {
+            arg_type: TypeId::of::<A>(),
+            behavior: Box::new(behavior)
+        });
+    }
+
+    /// Maps a fiber to a behavior with its own return type `O`,
+    /// converted to the motor's `R` by `output` when it fires.
+    /// NOTE: Overwrites existing impulse (fiber ID) key without checking.
+    pub fn add_mapped_fiber<A: 'static, O: 'static>(
+        &mut self,
+        impulse: u16,
+        behavior: impl Fn(A) -> O + 'static,
+        output: impl Fn(O) -> R + 'static
+    ) {
+
+        self.add_fiber(impulse, move |args: A| output(behavior(args)));
+    }
+
+    /// Maps a fiber to a behavior and the source of its argument in one step,
+    /// so the pairing cannot be left incomplete.
+    /// NOTE: Overwrites existing impulse (fiber ID) keys without checking.
+    pub fn add_sourced_fiber<A: 'static>(
+        &mut self,
+        impulse: u16,
+        source: impl Fn() -> A + 'static,
+        behavior: impl Fn(A) -> R + 'static
+    ) {
+
+        self.add_fiber(impulse, behavior);
+        self.set_arg_source(impulse, source);
+    }
+
+    /// Sets the function that builds the argument for a fiber's behavior.
+    /// NOTE: Overwrites existing impulse (fiber ID) key without checking.
+    pub fn set_arg_source<A: 'static>(
+        &mut self, 
+        impulse: u16, 
+        source: impl Fn() -> A + 'static
+    ) {
+
+        let build = move || Box::new(source()) as Box<dyn Any>;
+
+        self.sources.insert(impulse, ArgSource {
+            arg_type: TypeId::of::<A>(),
+            build: Box::new(build)
+        });
+    }
+
+    /// Checks that every fiber has an argument source of the expected type.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+
+        let mut missing: Vec<u16> = self.fibers.iter()
+            .filter(|(fid, fiber)| match self.sources.get(fid) {
+                Some(source) => source.arg_type != fiber.arg_type,
+                None => true
+            })
+            .map(|(fid, _)| *fid)
+            .collect();
+
+        if missing.is_empty() { 
+            Ok(()) 
+        } else {
+            missing.sort();
+            Err(ConfigError::MissingArgSources(missing))
+        }
+    }
+
+    /// Receives NeuronId messages and executes the corresponding function
+    /// with an argument built by that fiber's source.
+    /// NOTE: Batched impulses (`Sensor::send_impulses`) are not supported 
+    /// and are discarded.
+    pub async fn recv_impulse(&self) -> Result<R, CommunicationError> {
+
+        let mut buffer = self.recv_buffer.lock().await;
+        let impulse = loop {
+            let n_bytes = self.socket.recv(&mut buffer).await?;
+            if let Some(fiber) = crate::wire::decode_fiber(&buffer[..n_bytes])? { 
+                break fiber 
+            }
+        };
+
+        let Some(fiber) = self.fibers.get(&impulse) else {
+            return Err(CommunicationError::UnrecognizedImpulse(impulse))
+        };
+
+        let unusable = || ConfigError::MissingArgSources(vec![impulse]);
+        let source = self.sources.get(&impulse).ok_or_else(unusable)?;
+        let args = (source.build)();
+        let result = catch_unwind(AssertUnwindSafe(|| (fiber.behavior)(args)))
+            .map_err(|_| CommunicationError::BehaviorPanicked(impulse))?
+            .ok_or_else(unusable)?;
+        Ok(result)
+    }
+
+}
+
+
+use cajal_cx::tract::{ Tract, receiver::TractReceiver };
+
+impl<R> Tract for DynMotor<R> {
+    fn tract_name(&self) -> &str { &self.tract_name }
+    fn num_fibers(&self) -> usize { self.fibers.len() }
+    fn tract_address(&self) -> SocketAddr { self.address.clone() }
+}
+
+impl<R> TractReceiver for DynMotor<R> {}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::error::ConfigError;
+    use crate::types::Sensor;
+    use super::DynMotor;
+
+    #[tokio::test]
+    async fn validate_reports_fibers_without_matching_sources() {
+
+        let mut motor: DynMotor<String> = DynMotor::new("dyn", "127.0.0.1:0".parse().unwrap()).await.unwrap();
+        motor.add_sourced_fiber(1, || 3u8, |x: u8| x.to_string());
+        motor.add_fiber(2, |s: String| s);
+        motor.add_fiber(3, |x: f32| x.to_string());
+        motor.set_arg_source(3, || 1u8);
+
+        match motor.validate() {
+            Err(ConfigError::MissingArgSources(missing)) => assert_eq!(missing, vec![2, 3]),
+            other => panic!("unexpected {other:?}")
+        }
+
+        motor.set_arg_source(2, String::new);
+        motor.set_arg_source(3, || 1.5f32);
+        assert!(motor.validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn mapped_fibers_convert_their_output() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let mut motor: DynMotor<String> = DynMotor::new("mapped", any).await.unwrap();
+        motor.add_mapped_fiber(1, |x: u8| x * 2, |x: u8| x.to_string());
+        motor.set_arg_source(1, || 21u8);
+        let sensor: Sensor<char> = Sensor::connected("mapped", any, motor.address).await.unwrap()
+            .with_spectrum([('a', 1)]);
+
+        sensor.send_impulse(&'a').await.unwrap();
+        assert_eq!(motor.recv_impulse().await.unwrap(), "42");
+    }
+}
@@ -0,0 +1,99 @@
+
+//! Proprioception: a motor reporting the results of its behaviors 
+//! back into the Complex through a sensor, closing the sensorimotor loop.
+
+use std::hash::Hash;
+use std::sync::Arc;
+use std::sync::atomic::{ AtomicU64, Ordering };
+
+use crate::transport::Transport;
+use super::{ Sensor, Motor };
+use super::schedule::TrainHandle;
+
+
+/// The feedback path attached to a motor.
+pub(crate) struct Feedback<R> {
+    send: Box<dyn Fn(u16, &R) -> bool + Send + Sync>,
+    dropped: AtomicU64,
+    _driver: TrainHandle
+}
+
+impl<R> Feedback<R> {
+
+    pub(crate) fn report(&self, fiber: u16, result: &R) {
+
+        if !(self.send)(fiber, result) { self.dropped.fetch_add(1, Ordering::Relaxed); }
+    }
+}
+
+impl<B: Fn(A) -> R, A, R, T: Transport> Motor<B, A, R, T> {
+
+    /// After every behavior runs, summarize its fiber ID and result as a quantum
+    /// with `encoder` and send it through `sensor` back into the Complex.
+    /// `None` from the encoder sends nothing. Feedback is queued (up to `capacity`)
+    /// and sent by a driver task owned by the motor, so behaviors never wait on it;
+    /// when the queue is full, feedback is dropped and counted in `feedback_dropped`.
+    /// Must be called within a runtime. Replaces any previous feedback path.
+    pub fn set_feedback<Q, S>(
+        &mut self,
+        sensor: &Arc<Sensor<Q, S>>,
+        capacity: usize,
+        encoder: impl Fn(u16, &R) -> Option<Q> + Send + Sync + 'static
+    )
+    where
+        Q: Hash + Eq + Send + Sync + 'static,
+        S: Transport + Send + Sync + 'static
+    {
+
+        let (queue, driver) = sensor.channel(capacity);
+        let send = move |fiber: u16, result: &R| match encoder(fiber, result) {
+            Some(quantum) => queue.try_send(quantum).is_ok(),
+            None => true
+        };
+
+        self.feedback = Some(Feedback { 
+            send: Box::new(send), 
+            dropped: AtomicU64::new(0), 
+            _driver: driver 
+        });
+    }
+
+    /// Detach the feedback path, stopping its driver.
+    pub fn clear_feedback(&mut self) { self.feedback = None }
+
+    /// Number of results that could not be fed back because the queue was full.
+    pub fn feedback_dropped(&self) -> u64 {
+
+        self.feedback.as_ref().map_or(0, |feedback| feedback.dropped.load(Ordering::Relaxed))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+
+    #[tokio::test(start_paused = true)]
+    async fn results_are_fed_back_through_the_sensor() {
+
+        let (_, b) = ChannelTransport::pair(1);
+        let (c, d) = ChannelTransport::pair(4);
+        let mut motor: Motor<fn(u8) -> u8, u8, u8, _> = Motor::in_process("arm", b).with_fiber(1, |x| x);
+        let sensor: Sensor<char, _> = Sensor::in_process("proprioception", c).with_spectrum([('m', 7)]);
+        let cortex: Motor<fn(()) -> u8, (), u8, _> = Motor::in_process("proprioception", d).with_fiber(7, |_| 7);
+        motor.set_feedback(&Arc::new(sensor), 1, |_, moved| (*moved > 0).then_some('m'));
+
+        motor.dispatch(1, 0).unwrap();
+        motor.dispatch(1, 3).unwrap();
+        motor.dispatch(1, 4).unwrap();
+        assert_eq!(motor.feedback_dropped(), 1);
+
+        assert_eq!(cortex.recv_impulse(()).await.unwrap(), 7);
+        assert!(cortex.recv_impulse_timeout((), Duration::from_secs(1)).await.is_err());
+    }
+}
@@ -0,0 +1,173 @@
+
+//! Optional handshake to catch fiber-ID mismatches at connect time.
+//!
+//! Protocol (both sides must opt in):
+//! 1. The sensor connects and sends its sorted set of fiber IDs,
+//!    as an `ImpulseFrame::Handshake` (tag byte `0xFE`).
+//! 2. The motor, waiting in `accept_handshake`, replies to the sender 
+//!    with its own sorted set of fiber IDs in the same frame type.
+//! 3. The sensor fails with `ConfigError::FiberIdMismatch` if it can send
+//!    any fiber ID the motor has no behavior for. Extra motor fibers are fine.
+//!
+//! Both frames carry the sender's `WIRE_VERSION`. A motor answers an offer
+//! of any version with its own, and a sensor that receives a reply of 
+//! another version fails with `UnsupportedVersion` instead of timing out.
+//!
+//! The sensor retries its offer until `HANDSHAKE_TIMEOUT` elapses,
+//! so the motor may start listening slightly late.
+
+use std::net::SocketAddr;
+use std::hash::Hash;
+use std::time::Duration;
+use tokio::time::{ timeout, Instant };
+
+use crate::error::{ BuildError, CommunicationError, ConfigError };
+use crate::wire::{ ImpulseFrame, HANDSHAKE_TAG, peek_header };
+use super::{ Sensor, Motor };
+
+
+/// Total time the sensor waits for the motor's reply.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+
+const HANDSHAKE_RETRY: Duration = Duration::from_millis(200);
+
+/// Largest UDP payload, and so the largest handshake frame either side can receive.
+const MAX_HANDSHAKE_LEN: usize = 65507;
+
+/// Whether a datagram is a handshake frame of any wire version.
+fn is_handshake(datagram: &[u8]) -> bool {
+
+    matches!(peek_header(datagram), Some((_, tag)) if tag == HANDSHAKE_TAG)
+}
+
+impl<Q: Hash + Eq> Sensor<Q> {
+
+    /// Every fiber ID this sensor can send, sorted.
+    fn fiber_ids(&self) -> Vec<u16> {
+
+        let mut fibers: Vec<u16> = self.spectrum.values().copied()
+            .chain(self.weighted.values().flat_map(|w| w.fibers().iter().copied()))
+            .collect();
+        fibers.sort_unstable();
+        fibers.dedup();
+        fibers
+    }
+
+    /// Connect to `peer` and exchange fiber IDs with a motor 
+    /// waiting in `Motor::accept_handshake`. See the module docs for the protocol.
+    pub async fn connect_with_handshake(
+        &mut self, 
+        peer: &SocketAddr
+    ) -> Result<(), BuildError> {
+
+        self.connect(peer).await?;
+
+        let offer = self.codec.encode(&ImpulseFrame::Handshake(self.fiber_ids()))
+            .map_err(BuildError::HandshakeFailed)?;
+
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        let mut buffer = vec![0u8; MAX_HANDSHAKE_LEN];
+
+        let peer_fibers = loop {
+            if Instant::now() >= deadline {
+                return Err(CommunicationError::Timeout.into())
+            }
+            self.socket.send(&offer).await?;
+
+            match timeout(HANDSHAKE_RETRY, self.socket.recv(&mut buffer)).await {
+                Ok(received) => {
+                    let n_bytes = received?;
+                    match self.codec.decode(&buffer[..n_bytes]) {
+                        Ok(ImpulseFrame::Handshake(fibers)) => break fibers,
+                        Err(e @ CommunicationError::UnsupportedVersion(_)) 
+                        if is_handshake(&buffer[..n_bytes]) => return Err(e.into()),
+                        _ => continue
+                    }
+                },
+                Err(_) => continue
+            }
+        };
+
+        let unhandled: Vec<u16> = self.fiber_ids().into_iter()
+            .filter(|fid| peer_fibers.binary_search(fid).is_err())
+            .collect();
+
+        if unhandled.is_empty() { 
+            Ok(()) 
+        } else { 
+            Err(ConfigError::FiberIdMismatch(unhandled).into()) 
+        }
+    }
+}
+
+impl<B: Fn(A) -> R, A, R> Motor<B, A, R> {
+
+    /// Wait for a sensor's handshake and reply with this motor's fiber IDs.
+    /// Other datagrams received while waiting are discarded.
+    /// Returns the address of the sensor that shook hands.
+    pub async fn accept_handshake(&self) -> Result<SocketAddr, CommunicationError> {
+
+        let mut fibers: Vec<u16> = self.fibers.keys().copied().collect();
+        fibers.sort_unstable();
+        let reply = self.codec.encode(&ImpulseFrame::Handshake(fibers))?;
+
+        let mut buffer = vec![0u8; MAX_HANDSHAKE_LEN];
+        loop {
+            let (n_bytes, sender) = self.socket.recv_from(&mut buffer).await?;
+            match self.codec.decode(&buffer[..n_bytes]) {
+                Ok(ImpulseFrame::Handshake(_)) => {
+                    self.socket.send_to(&reply, sender).await?;
+                    return Ok(sender)
+                },
+                // Tell the sensor which version this motor speaks, and keep
+                // waiting in case it retries with a compatible one.
+                Err(CommunicationError::UnsupportedVersion(_)) if is_handshake(&buffer[..n_bytes]) => {
+                    self.socket.send_to(&reply, sender).await?;
+                },
+                _ => continue
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use crate::error::{ BuildError, ConfigError };
+    use crate::types::{ Sensor, FnMotor };
+
+    #[tokio::test]
+    async fn reports_fibers_the_motor_cannot_handle() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let motor = Arc::new(FnMotor::new("shake", any).await.unwrap()
+            .with_fiber(1, |_: ()| ())
+            .with_fiber(3, |_: ()| ()));
+
+        let accepting = tokio::spawn({
+            let motor = motor.clone();
+            async move { motor.accept_handshake().await }
+        });
+        let mut matching: Sensor<char> = Sensor::new("shake", any).await.unwrap()
+            .with_spectrum([('a', 1), ('c', 3)]);
+        matching.connect_with_handshake(&motor.address).await.unwrap();
+        assert_eq!(accepting.await.unwrap().unwrap(), matching.socket.local_addr().unwrap());
+
+        let accepting = tokio::spawn({
+            let motor = motor.clone();
+            async move { motor.accept_handshake().await }
+        });
+        let mut extra: Sensor<char> = Sensor::new("shake", any).await.unwrap()
+            .with_spectrum([('a', 1), ('b', 2), ('d', 4)]);
+        match extra.connect_with_handshake(&motor.address).await {
+            Err(BuildError::Misconfigured(ConfigError::FiberIdMismatch(fibers))) => {
+                assert_eq!(fibers, vec![2, 4])
+            },
+            other => panic!("unexpected {other:?}")
+        }
+        accepting.await.unwrap().unwrap();
+    }
+}
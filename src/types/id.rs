@@ -0,0 +1,113 @@
+
+use std::sync::atomic::{ AtomicU64, Ordering };
+use serde::{ Serialize, Deserialize };
+
+
+/// Stable identifier assigned to every tract when it is constructed.
+/// Unlike `tract_name`, no two tracts in a process share a `TractId`,
+/// so it is safe to key maps, metrics and logs on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TractId(u64);
+
+static NEXT_TRACT_ID: AtomicU64 = AtomicU64::new(0);
+
+impl TractId {
+
+    /// Claim the next unused ID.
+    pub(crate) fn next() -> Self {
+
+        TractId(NEXT_TRACT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// The raw value of the ID.
+    pub fn as_u64(&self) -> u64 { self.0 }
+}
+
+impl std::fmt::Display for TractId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "tract#{}", self.0)
+    }
+}
+
+/// Integer type of a fiber ID on the wire, in the spectrum and in `fibers`.
+/// Matches the `NeuronId` of cajal's Inputs and Outputs.
+pub type FiberWidth = u16;
+
+/// A fiber ID, i.e. the `NeuronId` an impulse carries between a tract 
+/// and its Input or Output, kept apart from other integers.
+/// Receptor and fiber methods accept anything convertible into one,
+/// so raw IDs (and cajal's `NeuronId`, which is a `FiberWidth`) still work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FiberId(pub FiberWidth);
+
+impl FiberId {
+
+    /// The largest fiber ID the wire format can carry.
+    pub const MAX: FiberId = FiberId(FiberWidth::MAX);
+
+    /// The raw value of the ID.
+    pub const fn raw(&self) -> FiberWidth { self.0 }
+}
+
+impl From<FiberWidth> for FiberId {
+    fn from(id: FiberWidth) -> Self { FiberId(id) }
+}
+
+impl From<FiberId> for FiberWidth {
+    fn from(id: FiberId) -> Self { id.0 }
+}
+
+impl std::fmt::Display for FiberId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "fiber#{}", self.0)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+    use super::{ TractId, FiberId, FiberWidth };
+
+    #[test]
+    fn every_tract_gets_a_distinct_id() {
+
+        let (a, b) = ChannelTransport::pair(1);
+        let sensor: Sensor<u8, _> = Sensor::in_process("same", a);
+        let motor: Motor<fn(()), (), (), _> = Motor::in_process("same", b);
+
+        assert_ne!(sensor.tract_id(), motor.tract_id());
+        assert!(TractId::next() > motor.tract_id().max(sensor.tract_id()));
+    }
+
+    #[test]
+    fn displays_with_prefix() {
+
+        let id = TractId::next();
+        assert_eq!(id.to_string(), format!("tract#{}", id.as_u64()));
+    }
+
+    #[test]
+    fn fiber_ids_convert_to_and_from_raw_ids() {
+
+        let raw: FiberWidth = 7;
+        assert_eq!(FiberId::from(raw), FiberId(7));
+        assert_eq!(FiberWidth::from(FiberId(7)), raw);
+        assert_eq!(FiberId(7).to_string(), "fiber#7");
+
+        let (a, b) = ChannelTransport::pair(1);
+        let mut sensor: Sensor<char, _> = Sensor::in_process("ids", a);
+        let mut motor: Motor<fn(()) -> u8, (), u8, _> = Motor::in_process("ids", b);
+        sensor.add_receptor('a', FiberId(1));
+        sensor.add_receptor('b', 2);
+        motor.add_fiber(FiberId(1), |_| 1);
+        motor.add_fiber(raw, |_| 7);
+
+        assert_eq!(sensor.spectrum.get(&'a'), Some(&1));
+        assert_eq!(motor.dispatch(1, ()).unwrap(), 1);
+        assert_eq!(motor.dispatch(7, ()).unwrap(), 7);
+    }
+}
@@ -0,0 +1,87 @@
+
+use tokio::task::JoinHandle;
+
+use crate::error::CommunicationError;
+use super::Motor;
+
+
+/// Runs a `Motor` whose behaviors are not `Send` (e.g. closures capturing `Rc`).
+/// The receive loop is spawned with `tokio::task::spawn_local`,
+/// so `run_local` MUST be called from within a `tokio::task::LocalSet`,
+/// otherwise it will panic.
+pub struct LocalMotor<B: Fn(A) -> R, A, R> {
+    pub motor: Motor<B, A, R>
+}
+
+impl<B: Fn(A) -> R, A, R> LocalMotor<B, A, R> {
+
+    /// Wrap a motor to run on the current thread.
+    pub fn new(motor: Motor<B, A, R>) -> Self {
+
+        LocalMotor { motor }
+    }
+
+    /// Spawn the receive loop on the current `LocalSet`.
+    /// `args` is called to produce the argument for each impulse,
+    /// and `sink` receives the result of every dispatch.
+    /// The loop runs until the returned handle is aborted, 
+    /// or until the motor's cancellation token is cancelled.
+    pub fn run_local<F, S>(self, mut args: F, mut sink: S) -> JoinHandle<()>
+    where
+        B: 'static,
+        A: 'static,
+        R: 'static,
+        F: FnMut() -> A + 'static,
+        S: FnMut(Result<R, CommunicationError>) + 'static
+    {
+
+        tokio::task::spawn_local(async move {
+            loop {
+                let fiber = tokio::select! {
+                    _ = self.motor.cancel.cancelled() => break,
+                    fiber = self.motor.recv_fiber() => fiber
+                };
+                sink(fiber.and_then(|fiber| self.motor.dispatch(fiber, args())))
+            }
+        })
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::rc::Rc;
+    use std::cell::Cell;
+    use tokio::task::LocalSet;
+
+    use crate::types::{ Sensor, Motor };
+    use super::LocalMotor;
+
+    #[tokio::test]
+    async fn runs_non_send_behaviors() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let count = Rc::new(Cell::new(0));
+        let counter = count.clone();
+        let motor: Motor<Box<dyn Fn(())>, (), ()> = Motor::new("local", any).await.unwrap()
+            .with_fiber(1, Box::new(move |_| counter.set(counter.get() + 1)));
+
+        let mut sensor: Sensor<u8> = Sensor::new("local", any).await.unwrap();
+        sensor.connect(&motor.address).await.unwrap();
+        sensor.add_receptor(0, 1);
+
+        LocalSet::new().run_until(async move {
+            let (sink, mut results) = tokio::sync::mpsc::unbounded_channel();
+            let handle = LocalMotor::new(motor)
+                .run_local(|| (), move |result| { let _ = sink.send(result); });
+
+            sensor.send_impulse(&0).await.unwrap();
+            assert!(results.recv().await.unwrap().is_ok());
+            handle.abort();
+        }).await;
+
+        assert_eq!(count.get(), 1);
+    }
+}
@@ -0,0 +1,204 @@
+
+//! Packet loss estimation from sequence numbers.
+//! Enable sequencing on the `Sensor` so every impulse carries a sequence 
+//! number; the `Motor` then tracks which numbers never arrived.
+//! Gaps that are later filled (reordering) are not counted as loss.
+//! Periodic `Sensor::send_report` frames tell the motor how many impulses
+//! were actually sent, which also accounts for loss at the tail.
+//! A sensor restart (or `Sensor::reset_state`) is detected by a sequence
+//! number far behind the expected one, or by a report counting fewer 
+//! impulses than the last, and tracking starts over.
+
+use std::collections::BTreeSet;
+use std::hash::Hash;
+use std::sync::MutexGuard;
+use std::sync::atomic::Ordering;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use crate::wire::ImpulseFrame;
+use super::{ Sensor, Motor };
+
+
+/// Gaps wider than this are counted as lost without tracking each number,
+/// so a sensor restart or corrupt sequence can't exhaust memory.
+const MAX_TRACKED_GAP: u64 = 1 << 16;
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LossTracker {
+    next_expected: u64,
+    received: u64,
+    missing: BTreeSet<u64>,
+    untracked_lost: u64,
+    reported_sent: Option<u64>
+}
+
+impl LossTracker {
+
+    pub(crate) fn observe(&mut self, seq: u64) {
+
+        if self.next_expected.saturating_sub(seq) > MAX_TRACKED_GAP {
+            *self = LossTracker::default();
+        }
+
+        if seq >= self.next_expected {
+            let gap = seq - self.next_expected;
+            if gap > MAX_TRACKED_GAP {
+                self.untracked_lost += gap;
+            } else {
+                self.missing.extend(self.next_expected..seq);
+            }
+            self.next_expected = seq + 1;
+            self.received += 1;
+        } else if self.missing.remove(&seq) {
+            self.received += 1;
+        }
+        // Otherwise a duplicate, which is ignored.
+    }
+
+    pub(crate) fn report(&mut self, sent: u64) {
+
+        // Reports only shrink when the sensor has restarted. What arrived 
+        // since the restart is unknown, so track from the reported count.
+        if self.reported_sent.is_some_and(|reported| sent < reported) {
+            *self = LossTracker { next_expected: sent, received: sent, ..LossTracker::default() };
+            return
+        }
+        self.reported_sent = Some(sent);
+    }
+
+    /// Highest sequence number up to which nothing tracked is missing.
+    pub(crate) fn contiguous_through(&self) -> Option<u64> {
+
+        let frontier = self.missing.first().copied().unwrap_or(self.next_expected);
+        frontier.checked_sub(1)
+    }
+
+    pub(crate) fn lost(&self) -> u64 { self.missing.len() as u64 + self.untracked_lost }
+
+    pub(crate) fn loss_rate(&self) -> f64 {
+
+        // Reports may be older than the latest impulse received.
+        let sent = self.reported_sent.unwrap_or(0).max(self.next_expected);
+        if sent == 0 { return 0.0 }
+
+        let lost = match self.reported_sent {
+            Some(_) => sent.saturating_sub(self.received),
+            None => self.lost()
+        };
+        lost as f64 / sent as f64
+    }
+}
+
+
+impl<B: Fn(A) -> R, A, R, T: Transport> Motor<B, A, R, T> {
+
+    pub(crate) fn loss(&self) -> MutexGuard<'_, LossTracker> {
+        self.loss.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Sequenced impulses that have not arrived (and have not been 
+    /// filled in late by reordering).
+    pub fn lost_count(&self) -> u64 { self.loss().lost() }
+
+    /// Fraction of sequenced impulses lost, from 0.0 to 1.0.
+    /// Uses the sensor's reported total when available, 
+    /// otherwise the highest sequence number seen.
+    /// NOTE: Impulses still in flight are counted as lost until they arrive.
+    pub fn estimated_loss_rate(&self) -> f64 { self.loss().loss_rate() }
+}
+
+
+impl<Q: Hash + Eq, T: Transport> Sensor<Q, T> {
+
+    /// Attach a sequence number to every impulse so the `Motor` 
+    /// can detect loss. NOTE: Only a phantom_limb `Motor` understands
+    /// sequenced impulses, so leave this off when sending to other peers.
+    pub fn set_sequenced(&mut self, sequenced: bool) {
+
+        self.sequenced = sequenced;
+    }
+
+    /// Number of sequenced impulses sent so far.
+    pub fn sent_count(&self) -> u64 { self.next_seq.load(Ordering::Relaxed) }
+
+    /// Tell the peer how many sequenced impulses have been sent,
+    /// so it can account for loss accurately. Call this periodically.
+    pub async fn send_report(&self) -> Result<(), CommunicationError> {
+
+        let report = self.codec.encode(&ImpulseFrame::Report { sent: self.sent_count() })?;
+        self.socket.send(&report).await?;
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+    use super::{ LossTracker, MAX_TRACKED_GAP };
+
+    #[test]
+    fn counts_gaps_that_are_never_filled() {
+
+        let mut tracker = LossTracker::default();
+        for seq in [0, 1, 4, 2, 5] { tracker.observe(seq) }
+        assert_eq!(tracker.lost(), 1);
+        assert_eq!(tracker.contiguous_through(), Some(2));
+
+        tracker.report(10);
+        assert_eq!(tracker.loss_rate(), 0.5);
+    }
+
+    #[test]
+    fn large_backward_jump_starts_over() {
+
+        let mut tracker = LossTracker::default();
+        tracker.observe(MAX_TRACKED_GAP + 10);
+        tracker.observe(0);
+        tracker.observe(1);
+        assert_eq!(tracker.lost(), 0);
+        assert_eq!(tracker.contiguous_through(), Some(1));
+    }
+
+    #[test]
+    fn shrinking_report_starts_over() {
+
+        let mut tracker = LossTracker::default();
+        for seq in 0..10 { tracker.observe(seq) }
+        tracker.report(10);
+
+        // The sensor restarted and has sent 2 impulses since.
+        tracker.observe(0);
+        tracker.report(2);
+        for seq in [2, 4] { tracker.observe(seq) }
+        tracker.report(5);
+        assert_eq!(tracker.lost(), 1);
+        assert_eq!(tracker.loss_rate(), 0.2);
+    }
+
+    #[tokio::test]
+    async fn motor_estimates_loss_from_reports() {
+
+        let (a, b) = ChannelTransport::pair(16);
+        let mut sensor: Sensor<char, _> = Sensor::in_process("lossy", a).with_spectrum([('a', 1)]);
+        sensor.set_sequenced(true);
+        let motor: Motor<fn(()), (), (), _> = Motor::in_process("lossy", b).with_fiber(1, |_| ());
+
+        for _ in 0..4 { sensor.send_impulse(&'a').await.unwrap() }
+        // One impulse is lost on the way.
+        sensor.next_seq.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        for _ in 0..3 { sensor.send_impulse(&'a').await.unwrap() }
+        sensor.send_report().await.unwrap();
+
+        for _ in 0..7 { motor.recv_impulse(()).await.unwrap() }
+        // The report is consumed by the next receive, which then waits.
+        let _ = tokio::time::timeout(std::time::Duration::from_millis(10), motor.recv_fiber()).await;
+
+        assert_eq!(sensor.sent_count(), 8);
+        assert_eq!(motor.lost_count(), 1);
+        assert_eq!(motor.estimated_loss_rate(), 1.0 / 8.0);
+    }
+}
@@ -1,6 +1,58 @@
 
+pub mod id;
 pub mod sensor;
 pub mod motor;
-pub use { sensor::Sensor, motor::Motor };
-
+pub mod local_motor;
+pub mod dyn_motor;
+pub mod split;
+pub mod spectrum_sync;
+pub mod collector;
+pub mod queued;
+pub mod concurrent;
+pub mod snapshot;
+pub mod weighted;
+pub mod socket_options;
+pub mod context;
+pub mod loss;
+pub mod handshake;
+pub mod async_motor;
+pub mod motor_set;
+pub mod replay;
+pub mod ack;
+pub mod barrier;
+pub mod reset;
+pub mod batch;
+pub mod drain;
+pub mod schedule;
+pub mod drive;
+pub mod builder;
+pub mod burst;
+pub mod serve;
+pub mod stateful;
+pub mod dead_letter;
+pub mod stream;
+pub mod close;
+pub mod feedback;
+pub use { 
+    id::{ TractId, FiberId, FiberWidth }, 
+    sensor::Sensor, 
+    motor::{ Motor, FnMotor, BoxMotor }, 
+    local_motor::LocalMotor,
+    dyn_motor::DynMotor,
+    split::{ SpectrumHandle, SenderHalf },
+    collector::{ Collector, FiberCount },
+    queued::{ QueuedSensor, QueueStats, LatencyHistogram },
+    snapshot::{ SensorSnapshot, MotorSnapshot },
+    context::TractContext,
+    async_motor::AsyncMotor,
+    motor_set::MotorSet,
+    replay::ImpulseLog,
+    barrier::TickBarrier,
+    schedule::{ SpikeTrain, TrainHandle },
+    builder::{ SensorBuilder, MotorBuilder },
+    serve::MotorHandle,
+    stateful::stateful,
+    dead_letter::{ DeadLetter, DeadLetterReason },
+    stream::MotorStream
+};
 
@@ -1,33 +1,88 @@
 
 use std::net::SocketAddr;
 use tokio::net::UdpSocket;
-use std::collections::HashMap;
+use std::collections::{ HashMap, VecDeque };
+use std::panic::{ catch_unwind, AssertUnwindSafe };
 
-use crate::error::{ BuildError, CommunicationError };
+use crate::error::{ BuildError, CommunicationError, ConfigError };
+use crate::transport::Transport;
+use super::id::{ TractId, FiberId, FiberWidth };
+use crate::bloom::BloomFilter;
+use crate::registry::BehaviorRegistry;
+use crate::wire::ImpulseFrame;
+use crate::codec::{ ImpulseCodec, BincodeCodec };
+use super::loss::LossTracker;
+use super::ack::CumulativeAck;
+use super::dead_letter::{ DeadLetterQueue, DeadLetterReason };
+use super::feedback::Feedback;
+use crate::metrics::{ Metrics, TractMetrics, ShutdownHook };
+use tokio_util::sync::CancellationToken;
 
 
 /// Handles the behavioral output of a bionic neural network made with cajal.
 /// When it receives a NeuronId, it executes the corresponding function.
 /// `B` is the behavior function pointer, `A` is the argument for the function,
 /// and `R` is the value returned by the function.
-pub struct Motor<B: Fn(A) -> R, A, R> {
+/// `T` is the transport impulses arrive on, UDP by default.
+pub struct Motor<B: Fn(A) -> R, A, R, T: Transport = UdpSocket> {
 
     /// The corresponding `cajal::io::Output` should be set to share this name.
     pub tract_name: String,
+    pub(crate) id: TractId,
 
     /// This should be set up to match the address of the 
     /// corresponding `Output` to be read from.
     pub address: SocketAddr,
-    pub(crate) socket: UdpSocket,
+    pub(crate) socket: T,
 
     /// Maps each fiber ID (`u16`) to a "behavior" function to execute 
     /// every time the ID is received.
     /// These should correspond to those in `Output.senders`.
     /// The sender IDs can be retrieved with the `Output::sender_ids` method. 
-    pub fibers: HashMap<u16, B>,
-    phantom_data: std::marker::PhantomData<(A, R)>
+    pub fibers: HashMap<FiberWidth, B>,
+
+    /// Registry names of fibers added with `add_named_fiber`.
+    pub(crate) fiber_names: HashMap<u16, String>,
+    /// Names of behaviors added with `add_behavior`, for inspection.
+    pub(crate) behavior_names: HashMap<u16, String>,
+    fallback: Option<Box<dyn Fn(u16, A) -> R + Send + Sync>>,
+    pub(crate) feedback: Option<Feedback<R>>,
+    prefilter: Option<BloomFilter>,
+    echo_unrecognized: bool,
+    reject_plain: bool,
+    pub(crate) codec: std::sync::Arc<dyn ImpulseCodec>,
+
+    /// Reused for every datagram received; see `set_recv_buffer_len`.
+    pub(crate) recv_buffer: tokio::sync::Mutex<Vec<u8>>,
+    pub(crate) loss: std::sync::Mutex<LossTracker>,
+    pub(crate) acks: std::sync::Mutex<Option<CumulativeAck>>,
+
+    /// Fibers from a batch datagram not yet returned by `recv_fiber`.
+    pub(crate) pending: std::sync::Mutex<VecDeque<u16>>,
+    pub(crate) dead_letters: std::sync::Mutex<Option<DeadLetterQueue>>,
+    pub(crate) metrics: Metrics,
+    pub(crate) shutdown: ShutdownHook,
+    pub(crate) cancel: CancellationToken,
+    /// Set when a sensor announces it is closing.
+    pub(crate) peer_closed: std::sync::atomic::AtomicBool,
+    /// Lookups in `fibers` by `behavior`, to test the prefilter's fast path.
+    #[cfg(test)]
+    lookups: std::sync::atomic::AtomicUsize,
+    phantom_data: std::marker::PhantomData<fn(A) -> R>
 } 
 
+/// Length of a motor's receive buffer unless configured otherwise:
+/// enough for the largest UDP datagram, so nothing is truncated.
+pub const DEFAULT_RECV_BUFFER_LEN: usize = 65536;
+
+/// A `Motor` whose behaviors are plain function pointers.
+/// Non-capturing closures coerce to `fn(A) -> R`, 
+/// so different closures can share one motor.
+pub type FnMotor<A, R> = Motor<fn(A) -> R, A, R>;
+
+/// A `Motor` whose behaviors are boxed closures, which may capture state.
+pub type BoxMotor<A, R> = Motor<Box<dyn Fn(A) -> R + Send + Sync>, A, R>;
+
 impl<B: Fn(A) -> R, A, R> Motor<B, A, R> {
 
     /// Create a motor socket. Use port '0' to have the system assign a port.
@@ -37,53 +92,727 @@ impl<B: Fn(A) -> R, A, R> Motor<B, A, R> {
         address: SocketAddr
     ) -> Result<Self, BuildError> {
 
-        let mut motor = Motor {
+        let socket = UdpSocket::bind(address).await?;
+        let mut motor = Motor::with_transport(tract_name, address, socket);
+
+        motor.address = motor.socket.local_addr()?;
+        Ok(motor)
+    }
+
+    /// Bind a motor that only accepts datagrams from `expected_peer`.
+    /// NOTE: Filtering is done by the OS via a connected UDP socket,
+    /// so datagrams from other sources are discarded before `recv_impulse`.
+    pub async fn listening_from(
+        tract_name: &str,
+        bind: SocketAddr,
+        expected_peer: SocketAddr
+    ) -> Result<Self, BuildError> {
+
+        let motor = Motor::new(tract_name, bind).await?;
+        motor.socket.connect(expected_peer).await?;
+        Ok(motor)
+    }
+}
+
+impl<B: Fn(A) -> R, A, R, T: Transport> Motor<B, A, R, T> {
+
+    /// Build a motor that receives on an already-established transport.
+    /// `address` is recorded as the motor's address.
+    pub fn with_transport(
+        tract_name: &str, 
+        address: SocketAddr, 
+        transport: T
+    ) -> Self {
+
+        Motor {
             tract_name: tract_name.to_owned(),
+            id: TractId::next(),
             address,
-            socket: UdpSocket::bind(address).await?,
+            socket: transport,
             fibers: HashMap::new(),
+            fiber_names: HashMap::new(),
+            behavior_names: HashMap::new(),
+            fallback: None,
+            feedback: None,
+            prefilter: None,
+            echo_unrecognized: false,
+            reject_plain: false,
+            codec: std::sync::Arc::new(BincodeCodec),
+            recv_buffer: tokio::sync::Mutex::new(vec![0; DEFAULT_RECV_BUFFER_LEN]),
+            loss: std::sync::Mutex::new(LossTracker::default()),
+            acks: std::sync::Mutex::new(None),
+            pending: std::sync::Mutex::new(VecDeque::new()),
+            dead_letters: std::sync::Mutex::new(None),
+            metrics: Metrics::default(),
+            shutdown: ShutdownHook::default(),
+            cancel: CancellationToken::new(),
+            peer_closed: std::sync::atomic::AtomicBool::new(false),
+            #[cfg(test)]
+            lookups: std::sync::atomic::AtomicUsize::new(0),
             phantom_data: std::marker::PhantomData
-        };
+        }
+    }
 
-        motor.address = motor.socket.local_addr()?;
-        Ok(motor)
+    /// Unique ID of this tract, distinct from the human-readable name.
+    pub fn tract_id(&self) -> TractId { self.id }
+
+    /// Current counters for this motor.
+    pub fn metrics(&self) -> TractMetrics { self.metrics.snapshot(&self.tract_name) }
+
+    /// End the motor's `serve` and `run_local` loops when `token` is cancelled,
+    /// after the behavior in progress (if any) returns.
+    /// Share one token across tracts to stop a whole limb in one call.
+    /// The motor keeps a child of `token`, so `close` stops only this motor.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+
+        self.cancel = token.child_token();
+    }
+
+    /// The token that stops this motor's loops (a child of
+    /// the one given to `set_cancellation_token`).
+    pub fn cancellation_token(&self) -> &CancellationToken { &self.cancel }
+
+    /// Run `hook` with the final metrics when the motor is dropped,
+    /// including when a task driving it ends. Replaces any previous hook.
+    pub fn on_shutdown(&mut self, hook: impl FnOnce(&TractMetrics) + Send + 'static) {
+
+        self.shutdown.set(&self.tract_name, &self.metrics, Box::new(hook));
     }
 
     /// Maps a neurotransmission signal to a process to be executed.
     /// NOTE: Overwrites existing impulse (fiber ID) key without checking.
-    pub fn add_fiber(&mut self, impulse: u16, behavior: B) {
+    pub fn add_fiber(&mut self, impulse: impl Into<FiberId>, behavior: B) {
+
+        let impulse = impulse.into().raw();
+        self.fibers.insert(impulse, behavior);
+        self.fiber_names.remove(&impulse);
+        self.behavior_names.remove(&impulse);
+        if self.prefilter.is_some() { self.enable_bloom_prefilter() }
+    }
+
+    /// Maps a neurotransmission signal to a process to be executed,
+    /// failing with `AlreadyMapped` if the fiber ID already has one.
+    pub fn try_add_fiber(&mut self, impulse: impl Into<FiberId>, behavior: B) -> Result<(), ConfigError> {
+
+        let impulse = impulse.into().raw();
+        if self.fibers.contains_key(&impulse) {
+            return Err(ConfigError::AlreadyMapped(impulse))
+        }
+        self.add_fiber(impulse, behavior);
+        Ok(())
+    }
+
+    /// Removes the behavior for a fiber ID, returning it.
+    pub fn remove_fiber(&mut self, impulse: u16) -> Option<B> {
+
+        let behavior = self.fibers.remove(&impulse)?;
+        self.fiber_names.remove(&impulse);
+        self.behavior_names.remove(&impulse);
+        if self.prefilter.is_some() { self.enable_bloom_prefilter() }
+        Some(behavior)
+    }
+
+    /// Maps a neurotransmission signal to a behavior from a registry,
+    /// remembering its name so the wiring can be snapshotted.
+    /// NOTE: Overwrites existing impulse (fiber ID) key without checking.
+    pub fn add_named_fiber(
+        &mut self, 
+        impulse: u16, 
+        name: &str, 
+        registry: &BehaviorRegistry<B>
+    ) -> Result<(), ConfigError> 
+    where B: Clone {
+
+        self.add_fiber(impulse, registry.resolve(name)?);
+        self.fiber_names.insert(impulse, name.to_owned());
+        Ok(())
+    }
+
+    /// Chainable `add_fiber`. `A` and `R` are inferred from the behavior,
+    /// so with `FnMotor` no turbofish or annotations are needed:
+    /// ```no_run
+    /// # async fn wheel() -> Result<(), phantom_limb::error::BuildError> {
+    /// use phantom_limb::FnMotor;
+    ///
+    /// let address = "127.0.0.1:0".parse().unwrap();
+    /// let motor = FnMotor::new("wheel", address).await?
+    ///     .with_fiber(1, |x: u8| x + 1)
+    ///     .with_fiber(2, |x: u8| x - 1);
+    /// # Ok(()) }
+    /// ```
+    pub fn with_fiber(mut self, impulse: impl Into<FiberId>, behavior: B) -> Self {
+
+        self.add_fiber(impulse, behavior);
+        self
+    }
+
+    /// Screen incoming impulses with a bloom filter of the registered fibers, 
+    /// so spurious IDs on noisy or hostile links are rejected before 
+    /// the `fibers` map is consulted. The filter has no false negatives;
+    /// its rare false positives simply fall through to the map lookup.
+    /// The filter is rebuilt whenever a fiber is added through `add_fiber`,
+    /// but NOT when the `fibers` field is edited directly.
+    pub fn enable_bloom_prefilter(&mut self) {
+
+        let keys = self.fibers.keys().map(|fid| *fid as u64);
+        self.prefilter = Some(BloomFilter::from_keys(keys));
+    }
+
+    /// Stop screening impulses with the bloom filter.
+    pub fn disable_bloom_prefilter(&mut self) {
+
+        self.prefilter = None;
+    }
+
+    /// When set, an impulse with no behavior is answered with an 
+    /// `ImpulseFrame::Unrecognized` diagnostic sent back to its source,
+    /// which the sensor can read with `recv_unrecognized`.
+    /// Echoes are best-effort: they are dropped if the socket is busy.
+    pub fn set_echo_unrecognized(&mut self, echo: bool) {
+
+        self.echo_unrecognized = echo;
+    }
+
+    /// Run `fallback` with the fiber ID and arguments of any impulse 
+    /// that has no behavior, instead of failing with `UnrecognizedImpulse`.
+    /// Unrecognized impulses are still counted in the metrics (and echoed, if enabled).
+    pub fn set_fallback(&mut self, fallback: impl Fn(u16, A) -> R + Send + Sync + 'static) {
+
+        self.fallback = Some(Box::new(fallback));
+    }
+
+    /// Remove the fallback, so unrecognized impulses fail again.
+    pub fn clear_fallback(&mut self) {
+
+        self.fallback = None;
+    }
+
+    /// Resize the buffer datagrams are received into (at least 2 bytes).
+    /// Datagrams longer than the buffer are truncated by the OS, 
+    /// so only shrink it below `DEFAULT_RECV_BUFFER_LEN` if every 
+    /// sender's frames are known to fit, e.g. plain impulses only.
+    pub fn set_recv_buffer_len(&mut self, len: usize) {
+
+        *self.recv_buffer.get_mut() = vec![0; len.max(2)];
+    }
+
+    /// Deserialize frames with `codec` instead of the default `BincodeCodec`.
+    /// Every sensor sending to this motor MUST use the same codec.
+    pub fn set_codec(&mut self, codec: impl ImpulseCodec + 'static) {
 
-        self.fibers.insert(impulse.clone(), behavior);
+        self.codec = std::sync::Arc::new(codec);
+    }
+
+    /// When set, plain (untagged) impulses fail with `ProtocolMismatch`,
+    /// so stray datagrams are never read as fiber IDs.
+    /// The motor's codec decides what is plain (`ImpulseCodec::is_plain`).
+    /// Pair with `Sensor::set_tagged` on every sender.
+    pub fn set_reject_plain(&mut self, reject: bool) {
+
+        self.reject_plain = reject;
+    }
+
+    /// Send any feedback due to `source` after it delivered `fiber`.
+    pub(crate) fn respond(&self, fiber: u16, source: SocketAddr) {
+
+        self.echo_if_unrecognized(fiber, source);
+        self.acknowledge(source);
+    }
+
+    /// Send the diagnostic for `fiber` to `source` if it has no behavior.
+    pub(crate) fn echo_if_unrecognized(&self, fiber: u16, source: SocketAddr) {
+
+        if !self.echo_unrecognized || self.behavior(fiber).is_some() { return }
+        if let Ok(echo) = self.codec.encode(&ImpulseFrame::Unrecognized(fiber)) {
+            let _ = self.socket.try_send_to(&echo, source);
+        }
     }
 
     /// Receives NeuronId messages and executes the corresponding function.
-    pub async fn recv_impulse(
+    pub async fn recv_impulse(&self, args: A) -> Result<R, CommunicationError> {
+
+        let impulse = self.recv_fiber().await?;
+        self.dispatch(impulse, args)
+    }
+
+    /// Receives an impulse and executes its behavior, building the argument 
+    /// from the impulse's payload (`None` for plain impulses and batches).
+    pub async fn recv_impulse_with(
+        &self,
+        args: impl FnOnce(Option<f32>) -> A
+    ) -> Result<R, CommunicationError> {
+
+        let (impulse, payload) = self.recv_fiber_with().await?;
+        self.dispatch(impulse, args(payload))
+    }
+
+    /// Like `recv_impulse`, failing with `Timeout` if no impulse arrives within `timeout`.
+    /// Only the wait is limited; a behavior that has started always runs to completion.
+    pub async fn recv_impulse_timeout(
         &self, 
-        buffer: &mut [u8], 
-        args: A
+        args: A, 
+        timeout: std::time::Duration
     ) -> Result<R, CommunicationError> {
 
-        let n_bytes = self.socket.recv(buffer).await?;
-        let buff = &buffer[..n_bytes];
-        let impulse: u16 = bincode::deserialize_from(buff)?;
+        self.recv_impulse_until(args, tokio::time::Instant::now() + timeout).await
+    }
+
+    /// Like `recv_impulse`, failing with `Timeout` if no impulse arrives by `deadline`,
+    /// so a watchdog loop can share one deadline across several receives.
+    pub async fn recv_impulse_until(
+        &self, 
+        args: A, 
+        deadline: tokio::time::Instant
+    ) -> Result<R, CommunicationError> {
+
+        let impulse = tokio::time::timeout_at(deadline, self.recv_fiber()).await
+            .map_err(|_| CommunicationError::Timeout)??;
+        self.dispatch(impulse, args)
+    }
+
+    /// Executes the behavior for a fiber ID as if its impulse was received.
+    /// A panicking behavior is caught and returned as `BehaviorPanicked`,
+    /// so the motor stays usable. 
+    /// NOTE: The panic hook still runs (printing the panic message by default),
+    /// and nothing is caught when built with `panic = "abort"`.
+    /// With a feedback path (`set_feedback`), every result is also fed back.
+    pub fn dispatch(&self, impulse: u16, args: A) -> Result<R, CommunicationError> {
+
+        let result = self.run_behavior(impulse, args);
+        if let (Some(feedback), Ok(result)) = (&self.feedback, &result) {
+            feedback.report(impulse, result)
+        }
+        result
+    }
+
+    /// Run the behavior (or the fallback) for a fiber ID.
+    fn run_behavior(&self, impulse: u16, args: A) -> Result<R, CommunicationError> {
+
+        match self.behavior(impulse) {
+            Some(behavior) => catch_unwind(AssertUnwindSafe(|| behavior(args)))
+                .map_err(|_| {
+                    self.dead_letter(DeadLetterReason::BehaviorPanicked(impulse));
+                    CommunicationError::BehaviorPanicked(impulse)
+                }),
+            None => {
+                self.metrics.record_unrecognized();
+                match &self.fallback {
+                    Some(fallback) => catch_unwind(AssertUnwindSafe(|| fallback(impulse, args)))
+                        .map_err(|_| {
+                            self.dead_letter(DeadLetterReason::BehaviorPanicked(impulse));
+                            CommunicationError::BehaviorPanicked(impulse)
+                        }),
+                    None => {
+                        self.dead_letter(DeadLetterReason::Unrecognized(impulse));
+                        Err(CommunicationError::UnrecognizedImpulse(impulse))
+                    }
+                }
+            }
+        }
+    }
+
+    /// The behavior for a fiber ID. IDs rejected by the bloom prefilter 
+    /// return `None` without the `fibers` map being consulted.
+    fn behavior(&self, impulse: u16) -> Option<&B> {
+
+        if let Some(prefilter) = &self.prefilter {
+            if !prefilter.may_contain(impulse as u64) { return None }
+        }
+
+        #[cfg(test)]
+        self.lookups.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.fibers.get(&impulse)
+    }
+
+    /// Receives the next impulse without dispatching it.
+    pub(crate) async fn recv_fiber(&self) -> Result<u16, CommunicationError> {
+
+        self.recv_fiber_with().await.map(|(fiber, _)| fiber)
+    }
+
+    /// Receives the next impulse and its payload without dispatching it.
+    pub(crate) async fn recv_fiber_with(&self) -> Result<(u16, Option<f32>), CommunicationError> {
+
+        if let Some(fiber) = self.next_pending() { return Ok((fiber, None)) }
+
+        let mut buffer = self.recv_buffer.lock().await;
+        self.recv_fiber_into(&mut buffer).await
+    }
+
+    /// Receives the next impulse into a receive buffer already locked by the caller.
+    pub(crate) async fn recv_fiber_into(
+        &self, 
+        buffer: &mut [u8]
+    ) -> Result<(u16, Option<f32>), CommunicationError> {
+
+        if let Some(fiber) = self.next_pending() { return Ok((fiber, None)) }
 
-        if let Some(behavior) = self.fibers.get(&impulse) { 
-            Ok(behavior(args)) 
-        } else { 
-            Err(CommunicationError::UnrecognizedImpulse(impulse))
+        loop {
+            let (n_bytes, source) = self.socket.recv_from(buffer).await?;
+            if let Some(impulse) = self.accept_datagram_with(&buffer[..n_bytes], source)? { 
+                return Ok(impulse) 
+            }
+        }
+    }
+
+    /// The next fiber left over from a batch, if any.
+    pub(crate) fn next_pending(&self) -> Option<u16> {
+
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).pop_front()
+    }
+
+    /// Account for a datagram received from `source` and send any feedback,
+    /// returning its (first) fiber ID if it carries impulses.
+    /// The rest of a batch is queued for `next_pending`.
+    pub(crate) fn accept_datagram(
+        &self, 
+        datagram: &[u8], 
+        source: SocketAddr
+    ) -> Result<Option<u16>, CommunicationError> {
+
+        Ok(self.accept_datagram_with(datagram, source)?.map(|(fiber, _)| fiber))
+    }
+
+    /// Like `accept_datagram`, also returning the impulse's payload.
+    pub(crate) fn accept_datagram_with(
+        &self, 
+        datagram: &[u8], 
+        source: SocketAddr
+    ) -> Result<Option<(u16, Option<f32>)>, CommunicationError> {
+
+        let malformed = || {
+            self.metrics.record_dropped();
+            self.dead_letter(DeadLetterReason::Malformed { source, len: datagram.len() });
+        };
+
+        if self.reject_plain && self.codec.is_plain(datagram) {
+            malformed();
+            return Err(CommunicationError::ProtocolMismatch)
+        }
+
+        let frame = self.codec.decode(datagram)
+            .inspect_err(|_| malformed())?;
+
+        match frame {
+            ImpulseFrame::Data { seq, fiber, payload } => {
+                self.metrics.record_received();
+                if let Some(seq) = seq { self.loss().observe(seq) }
+                self.respond(fiber, source);
+                Ok(Some((fiber, payload)))
+            },
+            ImpulseFrame::Batch(fibers) => {
+                for fiber in &fibers {
+                    self.metrics.record_received();
+                    self.echo_if_unrecognized(*fiber, source);
+                }
+                let mut fibers = VecDeque::from(fibers);
+                let first = fibers.pop_front();
+                self.pending.lock().unwrap_or_else(|e| e.into_inner()).extend(fibers);
+                Ok(first.map(|fiber| (fiber, None)))
+            },
+            ImpulseFrame::Report { sent } => { 
+                self.loss().report(sent); 
+                Ok(None)
+            },
+            ImpulseFrame::Closing => {
+                self.peer_closed.store(true, std::sync::atomic::Ordering::Release);
+                Ok(None)
+            },
+            ImpulseFrame::Silence 
+            | ImpulseFrame::Heartbeat 
+            | ImpulseFrame::Ack(_) 
+            | ImpulseFrame::Unrecognized(_)
+            | ImpulseFrame::Handshake(_) => Ok(None)
         }
     }
 
 }
 
+impl<B, A, R, E, T> Motor<B, A, Result<R, E>, T> 
+where
+    B: Fn(A) -> Result<R, E>,
+    E: std::fmt::Display,
+    T: Transport
+{
+
+    /// Like `recv_impulse`, for behaviors that can fail.
+    /// A behavior's `Err` is returned as `BehaviorFailed` with its message.
+    pub async fn try_recv_impulse(&self, args: A) -> Result<R, CommunicationError> {
+
+        let impulse = self.recv_fiber().await?;
+        self.try_dispatch(impulse, args)
+    }
+
+    /// Like `dispatch`, for behaviors that can fail.
+    pub fn try_dispatch(&self, impulse: u16, args: A) -> Result<R, CommunicationError> {
+
+        self.dispatch(impulse, args)?
+            .map_err(|e| {
+                let message = e.to_string();
+                self.dead_letter(DeadLetterReason::BehaviorFailed { fiber: impulse, message: message.clone() });
+                CommunicationError::BehaviorFailed(message)
+            })
+    }
+}
+
 
 use cajal_cx::tract::{ Tract, receiver::TractReceiver };
 
-impl<B: Fn(A) -> R, A, R> Tract for Motor<B, A, R> {
+impl<B: Fn(A) -> R, A, R, T: Transport> Tract for Motor<B, A, R, T> {
     fn tract_name(&self) -> &str { &self.tract_name }
     fn num_fibers(&self) -> usize { self.fibers.len() }
     fn tract_address(&self) -> SocketAddr { self.address.clone() }
 }
 
-impl<B: Fn(A) -> R, A, R> TractReceiver for Motor<B, A, R> {}
+impl<B: Fn(A) -> R, A, R, T: Transport> TractReceiver for Motor<B, A, R, T> {}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::error::{ CommunicationError, ConfigError };
+    use crate::transport::ChannelTransport;
+    use crate::types::Sensor;
+    use super::{ Motor, FnMotor };
+
+    #[tokio::test]
+    async fn fn_motor_infers_its_types_from_behaviors() {
+
+        let motor = FnMotor::new("wheel", "127.0.0.1:0".parse().unwrap()).await.unwrap()
+            .with_fiber(1, |x: u8| x + 1)
+            .with_fiber(2, |x: u8| x - 1);
+
+        assert_eq!(motor.dispatch(1, 4).unwrap(), 5);
+        assert_eq!(motor.dispatch(2, 4).unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn listening_motor_only_hears_its_peer() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let stranger: Sensor<u8> = Sensor::new("stranger", any).await.unwrap().with_spectrum([(0, 2)]);
+        let mut peer: Sensor<u8> = Sensor::new("peer", any).await.unwrap().with_spectrum([(0, 1)]);
+        let motor = FnMotor::listening_from("peer", any, peer.address).await.unwrap()
+            .with_fiber(1, |_: ()| 1u16)
+            .with_fiber(2, |_: ()| 2u16);
+        peer.connect(&motor.address).await.unwrap();
+
+        let direct: Sensor<u8> = Sensor::connected("direct", any, motor.address).await.unwrap();
+        assert_eq!(direct.peer(), Some(motor.address));
+
+        stranger.socket.send_to(&crate::wire::plain_impulse(2), motor.address).await.unwrap();
+        peer.send_impulse(&0).await.unwrap();
+        assert_eq!(motor.recv_impulse(()).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn unrecognized_impulses_are_echoed_to_the_sender() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let mut motor = FnMotor::new("echo", any).await.unwrap().with_fiber(1, |_: ()| ());
+        motor.set_echo_unrecognized(true);
+        let sensor: Sensor<u8> = Sensor::connected("echo", any, motor.address).await.unwrap()
+            .with_spectrum([(0, 1), (9, 9)]);
+
+        sensor.send_impulse(&9).await.unwrap();
+        assert!(matches!(motor.recv_impulse(()).await, Err(CommunicationError::UnrecognizedImpulse(9))));
+        assert_eq!(sensor.recv_unrecognized().await.unwrap(), 9);
+
+        sensor.send_impulse(&0).await.unwrap();
+        motor.recv_impulse(()).await.unwrap();
+        assert_eq!(motor.metrics().unrecognized, 1);
+    }
+
+    #[tokio::test]
+    async fn failing_behaviors_return_their_message() {
+
+        let motor = FnMotor::new("fallible", "127.0.0.1:0".parse().unwrap()).await.unwrap()
+            .with_fiber(1, |x: i8| if x < 0 { Err(format!("{x} is negative")) } else { Ok(x) });
+
+        assert_eq!(motor.try_dispatch(1, 3).unwrap(), 3);
+        match motor.try_dispatch(1, -3) {
+            Err(CommunicationError::BehaviorFailed(message)) => assert_eq!(message, "-3 is negative"),
+            other => panic!("unexpected {other:?}")
+        }
+    }
+
+    #[tokio::test]
+    async fn fibers_are_not_clobbered() {
+
+        let mut motor = FnMotor::new("mutate", "127.0.0.1:0".parse().unwrap()).await.unwrap()
+            .with_fiber(1, |_: ()| 1u8);
+
+        assert!(matches!(motor.try_add_fiber(1, |_| 2), Err(ConfigError::AlreadyMapped(1))));
+        motor.try_add_fiber(2, |_| 2).unwrap();
+        assert!(motor.remove_fiber(2).is_some());
+        assert!(matches!(motor.dispatch(2, ()), Err(CommunicationError::UnrecognizedImpulse(2))));
+        assert_eq!(motor.dispatch(1, ()).unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn payloads_reach_the_behavior() {
+
+        let (a, b) = ChannelTransport::pair(4);
+        let sensor: Sensor<char, _> = Sensor::in_process("payload", a).with_spectrum([('a', 1)]);
+        let motor: Motor<fn(f32) -> f32, f32, f32, _> = Motor::in_process("payload", b)
+            .with_fiber(1, |x| x * 2.0);
+
+        sensor.send_impulse_with(&'a', 0.25).await.unwrap();
+        sensor.send_impulse(&'a').await.unwrap();
+        assert_eq!(motor.recv_impulse_with(|p| p.unwrap_or(-1.0)).await.unwrap(), 0.5);
+        assert_eq!(motor.recv_impulse_with(|p| p.unwrap_or(-1.0)).await.unwrap(), -2.0);
+    }
+
+    #[tokio::test]
+    async fn strict_motors_only_accept_tagged_impulses() {
+
+        let (a, b) = ChannelTransport::pair(4);
+        let mut sensor: Sensor<char, _> = Sensor::in_process("strict", a).with_spectrum([('a', 1)]);
+        let mut motor: Motor<fn(()) -> u8, (), u8, _> = Motor::in_process("strict", b).with_fiber(1, |_| 1);
+        motor.set_reject_plain(true);
+
+        sensor.send_impulse(&'a').await.unwrap();
+        assert!(matches!(motor.recv_impulse(()).await, Err(CommunicationError::ProtocolMismatch)));
+
+        sensor.set_tagged(true);
+        sensor.send_impulse(&'a').await.unwrap();
+        assert_eq!(motor.recv_impulse(()).await.unwrap(), 1);
+        assert_eq!(motor.metrics().dropped, 1);
+    }
+
+    #[tokio::test]
+    async fn strict_motors_ask_their_codec_what_is_plain() {
+
+        use crate::codec::EnvelopeCodec;
+
+        let (a, b) = ChannelTransport::pair(4);
+        let mut sensor: Sensor<char, _> = Sensor::in_process("envelope", a).with_spectrum([('a', 1)]);
+        let mut motor: Motor<fn(()) -> u8, (), u8, _> = Motor::in_process("envelope", b).with_fiber(1, |_| 1);
+        sensor.set_codec(EnvelopeCodec::default());
+        motor.set_codec(EnvelopeCodec::default());
+        motor.set_reject_plain(true);
+
+        // The enveloped impulse is three bytes longer than a bare one, and still plain.
+        sensor.send_impulse(&'a').await.unwrap();
+        assert!(matches!(motor.recv_impulse(()).await, Err(CommunicationError::ProtocolMismatch)));
+
+        sensor.set_tagged(true);
+        sensor.send_impulse(&'a').await.unwrap();
+        assert_eq!(motor.recv_impulse(()).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn plain_impulses_fit_the_smallest_receive_buffer() {
+
+        let (a, b) = ChannelTransport::pair(4);
+        let sensor: Sensor<char, _> = Sensor::in_process("buffer", a).with_spectrum([('a', 1), ('b', 2)]);
+        let mut motor: Motor<fn(()) -> u8, (), u8, _> = Motor::in_process("buffer", b)
+            .with_fiber(1, |_| 1)
+            .with_fiber(2, |_| 2);
+        motor.set_recv_buffer_len(0);
+        assert_eq!(motor.recv_buffer.get_mut().len(), 2);
+
+        sensor.send_impulse(&'a').await.unwrap();
+        sensor.send_impulse(&'b').await.unwrap();
+        assert_eq!(motor.recv_impulse(()).await.unwrap(), 1);
+        assert_eq!(motor.recv_impulse(()).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn panicking_behaviors_leave_the_motor_usable() {
+
+        let (_, b) = ChannelTransport::pair(1);
+        let motor: Motor<fn(u8) -> u8, u8, u8, _> = Motor::in_process("panic", b)
+            .with_fiber(1, |x| 10 / x);
 
+        assert!(matches!(motor.dispatch(1, 0), Err(CommunicationError::BehaviorPanicked(1))));
+        assert_eq!(motor.dispatch(1, 5).unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn fallback_handles_unrecognized_impulses() {
+
+        let (_, b) = ChannelTransport::pair(1);
+        let mut motor: Motor<fn(u16) -> u16, u16, u16, _> = Motor::in_process("fallback", b)
+            .with_fiber(1, |x| x);
+        motor.set_fallback(|fiber, x| fiber * 100 + x);
+
+        assert_eq!(motor.dispatch(1, 5).unwrap(), 5);
+        assert_eq!(motor.dispatch(7, 5).unwrap(), 705);
+        assert_eq!(motor.metrics().unrecognized, 1);
+
+        motor.clear_fallback();
+        assert!(matches!(motor.dispatch(7, 5), Err(CommunicationError::UnrecognizedImpulse(7))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn receives_time_out_when_nothing_arrives() {
+
+        let (a, b) = ChannelTransport::pair(4);
+        let sensor: Sensor<char, _> = Sensor::in_process("timeout", a).with_spectrum([('a', 1)]);
+        let motor: Motor<fn(()) -> u8, (), u8, _> = Motor::in_process("timeout", b).with_fiber(1, |_| 1);
+        let second = std::time::Duration::from_secs(1);
+
+        assert!(matches!(motor.recv_impulse_timeout((), second).await, Err(CommunicationError::Timeout)));
+        sensor.send_impulse(&'a').await.unwrap();
+        assert_eq!(motor.recv_impulse_timeout((), second).await.unwrap(), 1);
+
+        let deadline = tokio::time::Instant::now() + second;
+        sensor.send_impulse(&'a').await.unwrap();
+        assert_eq!(motor.recv_impulse_until((), deadline).await.unwrap(), 1);
+        assert!(matches!(motor.recv_impulse_until((), deadline).await, Err(CommunicationError::Timeout)));
+        assert!(tokio::time::Instant::now() >= deadline);
+    }
+
+    #[tokio::test]
+    async fn screened_impulses_skip_the_fiber_lookup() {
+
+        use std::sync::atomic::Ordering;
+
+        let (_, b) = ChannelTransport::pair(1);
+        let mut motor: Motor<fn(()) -> u8, (), u8, _> = Motor::in_process("screen", b).with_fiber(1, |_| 1);
+        motor.enable_bloom_prefilter();
+        let screened: Vec<u16> = (2..=u16::MAX)
+            .filter(|fid| !motor.prefilter.as_ref().unwrap().may_contain(*fid as u64))
+            .take(100)
+            .collect();
+        assert_eq!(screened.len(), 100);
+
+        for fid in &screened {
+            assert!(matches!(motor.dispatch(*fid, ()), Err(CommunicationError::UnrecognizedImpulse(_))));
+        }
+        assert_eq!(motor.lookups.load(Ordering::Relaxed), 0);
+        assert_eq!(motor.dispatch(1, ()).unwrap(), 1);
+        assert_eq!(motor.lookups.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn prefilter_follows_fiber_edits() {
+
+        use std::sync::atomic::Ordering;
+
+        let (_, b) = ChannelTransport::pair(1);
+        let mut motor: Motor<fn(()) -> u8, (), u8, _> = Motor::in_process("rebuild", b).with_fiber(1, |_| 1);
+        motor.enable_bloom_prefilter();
+        let fid = (2..=u16::MAX)
+            .find(|fid| !motor.prefilter.as_ref().unwrap().may_contain(*fid as u64))
+            .unwrap();
+        let lookups = |motor: &Motor<fn(()) -> u8, (), u8, _>| motor.lookups.load(Ordering::Relaxed);
+
+        assert!(motor.dispatch(fid, ()).is_err());
+        assert_eq!(lookups(&motor), 0);
+
+        motor.add_fiber(fid, |_| 2);
+        assert_eq!(motor.dispatch(fid, ()).unwrap(), 2);
+        assert_eq!(lookups(&motor), 1);
+
+        motor.remove_fiber(fid);
+        assert!(motor.dispatch(fid, ()).is_err());
+        assert_eq!(lookups(&motor), 1);
+
+        motor.disable_bloom_prefilter();
+        assert!(motor.dispatch(fid, ()).is_err());
+        assert_eq!(lookups(&motor), 2);
+    }
+}
@@ -0,0 +1,148 @@
+
+use std::future::poll_fn;
+use std::sync::Mutex;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::task::Poll;
+use tokio::io::ReadBuf;
+
+use crate::error::CommunicationError;
+use super::Motor;
+use super::motor::DEFAULT_RECV_BUFFER_LEN;
+
+
+/// Several motors sharing one receive loop.
+/// Every motor dispatches with the same behavior and argument types.
+pub struct MotorSet<B: Fn(A) -> R, A, R> {
+    motors: Vec<Motor<B, A, R>>,
+    next: AtomicUsize,
+
+    /// Shared by every motor, since only one datagram is read per poll.
+    buffer: Mutex<Vec<u8>>
+}
+
+impl<B: Fn(A) -> R, A, R> Default for MotorSet<B, A, R> {
+    fn default() -> Self {
+        MotorSet { 
+            motors: Vec::new(), 
+            next: AtomicUsize::new(0), 
+            buffer: Mutex::new(vec![0; DEFAULT_RECV_BUFFER_LEN]) 
+        }
+    }
+}
+
+impl<B: Fn(A) -> R, A, R> MotorSet<B, A, R> {
+
+    /// Create a set with no motors.
+    pub fn new() -> Self { Self::default() }
+
+    /// Add a motor, returning its index in the set.
+    pub fn push(&mut self, motor: Motor<B, A, R>) -> usize {
+
+        self.motors.push(motor);
+        self.motors.len() - 1
+    }
+
+    /// The motor at `index`.
+    pub fn get(&self, index: usize) -> Option<&Motor<B, A, R>> { self.motors.get(index) }
+
+    /// Every motor, in the order they were added.
+    pub fn motors(&self) -> &[Motor<B, A, R>] { &self.motors }
+
+    /// Number of motors in the set.
+    pub fn len(&self) -> usize { self.motors.len() }
+
+    /// Whether the set has no motors.
+    pub fn is_empty(&self) -> bool { self.motors.is_empty() }
+
+    /// Receive from whichever motor has an impulse first and dispatch it,
+    /// returning the index of that motor with the result.
+    /// NOTE: Biased: motors are always checked in order, so a saturated
+    /// motor early in the set can starve the ones after it. 
+    /// See `recv_any_fair`. Never resolves if the set is empty.
+    pub async fn recv_any(&self, args: A) -> (usize, Result<R, CommunicationError>) {
+
+        self.recv_from(0, args).await
+    }
+
+    /// Like `recv_any`, but round-robin: checking starts at the motor 
+    /// after the one served last. A motor with an impulse waiting is 
+    /// therefore served within `len()` calls, however busy the others are.
+    pub async fn recv_any_fair(&self, args: A) -> (usize, Result<R, CommunicationError>) {
+
+        let start = self.next.load(Ordering::Relaxed);
+        let (index, result) = self.recv_from(start, args).await;
+        self.next.store(index + 1, Ordering::Relaxed);
+        (index, result)
+    }
+
+    async fn recv_from(
+        &self, 
+        start: usize, 
+        args: A
+    ) -> (usize, Result<R, CommunicationError>) {
+
+        let count = self.motors.len();
+        let (index, fiber) = poll_fn(|cx| {
+            // Only held within a single poll, never across an await.
+            let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+            for offset in 0..count {
+                let index = (start + offset) % count;
+                let motor = &self.motors[index];
+                if let Some(fiber) = motor.next_pending() { 
+                    return Poll::Ready((index, Ok(fiber))) 
+                }
+
+                let mut read = ReadBuf::new(&mut buffer);
+                match motor.socket.poll_recv_from(cx, &mut read) {
+                    Poll::Ready(Ok(source)) => match motor.accept_datagram(read.filled(), source) {
+                        Ok(Some(fiber)) => return Poll::Ready((index, Ok(fiber))),
+                        Err(e) => return Poll::Ready((index, Err(e))),
+                        // Datagram carried no impulse; poll again so 
+                        // this socket's waker is re-registered.
+                        Ok(None) => cx.waker().wake_by_ref()
+                    },
+                    Poll::Ready(Err(e)) => return Poll::Ready((index, Err(e.into()))),
+                    Poll::Pending => {}
+                }
+            }
+            Poll::Pending
+        }).await;
+
+        let result = fiber.and_then(|fiber| self.motors[index].dispatch(fiber, args));
+        (index, result)
+    }
+
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::types::{ Sensor, FnMotor };
+    use super::MotorSet;
+
+    #[tokio::test]
+    async fn fair_receive_alternates_between_busy_motors() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let mut set = MotorSet::new();
+        for _ in 0..2 {
+            set.push(FnMotor::new("set", any).await.unwrap().with_fiber(1, |_: ()| ()));
+        }
+
+        for motor in set.motors() {
+            let sensor: Sensor<u8> = Sensor::connected("set", any, motor.address).await.unwrap()
+                .with_spectrum([(0, 1)]);
+            for _ in 0..4 { sensor.send_impulse(&0).await.unwrap() }
+        }
+
+        let mut biased = Vec::new();
+        for _ in 0..2 { biased.push(set.recv_any(()).await.0) }
+        assert_eq!(biased, vec![0, 0]);
+
+        let mut fair = Vec::new();
+        for _ in 0..4 { fair.push(set.recv_any_fair(()).await.0) }
+        assert_eq!(fair, vec![0, 1, 0, 1]);
+    }
+}
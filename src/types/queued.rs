@@ -0,0 +1,302 @@
+
+use std::hash::Hash;
+use std::sync::{ Arc, Mutex, MutexGuard };
+use std::time::{ Duration, Instant };
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use super::Sensor;
+use super::schedule::TrainHandle;
+
+
+/// Number of power-of-two latency buckets, covering up to ~35 minutes.
+const LATENCY_BUCKETS: usize = 32;
+
+/// Coarse histogram of durations, bucketed by powers of two microseconds.
+/// Bucket 0 holds durations under 1µs, and bucket `i` holds `[2^(i-1), 2^i)`µs.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    buckets: [u64; LATENCY_BUCKETS],
+    count: u64,
+    max: Duration
+}
+
+impl LatencyHistogram {
+
+    pub(crate) fn record(&mut self, latency: Duration) {
+
+        let micros = latency.as_micros() as u64;
+        let bucket = (u64::BITS - micros.leading_zeros()) as usize;
+        self.buckets[bucket.min(LATENCY_BUCKETS - 1)] += 1;
+        self.count += 1;
+        self.max = self.max.max(latency);
+    }
+
+    /// Number of recorded durations.
+    pub fn count(&self) -> u64 { self.count }
+
+    /// Longest recorded duration.
+    pub fn max(&self) -> Duration { self.max }
+
+    /// Upper bound of the bucket containing the `p`th percentile (0.0..=1.0).
+    /// Precision is limited to a factor of two by the bucketing.
+    pub fn percentile(&self, p: f64) -> Duration {
+
+        if self.count == 0 { return Duration::ZERO }
+
+        let target = ((self.count as f64) * p.clamp(0.0, 1.0)).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                let bound = Duration::from_micros(1 << bucket);
+                return bound.min(self.max)
+            }
+        }
+        self.max
+    }
+}
+
+/// Snapshot of a `QueuedSensor`'s queue behavior.
+#[derive(Debug, Clone, Default)]
+pub struct QueueStats {
+
+    /// Impulses currently waiting to be sent.
+    pub current_len: usize,
+
+    /// Longest the queue has been since the last reset.
+    pub high_water_mark: usize,
+
+    /// Impulses sent successfully.
+    pub sent: u64,
+
+    /// Impulses that failed to send.
+    pub failed: u64,
+
+    /// Time from enqueue until the send completed.
+    pub latency: LatencyHistogram
+}
+
+
+/// Handle to a `Sensor` driven by a background task through a bounded queue.
+/// Cloning the handle shares the same queue; the driver task ends
+/// (dropping the sensor) once every handle has been dropped.
+pub struct QueuedSensor<Q> {
+    sender: mpsc::Sender<(Q, Instant)>,
+    stats: Arc<Mutex<QueueStats>>,
+    driver: Arc<Mutex<Option<JoinHandle<()>>>>
+}
+
+impl<Q> Clone for QueuedSensor<Q> {
+    fn clone(&self) -> Self {
+        QueuedSensor { 
+            sender: self.sender.clone(), 
+            stats: self.stats.clone(), 
+            driver: self.driver.clone() 
+        }
+    }
+}
+
+fn lock(stats: &Mutex<QueueStats>) -> MutexGuard<'_, QueueStats> {
+    stats.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl<Q, T> Sensor<Q, T> 
+where
+    Q: Hash + Eq + Send + Sync + 'static,
+    T: Transport + Send + Sync + 'static
+{
+
+    /// Move the sensor into a background task fed by a queue 
+    /// holding up to `capacity` impulses (at least one). Must be called within a runtime.
+    pub fn queued(self, capacity: usize) -> QueuedSensor<Q> {
+
+        let (sender, mut receiver) = mpsc::channel::<(Q, Instant)>(capacity.max(1));
+        let stats = Arc::new(Mutex::new(QueueStats::default()));
+        let driver_stats = stats.clone();
+
+        let running = self.track_driver();
+        let driver = tokio::spawn(async move {
+            let _running = running;
+            let cancel = self.cancel.clone();
+            while let Some((quantum, enqueued)) = tokio::select! {
+                _ = cancel.cancelled() => self.flush_next(&mut receiver),
+                next = receiver.recv() => next
+            } {
+                let result = self.send_impulse(&quantum).await;
+
+                let mut stats = lock(&driver_stats);
+                stats.current_len = stats.current_len.saturating_sub(1);
+                stats.latency.record(enqueued.elapsed());
+                match result {
+                    Ok(()) => stats.sent += 1,
+                    Err(_) => stats.failed += 1
+                }
+            }
+        });
+
+        QueuedSensor { sender, stats, driver: Arc::new(Mutex::new(Some(driver))) }
+    }
+
+    /// Spawn a driver that sends every quantum it receives on the 
+    /// returned channel, which buffers up to `capacity` quanta.
+    /// Producers get backpressure: `Sender::send` waits while the buffer 
+    /// is full, and `try_send` fails. The driver ends once every sender 
+    /// is dropped and the buffer is drained, or once the sensor's cancellation
+    /// token is cancelled (dropping what is still buffered, unless the sensor 
+    /// is being closed); joining its handle returns the number of impulses sent. 
+    /// Failed sends are only counted in metrics.
+    /// NOTE: Unlike `queued`, the sensor stays shared and usable directly.
+    pub fn channel(self: &Arc<Self>, capacity: usize) -> (mpsc::Sender<Q>, TrainHandle) {
+
+        let (sender, mut receiver) = mpsc::channel::<Q>(capacity.max(1));
+        let sensor = self.clone();
+        let running = sensor.track_driver();
+        let task = tokio::spawn(async move {
+            let _running = running;
+            let mut sent = 0;
+            while let Some(quantum) = tokio::select! {
+                _ = sensor.cancel.cancelled() => sensor.flush_next(&mut receiver),
+                next = receiver.recv() => next
+            } {
+                if sensor.send_impulse(&quantum).await.is_ok() { sent += 1 }
+            }
+            Ok(sent)
+        });
+
+        (sender, TrainHandle { task })
+    }
+}
+
+impl<Q> QueuedSensor<Q> {
+
+    /// Count an impulse into the queue. Called once its slot is reserved
+    /// but before it is sent, so the driver never dequeues it uncounted.
+    fn note_enqueued(&self) {
+
+        let mut stats = lock(&self.stats);
+        stats.current_len += 1;
+        stats.high_water_mark = stats.high_water_mark.max(stats.current_len);
+    }
+
+    /// Stop queueing from this handle and wait until every impulse already 
+    /// queued has been sent. The driver drains the queue once every clone 
+    /// of the handle is closed or dropped, so this waits for those too;
+    /// only the first clone to close waits, the rest return immediately.
+    /// NOTE: Dropping a handle without closing it also lets the queue drain, 
+    /// but in the background.
+    pub async fn close(self) {
+
+        let driver = self.driver.lock().unwrap_or_else(|e| e.into_inner()).take();
+        drop(self);
+        if let Some(driver) = driver { let _ = driver.await; }
+    }
+
+    /// Queue an impulse, waiting for space if the queue is full.
+    pub async fn enqueue(&self, quantum: Q) -> Result<(), CommunicationError> {
+
+        let permit = self.sender.reserve().await
+            .map_err(|_| CommunicationError::QueueClosed)?;
+        self.note_enqueued();
+        permit.send((quantum, Instant::now()));
+        Ok(())
+    }
+
+    /// Queue an impulse without waiting, failing if the queue is full.
+    pub fn try_enqueue(&self, quantum: Q) -> Result<(), CommunicationError> {
+
+        use mpsc::error::TrySendError;
+
+        let permit = self.sender.try_reserve().map_err(|e| match e {
+            TrySendError::Full(_) => CommunicationError::QueueFull,
+            TrySendError::Closed(_) => CommunicationError::QueueClosed
+        })?;
+        self.note_enqueued();
+        permit.send((quantum, Instant::now()));
+        Ok(())
+    }
+
+    /// Current queue statistics.
+    pub fn queue_stats(&self) -> QueueStats { lock(&self.stats).clone() }
+
+    /// Clear the counters and latency histogram, 
+    /// and reset the high-water mark to the current length.
+    pub fn reset_queue_stats(&self) {
+
+        let mut stats = lock(&self.stats);
+        let current_len = stats.current_len;
+        *stats = QueueStats {
+            current_len,
+            high_water_mark: current_len,
+            ..QueueStats::default()
+        };
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::error::CommunicationError;
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+
+    #[tokio::test(start_paused = true)]
+    async fn rejected_impulses_do_not_count_toward_the_high_water_mark() {
+
+        let (a, _b) = ChannelTransport::pair(64);
+        let queue = Sensor::in_process("queued", a)
+            .with_spectrum([('a', 1)])
+            .queued(2);
+
+        // The driver has not run yet, so the queue fills.
+        queue.try_enqueue('a').unwrap();
+        queue.try_enqueue('a').unwrap();
+        assert!(matches!(queue.try_enqueue('a'), Err(CommunicationError::QueueFull)));
+
+        let stats = queue.queue_stats();
+        assert_eq!(stats.current_len, 2);
+        assert_eq!(stats.high_water_mark, 2);
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let stats = queue.queue_stats();
+        assert_eq!(stats.current_len, 0);
+        assert_eq!(stats.high_water_mark, 2);
+        assert_eq!(stats.sent, 2);
+        assert_eq!(stats.latency.count(), 2);
+    }
+
+    #[tokio::test]
+    async fn channel_front_end_drains_before_ending() {
+
+        let (a, b) = ChannelTransport::pair(8);
+        let sensor = Arc::new(Sensor::in_process("channel", a).with_spectrum([('a', 1)]));
+        let motor: Motor<fn(()), (), (), _> = Motor::in_process("channel", b);
+
+        let (sender, handle) = sensor.channel(1);
+        sender.send('a').await.unwrap();
+        sender.send('x').await.unwrap();
+        sender.send('a').await.unwrap();
+        drop(sender);
+
+        assert_eq!(handle.join().await.unwrap().unwrap(), 2);
+        assert_eq!(sensor.metrics().unrecognized, 1);
+        assert_eq!(motor.recv_fiber().await.unwrap(), 1);
+        assert_eq!(motor.recv_fiber().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn zero_capacity_queues_hold_one_impulse() {
+
+        let (a, _b) = ChannelTransport::pair(8);
+        let queue = Sensor::in_process("empty", a).with_spectrum([('a', 1)]).queued(0);
+
+        queue.try_enqueue('a').unwrap();
+        assert!(matches!(queue.try_enqueue('a'), Err(CommunicationError::QueueFull)));
+    }
+}
@@ -0,0 +1,130 @@
+
+//! Record impulses as they arrive and replay them later,
+//! at real time or scaled by a speed factor.
+
+use std::hash::Hash;
+use std::time::Duration;
+use serde::{ Serialize, Deserialize };
+use tokio::time::Instant;
+
+use crate::error::{ CommunicationError, ConfigError };
+use crate::transport::Transport;
+use super::{ Sensor, Motor };
+
+
+/// Fiber IDs with the time each arrived, relative to the start of recording.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImpulseLog {
+    pub entries: Vec<(Duration, u16)>
+}
+
+impl ImpulseLog {
+
+    /// Create an empty log.
+    pub fn new() -> Self { Self::default() }
+
+    /// Append an impulse at `offset` from the start of the log.
+    /// NOTE: Offsets are expected in non-decreasing order.
+    pub fn push(&mut self, offset: Duration, fiber: u16) {
+
+        self.entries.push((offset, fiber));
+    }
+
+    /// Number of recorded impulses.
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    /// Whether nothing was recorded.
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    /// Offset of the last impulse, i.e. the real-time length of a replay.
+    pub fn duration(&self) -> Duration {
+
+        self.entries.last().map(|(offset, _)| *offset).unwrap_or_default()
+    }
+}
+
+impl<B: Fn(A) -> R, A, R, T: Transport> Motor<B, A, R, T> {
+
+    /// Record the next `count` impulses without dispatching them.
+    pub async fn record(&self, count: usize) -> Result<ImpulseLog, CommunicationError> {
+
+        let mut log = ImpulseLog::new();
+        let start = Instant::now();
+        for _ in 0..count {
+            let fiber = self.recv_fiber().await?;
+            log.push(start.elapsed(), fiber);
+        }
+        Ok(log)
+    }
+}
+
+impl<Q: Hash + Eq, T: Transport> Sensor<Q, T> {
+
+    /// Send every impulse in `log`, scaling the gaps between them by 
+    /// `1 / speed`: 2.0 replays twice as fast, 0.5 at half speed.
+    /// A speed of 0 sends as fast as possible with no pacing.
+    /// Negative or NaN speeds fail with `InvalidReplaySpeed`.
+    /// Returns the number of impulses sent.
+    pub async fn replay(
+        &self, 
+        log: &ImpulseLog, 
+        speed: f64
+    ) -> Result<usize, CommunicationError> {
+
+        if speed.is_nan() || speed < 0.0 {
+            return Err(ConfigError::InvalidReplaySpeed(speed).into())
+        }
+
+        let start = Instant::now();
+        for (offset, fiber) in &log.entries {
+            if speed > 0.0 {
+                let scaled = Duration::try_from_secs_f64(offset.as_secs_f64() / speed).ok();
+                if let Some(deadline) = scaled.and_then(|delay| start.checked_add(delay)) {
+                    tokio::time::sleep_until(deadline).await;
+                }
+            }
+            self.transmit(*fiber).await?;
+        }
+        Ok(log.len())
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    use crate::error::{ CommunicationError, ConfigError };
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+    use super::ImpulseLog;
+
+    #[tokio::test(start_paused = true)]
+    async fn replays_a_recorded_log_at_double_speed() {
+
+        let (a, b) = ChannelTransport::pair(8);
+        let sensor: Sensor<u8, _> = Sensor::in_process("replay", a);
+        let motor: Motor<fn(()), (), (), _> = Motor::in_process("replay", b);
+
+        let mut log = ImpulseLog::new();
+        log.push(Duration::ZERO, 1);
+        log.push(Duration::from_millis(100), 2);
+        log.push(Duration::from_millis(400), 3);
+
+        let start = Instant::now();
+        assert_eq!(sensor.replay(&log, 2.0).await.unwrap(), 3);
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+
+        let recorded = motor.record(3).await.unwrap();
+        let fibers: Vec<u16> = recorded.entries.iter().map(|(_, fiber)| *fiber).collect();
+        assert_eq!(fibers, vec![1, 2, 3]);
+
+        assert!(matches!(
+            sensor.replay(&log, -1.0).await,
+            Err(CommunicationError::Misconfigured(ConfigError::InvalidReplaySpeed(_)))
+        ));
+    }
+}
@@ -0,0 +1,103 @@
+
+//! Clearing the per-key state tracts accumulate while running,
+//! e.g. between experiment episodes, without rebuilding them.
+//! Mappings (spectrum, fibers, weights), configuration and 
+//! the socket are always preserved.
+
+use std::hash::Hash;
+use std::sync::atomic::Ordering;
+
+use crate::transport::Transport;
+use super::{ Sensor, Motor };
+use super::loss::LossTracker;
+
+
+impl<Q: Hash + Eq, T: Transport> Sensor<Q, T> {
+
+    /// Make the next impulse behave as if the sensor were fresh. Resets:
+    /// - the last fiber fired, so edge triggering fires again;
+    /// - the sequence counter, so numbering restarts from 0;
+    /// - the highest acked sequence number;
+    /// - when each fiber last fired, ending refractory periods 
+    ///   and debounced bursts.
+    ///
+    /// Metrics are cumulative and are not reset.
+    pub fn reset_state(&self) {
+
+        *self.last_fired.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        self.next_seq.store(0, Ordering::Relaxed);
+        self.acked.store(0, Ordering::Relaxed);
+        self.last_spike.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        self.last_trigger.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+impl<B: Fn(A) -> R, A, R, T: Transport> Motor<B, A, R, T> {
+
+    /// Forget what has been received, as if the motor were fresh. Resets:
+    /// - loss tracking (expected sequence, missing numbers, reports),
+    ///   to pair with a `Sensor::reset_state` on the sender;
+    /// - cumulative ack progress, keeping the ack policy;
+    /// - fibers of a batch not yet returned by a receive.
+    ///
+    /// Metrics are cumulative and are not reset.
+    pub fn reset_state(&self) {
+
+        *self.loss() = LossTracker::default();
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        if let Some(ack) = self.acks.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            ack.reset();
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::transport::{ Transport, ChannelTransport };
+    use crate::types::{ Sensor, Motor };
+
+    #[tokio::test]
+    async fn reset_sensor_fires_and_numbers_as_if_fresh() {
+
+        let (a, b) = ChannelTransport::pair(8);
+        let mut sensor: Sensor<char, _> = Sensor::in_process("reset", a).with_spectrum([('a', 1)]);
+        sensor.set_edge_triggered(true);
+        sensor.set_sequenced(true);
+
+        sensor.send_impulse(&'a').await.unwrap();
+        sensor.send_impulse(&'a').await.unwrap();
+        assert_eq!(sensor.sent_count(), 1);
+
+        sensor.reset_state();
+        assert_eq!(sensor.sent_count(), 0);
+        sensor.send_impulse(&'a').await.unwrap();
+        assert_eq!(sensor.sent_count(), 1);
+
+        let mut buf = [0u8; 64];
+        assert!(b.try_recv_from(&mut buf).is_ok());
+        assert!(b.try_recv_from(&mut buf).is_ok());
+        assert!(b.try_recv_from(&mut buf).is_err());
+    }
+
+    #[tokio::test]
+    async fn reset_motor_forgets_loss() {
+
+        let (a, b) = ChannelTransport::pair(8);
+        let mut sensor: Sensor<char, _> = Sensor::in_process("reset", a).with_spectrum([('a', 1)]);
+        sensor.set_sequenced(true);
+        let motor: Motor<fn(()), (), (), _> = Motor::in_process("reset", b).with_fiber(1, |_| ());
+
+        sensor.send_impulse(&'a').await.unwrap();
+        sensor.next_seq.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        sensor.send_impulse(&'a').await.unwrap();
+        motor.recv_impulse(()).await.unwrap();
+        motor.recv_impulse(()).await.unwrap();
+        assert!(motor.estimated_loss_rate() > 0.0);
+
+        motor.reset_state();
+        assert_eq!(motor.estimated_loss_rate(), 0.0);
+    }
+}
@@ -0,0 +1,199 @@
+
+//! Timed spike patterns sent from a background task.
+
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use super::Sensor;
+
+
+/// A pattern of fiber IDs, each at an offset from the start of the train.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpikeTrain {
+    spikes: Vec<(Duration, u16)>
+}
+
+impl SpikeTrain {
+
+    /// Create an empty train.
+    pub fn new() -> Self { Self::default() }
+
+    /// Add a spike on `fiber` at `offset` from the start of the train.
+    /// Spikes may be added in any order.
+    pub fn at(mut self, offset: Duration, fiber: u16) -> Self {
+
+        self.push(offset, fiber);
+        self
+    }
+
+    /// Add a spike on `fiber` at `offset` from the start of the train.
+    pub fn push(&mut self, offset: Duration, fiber: u16) {
+
+        let index = self.spikes.partition_point(|(existing, _)| *existing <= offset);
+        self.spikes.insert(index, (offset, fiber));
+    }
+
+    /// The spikes in the order they will be sent.
+    pub fn spikes(&self) -> &[(Duration, u16)] { &self.spikes }
+
+    /// Number of spikes in the train.
+    pub fn len(&self) -> usize { self.spikes.len() }
+
+    /// Whether the train has no spikes.
+    pub fn is_empty(&self) -> bool { self.spikes.is_empty() }
+
+    /// Offset of the last spike.
+    pub fn duration(&self) -> Duration {
+
+        self.spikes.last().map(|(offset, _)| *offset).unwrap_or_default()
+    }
+}
+
+impl From<super::ImpulseLog> for SpikeTrain {
+    fn from(log: super::ImpulseLog) -> Self {
+        log.entries.into_iter().fold(SpikeTrain::new(), |train, (offset, fiber)| train.at(offset, fiber))
+    }
+}
+
+/// Handle to a background firing task. 
+/// Dropping the handle stops the task.
+pub struct TrainHandle {
+    pub(crate) task: JoinHandle<Result<usize, CommunicationError>>
+}
+
+impl TrainHandle {
+
+    /// Stop sending; impulses already sent are not affected.
+    pub fn stop(&self) { self.task.abort() }
+
+    /// Whether the task has finished, been stopped, or failed.
+    pub fn is_finished(&self) -> bool { self.task.is_finished() }
+
+    /// Wait for the task to end, returning the number of impulses sent
+    /// or the error that ended it. Returns `None` if it was stopped.
+    /// NOTE: A tonic task only ends when stopped or cancelled.
+    pub async fn join(mut self) -> Option<Result<usize, CommunicationError>> {
+
+        (&mut self.task).await.ok()
+    }
+}
+
+impl Drop for TrainHandle {
+    fn drop(&mut self) {
+        self.task.abort()
+    }
+}
+
+impl<Q, T> Sensor<Q, T> 
+where
+    Q: Hash + Eq + Send + Sync + 'static,
+    T: Transport + Send + Sync + 'static
+{
+
+    /// Spawn a task that sends each spike in `train` at its offset
+    /// from now. The task ends after the last spike, the first failed send,
+    /// or when the sensor's cancellation token is cancelled.
+    /// NOTE: Timing is limited by the resolution of the tokio timer (~1ms).
+    pub fn schedule_train(self: &Arc<Self>, train: SpikeTrain) -> TrainHandle {
+
+        let sensor = self.clone();
+        let task = tokio::spawn(async move {
+            let start = Instant::now();
+            for (sent, (offset, fiber)) in train.spikes.iter().enumerate() {
+                tokio::select! {
+                    _ = sensor.cancel.cancelled() => return Ok(sent),
+                    _ = tokio::time::sleep_until(start + *offset) => {}
+                }
+                sensor.transmit(*fiber).await?;
+            }
+            Ok(train.len())
+        });
+
+        TrainHandle { task }
+    }
+
+    /// Spawn a task that fires `quantum` every `interval` as a baseline
+    /// input, until the handle is stopped or dropped or the sensor's 
+    /// cancellation token is cancelled. Joining returns the number of ticks.
+    /// Failed sends are counted in the metrics and do not end the task.
+    /// NOTE: Late ticks are delayed rather than fired in quick succession.
+    pub fn tonic(self: &Arc<Self>, quantum: Q, interval: Duration) -> TrainHandle {
+
+        let sensor = self.clone();
+        let task = tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval.max(Duration::from_millis(1)));
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut fired = 0;
+            loop {
+                tokio::select! {
+                    _ = sensor.cancel.cancelled() => return Ok(fired),
+                    _ = ticks.tick() => {}
+                }
+                let _ = sensor.send_impulse(&quantum).await;
+                fired += 1;
+            }
+        });
+
+        TrainHandle { task }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+    use super::SpikeTrain;
+
+    #[tokio::test(start_paused = true)]
+    async fn trains_fire_in_offset_order() {
+
+        let (a, b) = ChannelTransport::pair(8);
+        let sensor: Arc<Sensor<char, _>> = Arc::new(Sensor::in_process("train", a));
+        let motor: Motor<fn(()), (), (), _> = Motor::in_process("train", b);
+        let train = SpikeTrain::new()
+            .at(Duration::from_millis(30), 3)
+            .at(Duration::from_millis(10), 1)
+            .at(Duration::from_millis(20), 2);
+        assert_eq!(train.duration(), Duration::from_millis(30));
+
+        let start = Instant::now();
+        let handle = sensor.schedule_train(train);
+        for (fiber, offset) in [(1, 10), (2, 20), (3, 30)] {
+            assert_eq!(motor.recv_fiber().await.unwrap(), fiber);
+            assert_eq!(start.elapsed(), Duration::from_millis(offset));
+        }
+        assert_eq!(handle.join().await.unwrap().unwrap(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn tonic_firing_ticks_until_stopped_or_cancelled() {
+
+        let (a, b) = ChannelTransport::pair(64);
+        let sensor: Arc<Sensor<char, _>> = Arc::new(Sensor::in_process("tonic", a).with_spectrum([('a', 1)]));
+        let motor: Motor<fn(()), (), (), _> = Motor::in_process("tonic", b);
+
+        let handle = sensor.tonic('a', Duration::from_millis(100));
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        handle.stop();
+        assert!(handle.join().await.is_none());
+        assert_eq!(sensor.metrics().impulses_sent, 3);
+
+        let handle = sensor.tonic('a', Duration::from_millis(100));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        sensor.cancellation_token().cancel();
+        assert_eq!(handle.join().await.unwrap().unwrap(), 1);
+        for _ in 0..4 { assert_eq!(motor.recv_fiber().await.unwrap(), 1) }
+    }
+}
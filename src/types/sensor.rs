@@ -1,29 +1,108 @@
 
-use std::net::SocketAddr;
+use std::net::{ SocketAddr, IpAddr, Ipv4Addr };
 use tokio::net::UdpSocket;
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::sync::{ Arc, Mutex, OnceLock };
+use std::time::Duration;
+use tokio::time::Instant;
+use std::sync::atomic::{ AtomicBool, AtomicU64, Ordering };
+use tokio::sync::oneshot;
+use rand::{ SeedableRng, rngs::StdRng };
 
-use crate::error::{ BuildError, CommunicationError };
+use crate::error::{ BuildError, CommunicationError, ConfigError };
+use crate::transport::{ Transport, CallbackTransport };
+use crate::wire::ImpulseFrame;
+use crate::codec::{ ImpulseCodec, BincodeCodec };
+use crate::metrics::{ Metrics, TractMetrics, ShutdownHook };
+use crate::spectrum::RangeSpectrum;
+use super::id::{ TractId, FiberId, FiberWidth };
+use super::weighted::WeightedFibers;
+use tokio_util::sync::CancellationToken;
 
 
 /// Sends some data impulse as a NeuronId to trigger a Complex's Inputs.
 /// The frequency of that data's occurrence should form a meaningful signal.
 /// `Q` is the quantized form of the datum that creates the signal impulse.
 /// `Q` can also be any post-conversion key for triggering the impulse.
-pub struct Sensor<Q: Hash + Eq> {
+/// `T` is the transport that carries the impulses, UDP by default.
+pub struct Sensor<Q: Hash + Eq, T: Transport = UdpSocket> {
 
     /// The corresponding `cajal::io::Input` should be set to share this name. 
     pub tract_name: String,
+    pub(crate) id: TractId,
 
     /// This should be set up to match the address of the 
     /// corresponding `Input` that will read the Sensor signal.
     pub address: SocketAddr,
-    pub(crate) socket: UdpSocket,
+    pub(crate) socket: T,
+
+    /// Address communicated to peers, if it differs from the socket's own
+    /// (e.g. behind NAT or container port-mapping).
+    pub(crate) advertised: Option<SocketAddr>,
+
+    /// Remote address the socket is connected to, if any.
+    pub(crate) peer: Option<SocketAddr>,
+
+    /// Additional destinations every impulse is sent to.
+    pub(crate) targets: Vec<SocketAddr>,
+
+    /// When set, a refused or reset send reconnects to `peer` and retries once.
+    pub(crate) auto_reconnect: bool,
+
+    /// Largest IP packet the path to the peer carries, used to size batches.
+    pub(crate) mtu: usize,
 
     /// These should correspond to the NeuronIds in `Input.fibers`.
     /// The fiber IDs can be retrieved with the `Input::fiber_ids` method. 
-    pub spectrum: HashMap<Q, u16>,
+    pub spectrum: HashMap<Q, FiberWidth>,
+
+    /// Fiber ID back to a plain receptor's quantum, built by `quantum_for`
+    /// and dropped whenever a receptor method edits the spectrum.
+    pub(crate) reverse: OnceLock<HashMap<u16, Q>>,
+
+    /// Bins continuous readings to fiber IDs, for `send_analog`.
+    pub(crate) ranges: RangeSpectrum,
+
+    /// When set, repeats of the last fiber fired are suppressed.
+    pub(crate) edge_triggered: bool,
+    pub(crate) last_fired: Mutex<Option<u16>>,
+
+    /// Minimum time between impulses on a fiber, and when each last fired.
+    pub(crate) refractory: HashMap<u16, Duration>,
+    pub(crate) last_spike: Mutex<HashMap<u16, Instant>>,
+
+    /// When set, repeats of a fiber within this window of the previous
+    /// trigger are coalesced, and when each fiber was last triggered.
+    pub(crate) debounce: Option<Duration>,
+    pub(crate) last_trigger: Mutex<HashMap<u16, Instant>>,
+
+    /// Quanta that fire one of several fibers, chosen by weight.
+    pub(crate) weighted: HashMap<Q, WeightedFibers>,
+    pub(crate) rng: Mutex<StdRng>,
+
+    /// When set, new impulses are refused but the socket stays open.
+    pub(crate) quiescing: AtomicBool,
+
+    /// When set, impulses carry sequence numbers for loss detection.
+    pub(crate) sequenced: bool,
+    pub(crate) next_seq: AtomicU64,
+
+    /// When set, even plain impulses are sent with the tagged header.
+    pub(crate) tagged: bool,
+    pub(crate) codec: Arc<dyn ImpulseCodec>,
+
+    /// One past the highest sequence number acked by the motor.
+    pub(crate) acked: AtomicU64,
+
+    pub(crate) metrics: Metrics,
+    pub(crate) shutdown: ShutdownHook,
+    pub(crate) cancel: CancellationToken,
+
+    /// Set by `close`, so queue drivers send what is buffered before ending.
+    pub(crate) closing: AtomicBool,
+    /// One per queue driver, resolved when the driver ends (see `close`).
+    pub(crate) drivers: Mutex<Vec<oneshot::Receiver<()>>>,
 } 
 
 impl<Q: Hash + Eq> Sensor<Q> {
@@ -35,62 +114,617 @@ impl<Q: Hash + Eq> Sensor<Q> {
         address: SocketAddr
     ) -> Result<Self, BuildError> {
 
-        let mut sensor = Sensor {
-            tract_name: tract_name.to_owned(),
-            address,
-            socket: UdpSocket::bind(address).await?,
-            spectrum: HashMap::new()
-        };
+        let socket = UdpSocket::bind(address).await?;
+        let mut sensor = Sensor::with_transport(tract_name, address, socket);
 
         sensor.address = sensor.socket.local_addr()?;
         Ok(sensor)
     }
 
-    /// Maps a sensory bit to a new NeuronId.
-    /// NOTE: Overwrites existing quantum key without checking.
-    pub fn add_receptor(&mut self, quantum: Q, fid: u16) {
+    /// Bind and connect in one step, returning a sensor ready to send.
+    pub async fn connected(
+        tract_name: &str,
+        bind: SocketAddr,
+        peer: SocketAddr
+    ) -> Result<Self, BuildError> {
 
-        self.spectrum.insert(quantum, fid);
+        let mut sensor = Sensor::new(tract_name, bind).await?;
+        sensor.connect(&peer).await?;
+        Ok(sensor)
     }
 
     /// Connect to a remote socket. 
     /// Remember to ensure that the corresponding Input
     /// can handle all fiber IDs that will be sent by this sensor.
+    /// Returns the previous peer if this re-targets an existing connection.
     pub async fn connect(
         &mut self, 
         remote: &SocketAddr
-    ) -> Result<(), BuildError> {
+    ) -> Result<Option<SocketAddr>, BuildError> {
 
         self.socket.connect(remote).await?;
         self.address = remote.to_owned();
+        Ok(self.peer.replace(*remote))
+    }
+
+    /// Send every impulse to a broadcast address, enabling `SO_BROADCAST`.
+    pub fn add_broadcast_target(&mut self, target: SocketAddr) -> Result<bool, BuildError> {
+
+        self.socket.set_broadcast(true)?;
+        Ok(self.add_target(target))
+    }
+
+    /// Wait for a diagnostic from a motor with echoing enabled 
+    /// (see `Motor::set_echo_unrecognized`), returning the fiber ID it 
+    /// could not handle. Other datagrams received meanwhile are discarded.
+    pub async fn recv_unrecognized(&self) -> Result<u16, CommunicationError> {
+
+        let mut buffer = [0u8; 64];
+        loop {
+            let n_bytes = self.socket.recv(&mut buffer).await?;
+            if let Ok(ImpulseFrame::Unrecognized(fiber)) = self.codec.decode(&buffer[..n_bytes]) {
+                return Ok(fiber)
+            }
+        }
+    }
+
+    /// Send every impulse to a multicast group as well as the peer (if any).
+    /// Every motor that has joined the group (`Motor::join_group`) on that 
+    /// port receives it. Stop with `remove_target`.
+    /// Returns false if it was already a target.
+    /// NOTE: On BSD and macOS a connected sensor cannot send to the group
+    /// (`EISCONN`); see the `socket_options` module docs.
+    pub fn broadcast_to_group(&mut self, group: SocketAddr) -> Result<bool, BuildError> {
+
+        if !group.ip().is_multicast() {
+            let e = std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a multicast address");
+            return Err(e.into())
+        }
+        Ok(self.add_target(group))
+    }
+
+    /// Connect to a remote socket, failing if already connected.
+    pub async fn connect_once(
+        &mut self, 
+        remote: &SocketAddr
+    ) -> Result<(), BuildError> {
+
+        if let Some(peer) = self.peer {
+            return Err(BuildError::AlreadyConnected(peer))
+        }
+        self.connect(remote).await?;
+        Ok(())
+    }
+}
+
+impl<Q: Hash + Eq> Sensor<Q, CallbackTransport> {
+
+    /// Create a sensor with no socket that passes each fiber ID to `sink`.
+    /// Useful for embedding in a simulation with its own message bus.
+    /// The address field is left unspecified.
+    pub fn new_with_sink(
+        tract_name: &str, 
+        sink: impl Fn(u16) + Send + Sync + 'static
+    ) -> Self {
+
+        Self::new_with_sink_codec(tract_name, BincodeCodec, sink)
+    }
+
+    /// Like `new_with_sink`, encoding (and decoding for the sink) with `codec`.
+    /// NOTE: The sink keeps decoding with this codec, so use this rather 
+    /// than `set_codec` to change the codec of a sink sensor.
+    pub fn new_with_sink_codec(
+        tract_name: &str, 
+        codec: impl ImpulseCodec + 'static,
+        sink: impl Fn(u16) + Send + Sync + 'static
+    ) -> Self {
+
+        let codec: Arc<dyn ImpulseCodec> = Arc::new(codec);
+        let decoder = codec.clone();
+        let transport = CallbackTransport::new(move |datagram: &[u8]| {
+            if let Ok(frame) = decoder.decode(datagram) { frame.fibers().into_iter().for_each(&sink) }
+        });
+        let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0);
+        let mut sensor = Sensor::with_transport(tract_name, address, transport);
+        sensor.codec = codec;
+        sensor
+    }
+}
+
+impl<Q: Hash + Eq, T: Transport> Sensor<Q, T> {
+
+    /// Build a sensor around an already-established transport.
+    pub fn with_transport(
+        tract_name: &str, 
+        address: SocketAddr, 
+        transport: T
+    ) -> Self {
+
+        Sensor {
+            tract_name: tract_name.to_owned(),
+            id: TractId::next(),
+            address,
+            socket: transport,
+            advertised: None,
+            peer: None,
+            targets: Vec::new(),
+            auto_reconnect: false,
+            mtu: crate::wire::DEFAULT_MTU,
+            spectrum: HashMap::new(),
+            reverse: OnceLock::new(),
+            ranges: RangeSpectrum::new(),
+            edge_triggered: false,
+            last_fired: Mutex::new(None),
+            refractory: HashMap::new(),
+            last_spike: Mutex::new(HashMap::new()),
+            debounce: None,
+            last_trigger: Mutex::new(HashMap::new()),
+            weighted: HashMap::new(),
+            rng: Mutex::new(StdRng::from_entropy()),
+            quiescing: AtomicBool::new(false),
+            sequenced: false,
+            next_seq: AtomicU64::new(0),
+            tagged: false,
+            codec: Arc::new(BincodeCodec),
+            acked: AtomicU64::new(0),
+            metrics: Metrics::default(),
+            shutdown: ShutdownHook::default(),
+            cancel: CancellationToken::new(),
+            closing: AtomicBool::new(false),
+            drivers: Mutex::new(Vec::new())
+        }
+    }
+
+    /// Unique ID of this tract, distinct from the human-readable name.
+    pub fn tract_id(&self) -> TractId { self.id }
+
+    /// Remote address the sensor is connected to, if any.
+    pub fn peer(&self) -> Option<SocketAddr> { self.peer }
+
+    /// The address that peers should use to reach this sensor.
+    /// Defaults to the address field unless overridden.
+    pub fn advertised_address(&self) -> SocketAddr { 
+        self.advertised.unwrap_or(self.address) 
+    }
+
+    /// Override the address communicated to peers and Inputs,
+    /// without changing the address the socket is bound to.
+    pub fn set_advertised_address(&mut self, address: SocketAddr) {
+
+        self.advertised = Some(address);
+    }
+
+    /// Maps a sensory bit to a new NeuronId.
+    /// NOTE: Overwrites existing quantum key without checking.
+    pub fn add_receptor(&mut self, quantum: Q, fid: impl Into<FiberId>) {
+
+        let fid = fid.into().raw();
+        self.weighted.remove(&quantum);
+        self.spectrum.insert(quantum, fid);
+        self.reverse.take();
+    }
+
+    /// Maps a sensory bit to a new NeuronId, failing with `AlreadyMapped`
+    /// if the quantum already has a (plain or weighted) receptor.
+    pub fn try_add_receptor(&mut self, quantum: Q, fid: impl Into<FiberId>) -> Result<(), ConfigError> {
+
+        let fid = fid.into().raw();
+        if let Some(existing) = self.spectrum.get(&quantum) {
+            return Err(ConfigError::AlreadyMapped(*existing))
+        }
+        if let Some(weighted) = self.weighted.get(&quantum) {
+            return Err(ConfigError::AlreadyMapped(weighted.fibers()[0]))
+        }
+        self.spectrum.insert(quantum, fid);
+        self.reverse.take();
         Ok(())
     }
 
+    /// Removes a receptor (plain or weighted), returning the fiber ID 
+    /// it was mapped to (the first choice, if weighted).
+    pub fn remove_receptor(&mut self, quantum: &Q) -> Option<u16> {
+
+        self.reverse.take();
+        self.spectrum.remove(quantum)
+            .or_else(|| self.weighted.remove(quantum).map(|w| w.fibers()[0]))
+    }
+
+    /// Points an existing receptor at a different fiber ID,
+    /// returning the previous one, or `None` (mapping nothing) 
+    /// if the quantum has no plain receptor.
+    pub fn remap_receptor(&mut self, quantum: &Q, fid: u16) -> Option<u16> {
+
+        self.reverse.take();
+        self.spectrum.get_mut(quantum).map(|existing| std::mem::replace(existing, fid))
+    }
+
+    /// The quantum that fires `fid`, plain receptors first, then weighted.
+    /// If several quanta share the fiber, any one of them is returned.
+    /// NOTE: Plain receptors are indexed by fiber ID on first use, and the
+    /// index is rebuilt after edits through the receptor methods, 
+    /// but NOT when the `spectrum` field is edited directly.
+    /// Weighted receptors are searched only if no plain receptor matches.
+    pub fn quantum_for(&self, fid: u16) -> Option<&Q> 
+    where Q: Clone {
+
+        let index = self.reverse.get_or_init(|| {
+            self.spectrum.iter().map(|(quantum, f)| (*f, quantum.clone())).collect()
+        });
+        index.get(&fid).or_else(|| {
+            self.weighted.iter()
+                .find(|(_, w)| w.fibers().contains(&fid))
+                .map(|(quantum, _)| quantum)
+        })
+    }
+
+    /// Every plain receptor as a `(quantum, fiber ID)` pair, in no particular order.
+    pub fn receptors(&self) -> impl Iterator<Item = (&Q, u16)> + '_ {
+
+        self.spectrum.iter().map(|(quantum, fid)| (quantum, *fid))
+    }
+
+    /// Index from fiber ID back to quantum, for logging or for decoding 
+    /// on the motor side which stimulus a fiber stands for.
+    /// Covers plain receptors only; for shared fibers, any one quantum is kept.
+    pub fn reverse_spectrum(&self) -> HashMap<u16, &Q> {
+
+        self.receptors().map(|(quantum, fid)| (fid, quantum)).collect()
+    }
+
+    /// Chainable bulk `add_receptor`, e.g. from `spectrum::zip_spectrum`.
+    pub fn with_spectrum(mut self, receptors: impl IntoIterator<Item = (Q, u16)>) -> Self {
+
+        receptors.into_iter().for_each(|(quantum, fid)| self.add_receptor(quantum, fid));
+        self
+    }
+
+    /// Only fire when the fiber differs from the last one fired,
+    /// so a held stimulus produces one impulse instead of a stream.
+    /// Suppressed impulses are not an error.
+    pub fn set_edge_triggered(&mut self, edge_triggered: bool) {
+
+        self.edge_triggered = edge_triggered;
+    }
+
+    /// Send every impulse with the tagged header (magic bytes and version),
+    /// for motors that reject plain impulses (`Motor::set_reject_plain`).
+    /// NOTE: A plain cajal Input does not understand tagged impulses.
+    /// `connect` does not negotiate the wire version, even when tagged;
+    /// use `connect_with_handshake` for that.
+    pub fn set_tagged(&mut self, tagged: bool) {
+
+        self.tagged = tagged;
+    }
+
+    /// Serialize frames with `codec` instead of the default `BincodeCodec`.
+    /// The motor MUST use the same codec.
+    pub fn set_codec(&mut self, codec: impl ImpulseCodec + 'static) {
+
+        self.codec = Arc::new(codec);
+    }
+
+    /// Refuse impulses on the receptor for `quantum` until `period` has 
+    /// passed since it last fired, failing with `Refractory`.
+    /// `None` removes the refractory period. Returns false if 
+    /// `quantum` has no receptor in the spectrum.
+    /// NOTE: The period belongs to the fiber, so it is shared by 
+    /// every quantum that maps to it, and batches are not affected.
+    pub fn set_refractory(&mut self, quantum: &Q, period: Option<Duration>) -> bool {
+
+        let Some(fid) = self.spectrum.get(quantum).copied() else { return false };
+        match period {
+            Some(period) => self.refractory.insert(fid, period),
+            None => self.refractory.remove(&fid)
+        };
+        true
+    }
+
+    /// Coalesce bursts of the same quantum into a single impulse:
+    /// a repeat within `window` of the previous trigger is skipped
+    /// (returning `Ok`), and extends the burst by another `window`.
+    pub fn with_debounce(mut self, window: Duration) -> Self {
+
+        self.set_debounce(Some(window));
+        self
+    }
+
+    /// Change the debounce window, or disable debouncing with `None`.
+    pub fn set_debounce(&mut self, window: Option<Duration>) {
+
+        self.debounce = window;
+        self.last_trigger.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Whether `fid` repeats within the debounce window, 
+    /// recording the trigger either way.
+    fn is_bouncing(&self, fid: u16) -> bool {
+
+        let Some(window) = self.debounce else { return false };
+        let now = Instant::now();
+        let mut last = self.last_trigger.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = last.insert(fid, now);
+        previous.is_some_and(|triggered| now.duration_since(triggered) < window)
+    }
+
+    /// Whether `fid` is still within its refractory period, 
+    /// otherwise marking it as fired now.
+    fn is_refractory(&self, fid: u16) -> bool {
+
+        let Some(period) = self.refractory.get(&fid) else { return false };
+        let now = Instant::now();
+        let mut last = self.last_spike.lock().unwrap_or_else(|e| e.into_inner());
+        match last.get(&fid) {
+            Some(fired) if now.duration_since(*fired) < *period => true,
+            _ => { last.insert(fid, now); false }
+        }
+    }
+
+    /// Current counters for this sensor.
+    pub fn metrics(&self) -> TractMetrics { self.metrics.snapshot(&self.tract_name) }
+
+    /// Stop the sensor's background tasks (trains, tonic firing, drivers,
+    /// rate and Poisson coders) when `token` is cancelled. Tasks stop between 
+    /// impulses, never in the middle of a send. Share one token across tracts 
+    /// to stop a whole limb in one call.
+    /// The sensor keeps a child of `token`, so `close` stops only this sensor.
+    /// NOTE: Only tasks spawned after this call observe the new token.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+
+        self.cancel = token.child_token();
+    }
+
+    /// The token that stops this sensor's background tasks (a child of
+    /// the one given to `set_cancellation_token`).
+    pub fn cancellation_token(&self) -> &CancellationToken { &self.cancel }
+
+    /// Run `hook` with the final metrics when the sensor is dropped,
+    /// including when a task driving it ends. Replaces any previous hook.
+    pub fn on_shutdown(&mut self, hook: impl FnOnce(&TractMetrics) + Send + 'static) {
+
+        self.shutdown.set(&self.tract_name, &self.metrics, Box::new(hook));
+    }
+
+    /// Stop initiating impulses: sends fail with `Quiescing` until `resume`.
+    /// The socket stays open, so replies already in flight (e.g. acks)
+    /// can still be received while the sensor drains before shutdown.
+    pub fn quiesce(&self) { self.quiescing.store(true, Ordering::Release) }
+
+    /// Re-enable sending after `quiesce`.
+    pub fn resume(&self) { self.quiescing.store(false, Ordering::Release) }
+
+    /// Whether the sensor is currently refusing to send.
+    pub fn is_quiescing(&self) -> bool { self.quiescing.load(Ordering::Acquire) }
+
     /// Attempts to send a sensory datum as a neurotransmission impulse.
     pub async fn send_impulse(
         &self, 
         quantum: &Q
     ) -> Result<(), CommunicationError> {
 
-        if let Some(nid) = self.spectrum.get(quantum) {
-            let nid = bincode::serialize(nid)?;
-            self.socket.send(&nid).await?;
-            Ok(())
+        let fid = self.spectrum.get(quantum).copied()
+            .or_else(|| self.sample_weighted(quantum));
+
+        if let Some(nid) = fid {
+            self.transmit(nid).await
         } else { 
+            self.metrics.record_unrecognized();
             let name = self.tract_name.clone();
             Err(CommunicationError::UnrecognizedTrigger(name)) 
         }
     }
 
+    /// Fire the same quantum `count` times, encoding intensity as a burst.
+    /// If `interval` is given, sends are spaced by that duration.
+    /// Returns the number of impulses sent, or `PartialBurst` if a send 
+    /// fails after at least one impulse has gone out.
+    /// NOTE: An edge-triggered sensor will suppress all but the first impulse.
+    pub async fn send_burst(
+        &self,
+        quantum: &Q,
+        count: usize,
+        interval: Option<Duration>
+    ) -> Result<usize, CommunicationError> {
+
+        for sent in 0..count {
+            if sent > 0 {
+                if let Some(interval) = interval { tokio::time::sleep(interval).await }
+            }
+
+            if let Err(e) = self.send_impulse(quantum).await {
+                if sent == 0 { return Err(e) }
+                return Err(CommunicationError::PartialBurst { sent, source: Box::new(e) })
+            }
+        }
+        Ok(count)
+    }
+
+    /// Attempts to send a sensory datum along with a scalar (e.g. a magnitude),
+    /// which a motor reads with `recv_impulse_with`.
+    /// NOTE: This uses the tagged frame format, so the payload is not 
+    /// understood by a plain cajal Input.
+    pub async fn send_impulse_with(
+        &self, 
+        quantum: &Q,
+        payload: f32
+    ) -> Result<(), CommunicationError> {
+
+        let fid = self.spectrum.get(quantum).copied()
+            .or_else(|| self.sample_weighted(quantum));
+
+        if let Some(nid) = fid {
+            self.transmit_with(nid, Some(payload)).await
+        } else { 
+            self.metrics.record_unrecognized();
+            let name = self.tract_name.clone();
+            Err(CommunicationError::UnrecognizedTrigger(name)) 
+        }
+    }
+
+    /// Sends a fiber ID that has already been resolved from a quantum.
+    pub(crate) async fn transmit(&self, fid: u16) -> Result<(), CommunicationError> {
+
+        self.transmit_with(fid, None).await
+    }
+
+    /// Sends a resolved fiber ID, with a payload if given.
+    pub(crate) async fn transmit_with(
+        &self, 
+        fid: u16, 
+        payload: Option<f32>
+    ) -> Result<(), CommunicationError> {
+
+        if self.is_quiescing() { 
+            self.metrics.record_dropped();
+            return Err(CommunicationError::Quiescing) 
+        }
+
+        if self.is_bouncing(fid) {
+            self.metrics.record_dropped();
+            return Ok(())
+        }
+
+        if self.is_refractory(fid) {
+            self.metrics.record_dropped();
+            return Err(CommunicationError::Refractory(fid))
+        }
+
+        // The edge is claimed before sending, so concurrent repeats are suppressed,
+        // and handed back if the send fails, so a retry is not.
+        let mut edge = None;
+        if self.edge_triggered {
+            let mut last = self.last_fired.lock().unwrap_or_else(|e| e.into_inner());
+            if *last == Some(fid) { 
+                self.metrics.record_dropped();
+                return Ok(()) 
+            }
+            edge = Some(last.replace(fid));
+        }
+
+        let seq = self.sequenced.then(|| self.next_seq.fetch_add(1, Ordering::Relaxed));
+        let plain = match (seq, payload, self.tagged) {
+            (None, None, false) => self.codec.encode_plain(fid),
+            _ => None
+        };
+
+        // Plain impulses are encoded on the stack, so the common case never allocates.
+        let delivered = if let Some(nid) = plain {
+            self.deliver(&nid).await
+        } else {
+            let frame = ImpulseFrame::Data { seq, fiber: fid, payload };
+            let nid = if self.tagged { self.codec.encode_tagged(&frame)? } else { self.codec.encode(&frame)? };
+            self.deliver(&nid).await
+        };
+
+        if let Err(e) = delivered {
+            if let Some(previous) = edge {
+                let mut last = self.last_fired.lock().unwrap_or_else(|e| e.into_inner());
+                if *last == Some(fid) { *last = previous }
+            }
+            self.metrics.record_dropped();
+            return Err(e)
+        }
+        self.metrics.record_sent();
+        Ok(())
+    }
+
+    /// When enabled, a send that fails with `ConnectionRefused` or
+    /// `ConnectionReset` (e.g. the peer restarted) reconnects to the 
+    /// last peer and retries once before returning the error.
+    pub fn set_auto_reconnect(&mut self, enabled: bool) {
+
+        self.auto_reconnect = enabled;
+    }
+
+    /// Send a datagram to the connected peer (if any) and every target,
+    /// continuing past failures. With no peer and no targets, 
+    /// the transport decides where it goes.
+    /// A single destination fails with its own error; 
+    /// with several, every failure is reported in `FanOutFailed`.
+    pub(crate) async fn deliver(&self, datagram: &[u8]) -> Result<(), CommunicationError> {
+
+        let mut failed = Vec::new();
+        if self.peer.is_some() || self.targets.is_empty() {
+            if let Err(e) = self.send_to_peer(datagram).await {
+                failed.push((self.peer.unwrap_or(self.address), e));
+            }
+        }
+        for target in &self.targets {
+            if let Err(e) = self.socket.send_to(datagram, *target).await {
+                failed.push((*target, e));
+            }
+        }
+
+        let destinations = self.targets.len() + usize::from(self.peer.is_some());
+        match failed.len() {
+            0 => Ok(()),
+            1 if destinations <= 1 => Err(failed.remove(0).1.into()),
+            _ => Err(CommunicationError::FanOutFailed(failed))
+        }
+    }
+
+    /// Send on the connected transport, reconnecting once if allowed.
+    async fn send_to_peer(&self, datagram: &[u8]) -> std::io::Result<usize> {
+
+        use std::io::ErrorKind::{ ConnectionRefused, ConnectionReset };
+
+        match (self.socket.send(datagram).await, self.peer) {
+            (Err(e), Some(peer)) 
+            if self.auto_reconnect && matches!(e.kind(), ConnectionRefused | ConnectionReset) => {
+                self.socket.connect(peer).await?;
+                self.socket.send(datagram).await
+            },
+            (result, _) => result
+        }
+    }
+
+    /// The MTU batches are sized for. Defaults to `DEFAULT_MTU`.
+    pub fn mtu(&self) -> usize { self.mtu }
+
+    /// Size batches for a path with this MTU.
+    pub fn set_mtu(&mut self, mtu: usize) {
+
+        self.mtu = mtu;
+    }
+
+    /// The most plain impulses that fit in one datagram at the configured MTU.
+    pub fn recommended_batch_size(&self) -> usize {
+
+        crate::wire::max_impulses_per_datagram(0, self.mtu)
+    }
+
+    /// Every destination an impulse is sent to: 
+    /// the connected peer (if any) followed by the configured targets.
+    pub fn targets(&self) -> Vec<SocketAddr> {
+
+        self.peer.iter().chain(self.targets.iter()).copied().collect()
+    }
+
+    /// Send every impulse to `target` as well, without connecting to it.
+    /// Returns false if it was already a target.
+    pub fn add_target(&mut self, target: SocketAddr) -> bool {
+
+        if self.targets.contains(&target) { return false }
+        self.targets.push(target);
+        true
+    }
+
+    /// Stop sending to `target`. Returns false if it was not a target.
+    pub fn remove_target(&mut self, target: &SocketAddr) -> bool {
+
+        let before = self.targets.len();
+        self.targets.retain(|t| t != target);
+        self.targets.len() != before
+    }
+
 }
 
 
 use cajal_cx::tract::{ Tract, sender::TractSender };
 
-impl<Q: Hash + Eq> Tract for Sensor<Q> {
+impl<Q: Hash + Eq, T: Transport> Tract for Sensor<Q, T> {
     fn tract_name(&self) -> &str { &self.tract_name }
     fn num_fibers(&self) -> usize { self.spectrum.len() }
-    fn tract_address(&self) -> SocketAddr { self.address.clone() }
+    fn tract_address(&self) -> SocketAddr { self.advertised_address() }
 }
 
 impl<Q: Hash + Eq> TractSender for Sensor<Q> {
@@ -98,6 +732,317 @@ impl<Q: Hash + Eq> TractSender for Sensor<Q> {
     async fn set_target_address(&mut self, target_address: SocketAddr) -> Result<(), std::io::Error> {
         self.socket.connect(target_address).await?;
         self.address = target_address.clone();
+        self.peer = Some(target_address);
         Ok(())
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::net::SocketAddr;
+    use std::sync::{ Arc, Mutex, OnceLock };
+    use std::sync::atomic::{ AtomicBool, Ordering };
+    use std::time::Duration;
+
+    use crate::codec::RawCodec;
+    use crate::error::{ BuildError, CommunicationError, ConfigError };
+    use crate::transport::{ Transport, ChannelTransport };
+    use crate::types::FnMotor;
+    use super::Sensor;
+
+    #[test]
+    fn advertised_address_defaults_to_the_bound_one() {
+
+        let (a, _b) = ChannelTransport::pair(1);
+        let mut sensor: Sensor<u8, _> = Sensor::in_process("nat", a);
+        assert_eq!(sensor.advertised_address(), sensor.address);
+
+        let public = "203.0.113.7:4000".parse().unwrap();
+        sensor.set_advertised_address(public);
+        assert_eq!(sensor.advertised_address(), public);
+        assert_ne!(sensor.address, public);
+    }
+
+    #[tokio::test]
+    async fn sink_receives_fiber_ids() {
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let mut sensor = Sensor::new_with_sink("sim", move |fid| {
+            sink.lock().unwrap().push(fid)
+        });
+        sensor.add_receptor('a', 4);
+        sensor.add_receptor('b', 5);
+
+        sensor.send_impulse(&'a').await.unwrap();
+        sensor.set_sequenced(true);
+        sensor.send_impulse(&'b').await.unwrap();
+        assert_eq!(*received.lock().unwrap(), vec![4, 5]);
+    }
+
+    #[tokio::test]
+    async fn reconnecting_reports_the_previous_peer() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let first = "127.0.0.1:4001".parse().unwrap();
+        let second = "127.0.0.1:4002".parse().unwrap();
+        let mut sensor: Sensor<u8> = Sensor::new("twice", any).await.unwrap();
+
+        assert_eq!(sensor.connect(&first).await.unwrap(), None);
+        assert_eq!(sensor.connect(&second).await.unwrap(), Some(first));
+        assert!(matches!(
+            sensor.connect_once(&first).await, 
+            Err(BuildError::AlreadyConnected(peer)) if peer == second
+        ));
+        assert_eq!(sensor.peer(), Some(second));
+    }
+
+    #[tokio::test]
+    async fn quiescing_refuses_sends_until_resumed() {
+
+        let (a, b) = ChannelTransport::pair(4);
+        let sensor: Sensor<char, _> = Sensor::in_process("quiet", a).with_spectrum([('a', 1)]);
+
+        sensor.quiesce();
+        assert!(sensor.is_quiescing());
+        assert!(matches!(sensor.send_impulse(&'a').await, Err(CommunicationError::Quiescing)));
+
+        sensor.resume();
+        sensor.send_impulse(&'a').await.unwrap();
+
+        let mut buffer = [0u8; 8];
+        let (n_bytes, _) = b.recv_from(&mut buffer).await.unwrap();
+        assert_eq!(crate::wire::decode_impulse(&buffer[..n_bytes]).unwrap(), 1);
+        assert_eq!(sensor.metrics().dropped, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn burst_fires_count_times_at_the_interval() {
+
+        let (a, b) = ChannelTransport::pair(8);
+        let sensor: Sensor<char, _> = Sensor::in_process("burst", a).with_spectrum([('a', 1)]);
+
+        let start = tokio::time::Instant::now();
+        let sent = sensor.send_burst(&'a', 3, Some(Duration::from_millis(10))).await.unwrap();
+        assert_eq!(sent, 3);
+        assert_eq!(start.elapsed(), Duration::from_millis(20));
+
+        let mut buffer = [0u8; 8];
+        for _ in 0..3 { b.recv_from(&mut buffer).await.unwrap(); }
+        assert!(b.try_recv_from(&mut buffer).is_err());
+
+        assert!(matches!(
+            sensor.send_burst(&'z', 3, None).await,
+            Err(CommunicationError::UnrecognizedTrigger(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn targets_list_the_peer_first() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let peer = "127.0.0.1:4001".parse().unwrap();
+        let extra = "127.0.0.1:4002".parse().unwrap();
+        let mut sensor: Sensor<u8> = Sensor::new("targets", any).await.unwrap();
+        assert!(sensor.targets().is_empty());
+
+        assert!(sensor.add_target(extra));
+        assert!(!sensor.add_target(extra));
+        sensor.connect(&peer).await.unwrap();
+        assert_eq!(sensor.targets(), vec![peer, extra]);
+
+        assert!(sensor.remove_target(&extra));
+        assert!(!sensor.remove_target(&extra));
+        assert_eq!(sensor.targets(), vec![peer]);
+    }
+
+    /// Refuses every send until (re)connected.
+    #[derive(Default)]
+    struct Refusing { connected: AtomicBool }
+
+    impl Transport for Refusing {
+
+        async fn send(&self, datagram: &[u8]) -> std::io::Result<usize> {
+
+            if !self.connected.load(Ordering::SeqCst) { 
+                return Err(std::io::ErrorKind::ConnectionRefused.into()) 
+            }
+            Ok(datagram.len())
+        }
+
+        async fn connect(&self, _peer: SocketAddr) -> std::io::Result<()> {
+
+            self.connected.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn refused_send_reconnects_once_when_enabled() {
+
+        let peer = "127.0.0.1:4001".parse().unwrap();
+        let mut sensor = Sensor::with_transport("restart", peer, Refusing::default())
+            .with_spectrum([('a', 1)]);
+        sensor.peer = Some(peer);
+
+        assert!(matches!(sensor.send_impulse(&'a').await, Err(CommunicationError::SocketFailed(_))));
+
+        sensor.set_auto_reconnect(true);
+        sensor.send_impulse(&'a').await.unwrap();
+        assert_eq!(sensor.metrics().impulses_sent, 1);
+    }
+
+    #[tokio::test]
+    async fn failed_edges_are_retried() {
+
+        let peer = "127.0.0.1:4001".parse().unwrap();
+        let mut sensor = Sensor::with_transport("edge", peer, Refusing::default())
+            .with_spectrum([('a', 1)]);
+        sensor.set_edge_triggered(true);
+
+        assert!(sensor.send_impulse(&'a').await.is_err());
+        sensor.socket.connected.store(true, Ordering::SeqCst);
+        sensor.send_impulse(&'a').await.unwrap();
+        sensor.send_impulse(&'a').await.unwrap();
+
+        let metrics = sensor.metrics();
+        assert_eq!(metrics.impulses_sent, 1);
+        assert_eq!(metrics.dropped, 2);
+    }
+
+    #[tokio::test]
+    async fn fan_out_reaches_every_target_past_failures() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let left: FnMotor<(), ()> = FnMotor::new("left", any).await.unwrap();
+        let right: FnMotor<(), ()> = FnMotor::new("right", any).await.unwrap();
+        let unreachable: SocketAddr = "[::1]:4000".parse().unwrap();
+        let mut sensor: Sensor<u8> = Sensor::new("fan", any).await.unwrap().with_spectrum([(0, 1)]);
+        sensor.add_target(left.address);
+        sensor.add_target(unreachable);
+        sensor.add_target(right.address);
+
+        match sensor.send_impulse(&0).await {
+            Err(CommunicationError::FanOutFailed(failed)) => {
+                assert_eq!(failed.len(), 1);
+                assert_eq!(failed[0].0, unreachable);
+            },
+            other => panic!("unexpected {other:?}")
+        }
+        assert_eq!(left.recv_fiber().await.unwrap(), 1);
+        assert_eq!(right.recv_fiber().await.unwrap(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refractory_receptors_refuse_until_the_period_passes() {
+
+        let (a, _b) = ChannelTransport::pair(8);
+        let mut sensor: Sensor<char, _> = Sensor::in_process("refractory", a)
+            .with_spectrum([('a', 1), ('b', 2)]);
+        assert!(!sensor.set_refractory(&'z', Some(Duration::from_millis(100))));
+        assert!(sensor.set_refractory(&'a', Some(Duration::from_millis(100))));
+
+        sensor.send_impulse(&'a').await.unwrap();
+        assert!(matches!(sensor.send_impulse(&'a').await, Err(CommunicationError::Refractory(1))));
+        sensor.send_impulse(&'b').await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        sensor.send_impulse(&'a').await.unwrap();
+        let metrics = sensor.metrics();
+        assert_eq!((metrics.impulses_sent, metrics.dropped), (3, 1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn debounced_bursts_extend_their_window() {
+
+        let (a, _b) = ChannelTransport::pair(8);
+        let sensor: Sensor<char, _> = Sensor::in_process("debounce", a)
+            .with_spectrum([('a', 1)])
+            .with_debounce(Duration::from_millis(100));
+
+        // Every repeat lands within 100ms of the last, so the burst never ends.
+        for _ in 0..5 {
+            sensor.send_impulse(&'a').await.unwrap();
+            tokio::time::sleep(Duration::from_millis(60)).await;
+        }
+        assert_eq!(sensor.metrics().impulses_sent, 1);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        sensor.send_impulse(&'a').await.unwrap();
+        let metrics = sensor.metrics();
+        assert_eq!((metrics.impulses_sent, metrics.dropped), (2, 4));
+    }
+
+    #[test]
+    fn receptors_are_not_clobbered() {
+
+        let (a, _b) = ChannelTransport::pair(1);
+        let mut sensor: Sensor<char, _> = Sensor::in_process("mutate", a).with_spectrum([('a', 1)]);
+
+        assert!(matches!(sensor.try_add_receptor('a', 2), Err(ConfigError::AlreadyMapped(1))));
+        sensor.try_add_receptor('b', 2).unwrap();
+        assert_eq!(sensor.remap_receptor(&'b', 3), Some(2));
+        assert_eq!(sensor.remap_receptor(&'z', 3), None);
+        assert_eq!(sensor.remove_receptor(&'b'), Some(3));
+        assert_eq!(sensor.remove_receptor(&'b'), None);
+    }
+
+    #[test]
+    fn reverse_lookups_follow_receptor_edits() {
+
+        let (a, _b) = ChannelTransport::pair(1);
+        let mut sensor: Sensor<char, _> = Sensor::in_process("reverse", a)
+            .with_spectrum([('a', 1), ('b', 2), ('c', 2)]);
+        sensor.add_receptor_weighted('w', vec![(7, 1.0)]).unwrap();
+
+        assert_eq!(sensor.quantum_for(1), Some(&'a'));
+        assert!(matches!(sensor.quantum_for(2), Some('b' | 'c')));
+        assert_eq!(sensor.quantum_for(7), Some(&'w'));
+
+        sensor.remap_receptor(&'a', 3);
+        assert_eq!(sensor.quantum_for(1), None);
+        assert_eq!(sensor.quantum_for(3), Some(&'a'));
+
+        sensor.remove_receptor(&'b');
+        assert_eq!(sensor.quantum_for(2), Some(&'c'));
+        sensor.add_receptor('c', 4);
+        assert_eq!(sensor.quantum_for(2), None);
+        assert_eq!(sensor.spectrum.len(), 2);
+        assert_eq!(sensor.reverse_spectrum()[&4], &'c');
+    }
+
+    #[test]
+    fn receptors_do_not_need_clonable_quanta() {
+
+        #[derive(Debug, PartialEq, Eq, Hash)]
+        struct Stimulus(u8);
+
+        let (a, _b) = ChannelTransport::pair(1);
+        let mut sensor: Sensor<Stimulus, _> = Sensor::in_process("opaque", a)
+            .with_spectrum([(Stimulus(1), 1)]);
+        sensor.add_receptor(Stimulus(2), 2);
+        sensor.remap_receptor(&Stimulus(1), 3);
+
+        assert_eq!(sensor.spectrum.get(&Stimulus(1)), Some(&3));
+        assert_eq!(sensor.reverse_spectrum()[&2], &Stimulus(2));
+    }
+
+    #[tokio::test]
+    async fn sinks_decode_with_the_sensor_codec() {
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let mut sensor = Sensor::new_with_sink_codec("raw", RawCodec, move |fid| {
+            sink.lock().unwrap().push(fid)
+        });
+        sensor.add_receptor('a', 4);
+        sensor.set_sequenced(true);
+
+        sensor.send_impulse(&'a').await.unwrap();
+        assert!(matches!(sensor.send_impulse(&'z').await, Err(CommunicationError::UnrecognizedTrigger(name)) if name == "raw"));
+        sensor.send_impulses(&['a', 'a']).await.unwrap();
+        assert_eq!(*received.lock().unwrap(), vec![4, 4, 4]);
+    }
+}
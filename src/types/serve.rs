@@ -0,0 +1,128 @@
+
+use tokio::sync::{ mpsc, oneshot };
+use tokio::task::JoinHandle;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use super::Motor;
+
+
+/// Errors held by a `MotorHandle` before new errors are dropped.
+pub const SERVE_ERROR_CAPACITY: usize = 64;
+
+/// Handle to a motor's receive loop, returned by `Motor::serve`.
+/// Dropping the handle aborts the loop; use `shutdown` to stop it
+/// gracefully and get the motor back.
+pub struct MotorHandle<B: Fn(A) -> R, A, R, T: Transport> {
+    stop: Option<oneshot::Sender<()>>,
+    errors: mpsc::Receiver<CommunicationError>,
+    task: JoinHandle<Motor<B, A, R, T>>
+}
+
+impl<B: Fn(A) -> R, A, R, T: Transport> MotorHandle<B, A, R, T> {
+
+    /// Wait for the next error raised by the loop.
+    /// Returns `None` once the loop has ended and all errors are read.
+    pub async fn next_error(&mut self) -> Option<CommunicationError> {
+
+        self.errors.recv().await
+    }
+
+    /// Take an error without waiting, if one is queued.
+    pub fn try_next_error(&mut self) -> Option<CommunicationError> {
+
+        self.errors.try_recv().ok()
+    }
+
+    /// Whether the loop has ended (shut down or panicked).
+    pub fn is_finished(&self) -> bool { self.task.is_finished() }
+
+    /// Stop receiving after the behavior in progress (if any) returns,
+    /// and get the motor back. Returns `None` if the loop panicked.
+    /// NOTE: Errors still queued on the handle are discarded.
+    pub async fn shutdown(mut self) -> Option<Motor<B, A, R, T>> {
+
+        if let Some(stop) = self.stop.take() { let _ = stop.send(()); }
+        (&mut self.task).await.ok()
+    }
+}
+
+impl<B: Fn(A) -> R, A, R, T: Transport> Drop for MotorHandle<B, A, R, T> {
+    fn drop(&mut self) {
+        self.task.abort()
+    }
+}
+
+impl<B, A, R, T> Motor<B, A, R, T>
+where
+    B: Fn(A) -> R + Send + Sync + 'static,
+    A: Send + 'static,
+    R: Send + 'static,
+    T: Transport + Send + Sync + 'static
+{
+
+    /// Spawn the receive loop: every impulse runs its behavior with
+    /// an argument from `args`, and results are discarded.
+    /// Errors (unrecognized impulses, socket and decoding errors) do not
+    /// end the loop; they are sent to the returned handle instead.
+    /// The loop also ends when the motor's cancellation token is cancelled.
+    /// NOTE: If errors are not read, the newest are dropped once
+    /// `SERVE_ERROR_CAPACITY` are queued.
+    pub fn serve<F>(self, mut args: F) -> MotorHandle<B, A, R, T>
+    where
+        F: FnMut() -> A + Send + 'static
+    {
+
+        let (stop, mut stopped) = oneshot::channel();
+        let (error_tx, errors) = mpsc::channel(SERVE_ERROR_CAPACITY);
+
+        let task = tokio::spawn(async move {
+            loop {
+                let result = tokio::select! {
+                    biased;
+                    _ = &mut stopped => break,
+                    _ = self.cancel.cancelled() => break,
+                    received = self.recv_fiber() => {
+                        received.and_then(|fiber| self.dispatch(fiber, args()))
+                    }
+                };
+                if let Err(e) = result { let _ = error_tx.try_send(e); }
+            }
+            self
+        });
+
+        MotorHandle { stop: Some(stop), errors, task }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::atomic::{ AtomicUsize, Ordering };
+    use crate::error::CommunicationError;
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+
+    static SERVED: AtomicUsize = AtomicUsize::new(0);
+
+    #[tokio::test]
+    async fn serve_reports_errors_and_returns_the_motor() {
+
+        let (a, b) = ChannelTransport::pair(4);
+        let sensor: Sensor<char, _> = Sensor::in_process("serve", a).with_spectrum([('a', 1), ('z', 9)]);
+        let motor: Motor<fn(()), (), (), _> = Motor::in_process("serve", b)
+            .with_fiber(1, |_| { SERVED.fetch_add(1, Ordering::SeqCst); });
+        let mut handle = motor.serve(|| ());
+
+        sensor.send_impulse(&'z').await.unwrap();
+        assert!(matches!(handle.next_error().await, Some(CommunicationError::UnrecognizedImpulse(9))));
+
+        sensor.send_impulse(&'a').await.unwrap();
+        while SERVED.load(Ordering::SeqCst) == 0 { tokio::task::yield_now().await }
+        assert!(!handle.is_finished());
+
+        let motor = handle.shutdown().await.unwrap();
+        assert_eq!(motor.metrics().impulses_received, 2);
+    }
+}
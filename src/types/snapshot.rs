@@ -0,0 +1,219 @@
+
+//! Serializable state for recreating a tract in another process,
+//! e.g. for hot-standby failover. Live sockets are never captured;
+//! a restored tract binds a fresh socket.
+
+use std::net::SocketAddr;
+use std::hash::Hash;
+use std::sync::atomic::AtomicU64;
+use std::time::Duration;
+use serde::{ Serialize, Deserialize };
+
+use crate::codec::CodecKind;
+use crate::error::BuildError;
+use crate::metrics::{ Metrics, TractMetrics };
+use crate::registry::BehaviorRegistry;
+use crate::transport::Transport;
+use super::{ Sensor, Motor };
+
+
+/// Everything needed to rebuild a `Sensor`, minus its socket:
+/// its wiring, its modes and limits, its metrics and its sequence position.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensorSnapshot<Q> {
+    pub tract_name: String,
+    pub advertised: Option<SocketAddr>,
+    pub peer: Option<SocketAddr>,
+    pub targets: Vec<SocketAddr>,
+    pub spectrum: Vec<(Q, u16)>,
+
+    /// Quanta that fire one of several fibers, with each fiber's weight.
+    pub weighted: Vec<(Q, Vec<(u16, f64)>)>,
+
+    /// Refractory period of each fiber.
+    pub refractory: Vec<(u16, Duration)>,
+    pub debounce: Option<Duration>,
+    pub edge_triggered: bool,
+    pub sequenced: bool,
+
+    /// Sequence number of the next sequenced impulse, so the motor's
+    /// loss accounting carries on across the failover.
+    pub next_seq: u64,
+    pub tagged: bool,
+    pub codec: CodecKind,
+    pub mtu: usize,
+    pub auto_reconnect: bool,
+    pub metrics: TractMetrics
+}
+
+impl<Q: Hash + Eq> Sensor<Q> {
+
+    /// Capture the sensor's state.
+    pub fn snapshot(&self) -> SensorSnapshot<Q> 
+    where Q: Clone {
+
+        let mut refractory: Vec<(u16, Duration)> = self.refractory.iter()
+            .map(|(fid, period)| (*fid, *period))
+            .collect();
+        refractory.sort_by_key(|(fid, _)| *fid);
+
+        SensorSnapshot {
+            tract_name: self.tract_name.clone(),
+            advertised: self.advertised,
+            peer: self.peer,
+            targets: self.targets.clone(),
+            spectrum: self.spectrum.iter()
+                .map(|(quantum, fid)| (quantum.clone(), *fid))
+                .collect(),
+            weighted: self.weighted.iter()
+                .map(|(quantum, choices)| (quantum.clone(), choices.choices()))
+                .collect(),
+            refractory,
+            debounce: self.debounce,
+            edge_triggered: self.edge_triggered,
+            sequenced: self.sequenced,
+            next_seq: self.sent_count(),
+            tagged: self.tagged,
+            codec: self.codec.kind(),
+            mtu: self.mtu,
+            auto_reconnect: self.auto_reconnect,
+            metrics: self.metrics()
+        }
+    }
+
+    /// Rebuild a sensor from a snapshot on a new socket bound to `bind`,
+    /// reconnecting to the snapshot's peer if it had one.
+    /// The restored sensor is a new tract with its own `TractId`,
+    /// and its metrics carry on from the snapshot's.
+    /// NOTE: A `CodecKind::Custom` codec cannot be rebuilt, so the restored
+    /// sensor uses `BincodeCodec` until `set_codec` is called again.
+    pub async fn restore(
+        snapshot: SensorSnapshot<Q>, 
+        bind: SocketAddr
+    ) -> Result<Self, BuildError> {
+
+        let mut sensor = Sensor::new(&snapshot.tract_name, bind).await?;
+        sensor.advertised = snapshot.advertised;
+        sensor.spectrum = snapshot.spectrum.into_iter().collect();
+        for (quantum, choices) in snapshot.weighted {
+            sensor.add_receptor_weighted(quantum, choices)?;
+        }
+        sensor.refractory = snapshot.refractory.into_iter().collect();
+        sensor.debounce = snapshot.debounce;
+        sensor.edge_triggered = snapshot.edge_triggered;
+        sensor.sequenced = snapshot.sequenced;
+        sensor.next_seq = AtomicU64::new(snapshot.next_seq);
+        sensor.tagged = snapshot.tagged;
+        if let Some(codec) = snapshot.codec.codec() { sensor.codec = codec }
+        sensor.mtu = snapshot.mtu;
+        sensor.auto_reconnect = snapshot.auto_reconnect;
+        sensor.metrics = Metrics::resume(&snapshot.metrics);
+
+        if let Some(peer) = snapshot.peer {
+            sensor.connect(&peer).await?;
+        }
+        snapshot.targets.into_iter().for_each(|target| { sensor.add_target(target); });
+        Ok(sensor)
+    }
+}
+
+
+/// Everything needed to rebuild a `Motor` against a `BehaviorRegistry`.
+/// Only fibers added with `add_named_fiber` can be captured,
+/// since anonymous behaviors cannot be resolved elsewhere.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MotorSnapshot {
+    pub tract_name: String,
+    pub fibers: Vec<(u16, String)>
+}
+
+impl<B: Fn(A) -> R, A, R, T: Transport> Motor<B, A, R, T> {
+
+    /// Capture the motor's named wiring.
+    pub fn snapshot(&self) -> MotorSnapshot {
+
+        let mut fibers: Vec<(u16, String)> = self.fiber_names.iter()
+            .map(|(fid, name)| (*fid, name.clone()))
+            .collect();
+        fibers.sort();
+
+        MotorSnapshot { tract_name: self.tract_name.clone(), fibers }
+    }
+}
+
+impl<B: Fn(A) -> R, A, R> Motor<B, A, R> {
+
+    /// Rebuild a motor from a snapshot on a new socket bound to `bind`,
+    /// resolving every fiber's behavior by name from `registry`.
+    /// The restored motor is a new tract with its own `TractId`.
+    pub async fn restore(
+        snapshot: MotorSnapshot,
+        registry: &BehaviorRegistry<B>,
+        bind: SocketAddr
+    ) -> Result<Self, BuildError> 
+    where B: Clone {
+
+        let mut motor = Motor::new(&snapshot.tract_name, bind).await?;
+        for (fid, name) in &snapshot.fibers {
+            motor.add_named_fiber(*fid, name, registry)?;
+        }
+        Ok(motor)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use crate::codec::{ CodecKind, RawCodec };
+    use crate::registry::BehaviorRegistry;
+    use crate::types::{ Sensor, Motor };
+
+    #[tokio::test]
+    async fn restores_a_sensor_and_motor_on_fresh_sockets() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let mut motor: Motor<fn(u8) -> u8, u8, u8> = Motor::new("failover", any).await.unwrap();
+        let registry = BehaviorRegistry::new()
+            .with("double", (|x| x * 2) as fn(u8) -> u8);
+        motor.add_named_fiber(3, "double", &registry).unwrap();
+
+        let mut sensor: Sensor<char> = Sensor::new("failover", any).await.unwrap()
+            .with_spectrum([('a', 3)]);
+        sensor.connect(&motor.address).await.unwrap();
+        sensor.add_receptor_weighted('w', vec![(3, 1.0), (4, 3.0)]).unwrap();
+        sensor.set_refractory(&'a', Some(Duration::from_millis(5)));
+        sensor.set_debounce(Some(Duration::from_millis(2)));
+        sensor.set_edge_triggered(true);
+        sensor.set_sequenced(true);
+        sensor.set_tagged(true);
+        sensor.set_codec(RawCodec);
+        sensor.set_mtu(9000);
+        sensor.set_auto_reconnect(true);
+        sensor.add_target("127.0.0.1:9".parse().unwrap());
+        sensor.send_impulse(&'a').await.unwrap();
+
+        let snapshot = sensor.snapshot();
+        let restored = Sensor::restore(snapshot.clone(), any).await.unwrap();
+        assert_ne!(restored.tract_id(), sensor.tract_id());
+        assert_eq!(restored.peer(), Some(motor.address));
+        assert_eq!(restored.spectrum, sensor.spectrum);
+        assert_eq!(restored.targets(), sensor.targets());
+        assert_eq!(restored.weighted[&'w'].choices(), vec![(3, 1.0), (4, 3.0)]);
+        assert_eq!(restored.refractory, sensor.refractory);
+        assert_eq!(restored.debounce, Some(Duration::from_millis(2)));
+        assert!(restored.edge_triggered && restored.sequenced && restored.tagged && restored.auto_reconnect);
+        assert_eq!(restored.sent_count(), 1);
+        assert_eq!(restored.codec.kind(), CodecKind::Raw);
+        assert_eq!(restored.mtu(), 9000);
+        assert_eq!(restored.metrics(), sensor.metrics());
+        assert_eq!(restored.snapshot().spectrum, snapshot.spectrum);
+
+        let standby = Motor::restore(motor.snapshot(), &registry, any).await.unwrap();
+        assert_eq!(standby.snapshot().fibers, vec![(3, "double".to_owned())]);
+        assert_eq!(standby.dispatch(3, 4).unwrap(), 8);
+    }
+}
@@ -0,0 +1,265 @@
+
+//! Kernel socket options for UDP tracts.
+//! The OS may clamp (or, on Linux, double) requested buffer sizes,
+//! so read the effective value back after setting it.
+//!
+//! Multicast and unicast on one sensor: impulses go to the connected peer
+//! (unicast) and to every multicast target, from the same socket.
+//! The outgoing interface for multicast is chosen by the routing table
+//! unless set with `set_multicast_interface_v4`/`_v6`; a sensor bound to a
+//! specific unicast address uses it as the source for both.
+//! With multicast loop on (the OS default), receivers on the sending host,
+//! including its own motors, also get each multicast impulse.
+//!
+//! NOTE: BSD and macOS refuse `send_to` on a connected UDP socket (`EISCONN`),
+//! so there a sensor with a peer cannot also send to group targets;
+//! each target then fails in `FanOutFailed`. Use a second, unconnected 
+//! sensor for the group on those platforms.
+//!
+//! `bind_device` (`SO_BINDTODEVICE`) is only available on Linux and Android,
+//! and needs `CAP_NET_RAW` on most kernels; elsewhere it returns `Unsupported`.
+
+use std::io;
+use std::net::{ IpAddr, Ipv4Addr };
+use std::hash::Hash;
+use socket2::SockRef;
+use tokio::net::UdpSocket;
+
+use super::{ Sensor, Motor };
+
+
+impl<Q: Hash + Eq> Sensor<Q> {
+
+    /// Effective kernel receive buffer size, in bytes.
+    pub fn recv_buffer_size(&self) -> io::Result<usize> {
+        SockRef::from(&self.socket).recv_buffer_size()
+    }
+
+    /// Effective kernel send buffer size, in bytes.
+    pub fn send_buffer_size(&self) -> io::Result<usize> {
+        SockRef::from(&self.socket).send_buffer_size()
+    }
+
+    /// Request a kernel receive buffer size, in bytes.
+    pub fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
+        SockRef::from(&self.socket).set_recv_buffer_size(size)
+    }
+
+    /// Request a kernel send buffer size, in bytes.
+    pub fn set_send_buffer_size(&self, size: usize) -> io::Result<()> {
+        SockRef::from(&self.socket).set_send_buffer_size(size)
+    }
+
+    /// Send and receive only through the named network interface (e.g. "eth1").
+    pub fn bind_device(&self, interface: &str) -> io::Result<()> {
+        bind_device(&self.socket, interface)
+    }
+
+    /// Whether multicast impulses are also delivered back to this host.
+    /// Applies to the socket's address family.
+    pub fn set_multicast_loop(&self, enabled: bool) -> io::Result<()> {
+        if self.socket.local_addr()?.is_ipv4() {
+            self.socket.set_multicast_loop_v4(enabled)
+        } else {
+            self.socket.set_multicast_loop_v6(enabled)
+        }
+    }
+
+    /// Send IPv4 multicast through the interface with this local address.
+    pub fn set_multicast_interface_v4(&self, interface: Ipv4Addr) -> io::Result<()> {
+        SockRef::from(&self.socket).set_multicast_if_v4(&interface)
+    }
+
+    /// Send IPv6 multicast through the interface with this index (0 for any).
+    pub fn set_multicast_interface_v6(&self, interface: u32) -> io::Result<()> {
+        SockRef::from(&self.socket).set_multicast_if_v6(interface)
+    }
+}
+
+impl<B: Fn(A) -> R, A, R> Motor<B, A, R> {
+
+    /// Effective kernel receive buffer size, in bytes.
+    pub fn recv_buffer_size(&self) -> io::Result<usize> {
+        SockRef::from(&self.socket).recv_buffer_size()
+    }
+
+    /// Effective kernel send buffer size, in bytes.
+    pub fn send_buffer_size(&self) -> io::Result<usize> {
+        SockRef::from(&self.socket).send_buffer_size()
+    }
+
+    /// Request a kernel receive buffer size, in bytes.
+    pub fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
+        SockRef::from(&self.socket).set_recv_buffer_size(size)
+    }
+
+    /// Request a kernel send buffer size, in bytes.
+    pub fn set_send_buffer_size(&self, size: usize) -> io::Result<()> {
+        SockRef::from(&self.socket).set_send_buffer_size(size)
+    }
+
+    /// Send and receive only through the named network interface (e.g. "eth1").
+    pub fn bind_device(&self, interface: &str) -> io::Result<()> {
+        bind_device(&self.socket, interface)
+    }
+
+    /// Receive impulses sent to a multicast group, on any interface.
+    /// The motor must be bound to the group's port on the unspecified 
+    /// address (e.g. `0.0.0.0:port`) for the datagrams to arrive.
+    pub fn join_group(&self, group: IpAddr) -> io::Result<()> {
+        match group {
+            IpAddr::V4(group) => self.socket.join_multicast_v4(group, Ipv4Addr::UNSPECIFIED),
+            IpAddr::V6(group) => self.socket.join_multicast_v6(&group, 0)
+        }
+    }
+
+    /// Stop receiving impulses sent to a multicast group.
+    pub fn leave_group(&self, group: IpAddr) -> io::Result<()> {
+        match group {
+            IpAddr::V4(group) => self.socket.leave_multicast_v4(group, Ipv4Addr::UNSPECIFIED),
+            IpAddr::V6(group) => self.socket.leave_multicast_v6(&group, 0)
+        }
+    }
+}
+
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn bind_device(socket: &UdpSocket, interface: &str) -> io::Result<()> {
+    SockRef::from(socket).bind_device(Some(interface.as_bytes()))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn bind_device(_socket: &UdpSocket, _interface: &str) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "SO_BINDTODEVICE is not available on this platform"))
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::net::{ IpAddr, Ipv4Addr, SocketAddr };
+    use std::time::Duration;
+    use tokio::net::UdpSocket;
+    use crate::error::CommunicationError;
+    use crate::types::{ Sensor, Motor, FnMotor };
+
+    const WAIT: Duration = Duration::from_millis(500);
+
+    /// A motor that has joined `group`, and the group address on its port,
+    /// or None if multicast loopback is unavailable on this host 
+    /// (no multicast route, or a probe impulse never arrives).
+    async fn group_member(group: Ipv4Addr) -> Option<(FnMotor<(), u16>, SocketAddr)> {
+
+        let motor = FnMotor::<(), u16>::new("member", "0.0.0.0:0".parse().unwrap()).await.ok()?
+            .with_fiber(0, |_| 0)
+            .with_fiber(1, |_| 1)
+            .with_fiber(2, |_| 2);
+        motor.join_group(IpAddr::V4(group)).ok()?;
+        let target = SocketAddr::new(IpAddr::V4(group), motor.address.port());
+
+        let probe = UdpSocket::bind("0.0.0.0:0").await.ok()?;
+        probe.send_to(&crate::wire::plain_impulse(0), target).await.ok()?;
+        motor.recv_impulse_timeout((), WAIT).await.ok()?;
+        Some((motor, target))
+    }
+
+    #[tokio::test]
+    async fn buffer_sizes_are_readable_after_bind() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let sensor: Sensor<u8> = Sensor::new("buffers", any).await.unwrap();
+        let motor: Motor<fn(()), (), ()> = Motor::new("buffers", any).await.unwrap();
+
+        sensor.set_send_buffer_size(32 * 1024).unwrap();
+        motor.set_recv_buffer_size(32 * 1024).unwrap();
+        assert!(sensor.send_buffer_size().unwrap() > 0);
+        assert!(motor.recv_buffer_size().unwrap() > 0);
+
+        // Linux doubles the request to leave room for bookkeeping.
+        #[cfg(target_os = "linux")]
+        assert!(motor.recv_buffer_size().unwrap() >= 32 * 1024);
+    }
+
+    #[tokio::test]
+    async fn binding_to_a_missing_interface_fails() {
+
+        let sensor: Sensor<u8> = Sensor::new("device", "127.0.0.1:0".parse().unwrap()).await.unwrap();
+        assert!(sensor.bind_device("no-such-if0").is_err());
+    }
+
+    #[tokio::test]
+    async fn multicast_groups_are_targets_beside_the_peer() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let peer = "127.0.0.1:4001".parse().unwrap();
+        let group = "239.1.2.3:4002".parse().unwrap();
+        let mut sensor: Sensor<u8> = Sensor::connected("multi", any, peer).await.unwrap();
+
+        assert!(sensor.broadcast_to_group("127.0.0.1:4003".parse().unwrap()).is_err());
+        assert!(sensor.broadcast_to_group(group).unwrap());
+        assert_eq!(sensor.targets(), vec![peer, group]);
+    }
+
+    // Connected sockets cannot `send_to` other addresses on BSD and macOS.
+    #[cfg(not(any(
+        target_os = "macos", target_os = "ios", 
+        target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"
+    )))]
+    #[tokio::test]
+    async fn unicast_reaches_the_peer_and_multicast_reaches_the_group() {
+
+        let Some((member, group)) = group_member("239.1.2.4".parse().unwrap()).await else {
+            eprintln!("skipping: multicast loopback is unavailable");
+            return
+        };
+        let peer = FnMotor::<(), u16>::new("unicast", "127.0.0.1:0".parse().unwrap()).await.unwrap()
+            .with_fiber(1, |_| 1)
+            .with_fiber(2, |_| 2);
+        let mut sensor: Sensor<char> = Sensor::connected("both", "0.0.0.0:0".parse().unwrap(), peer.address).await.unwrap()
+            .with_spectrum([('a', 1), ('b', 2)]);
+
+        sensor.send_impulse(&'a').await.unwrap();
+        sensor.broadcast_to_group(group).unwrap();
+        sensor.send_impulse(&'b').await.unwrap();
+
+        // The peer gets both; the group only what was sent after it became a target.
+        assert_eq!(peer.recv_impulse_timeout((), WAIT).await.unwrap(), 1);
+        assert_eq!(peer.recv_impulse_timeout((), WAIT).await.unwrap(), 2);
+        assert_eq!(member.recv_impulse_timeout((), WAIT).await.unwrap(), 2);
+        assert!(matches!(member.recv_impulse_timeout((), WAIT).await, Err(CommunicationError::Timeout)));
+
+        // With loop off, this host no longer hears the group.
+        sensor.set_multicast_loop(false).unwrap();
+        sensor.send_impulse(&'b').await.unwrap();
+        assert_eq!(peer.recv_impulse_timeout((), WAIT).await.unwrap(), 2);
+        assert!(matches!(member.recv_impulse_timeout((), WAIT).await, Err(CommunicationError::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn only_multicast_groups_can_be_joined() {
+
+        let motor: Motor<fn(()), (), ()> = Motor::new("group", "0.0.0.0:0".parse().unwrap()).await.unwrap();
+
+        assert!(motor.join_group("127.0.0.1".parse().unwrap()).is_err());
+        assert!(motor.leave_group("239.1.2.3".parse().unwrap()).is_err());
+    }
+
+    #[tokio::test]
+    async fn joined_members_receive_group_impulses_until_they_leave() {
+
+        let Some((member, group)) = group_member("239.1.2.5".parse().unwrap()).await else {
+            eprintln!("skipping: multicast loopback is unavailable");
+            return
+        };
+        let mut sensor: Sensor<char> = Sensor::new("group", "0.0.0.0:0".parse().unwrap()).await.unwrap()
+            .with_spectrum([('a', 1), ('b', 2)]);
+        sensor.broadcast_to_group(group).unwrap();
+
+        sensor.send_impulse(&'a').await.unwrap();
+        assert_eq!(member.recv_impulse_timeout((), WAIT).await.unwrap(), 1);
+
+        member.leave_group(group.ip()).unwrap();
+        sensor.send_impulse(&'b').await.unwrap();
+        assert!(matches!(member.recv_impulse_timeout((), WAIT).await, Err(CommunicationError::Timeout)));
+    }
+}
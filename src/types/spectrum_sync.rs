@@ -0,0 +1,235 @@
+
+//! Reliable transfer of a `Sensor` spectrum to a peer node over UDP.
+//! The spectrum is serialized and split into `SYNC_CHUNK_SIZE` chunks.
+//! The receiver replies `Retransmit` listing missing chunks when a round
+//! times out, and `Complete` once every chunk has arrived;
+//! chunks may arrive in any order. The sender waits longer than a
+//! receiver's round before resending everything, so it hears `Retransmit`
+//! first whenever the link is up.
+
+use std::net::{ SocketAddr, IpAddr, Ipv4Addr, Ipv6Addr };
+use std::time::Duration;
+use std::hash::Hash;
+use tokio::net::UdpSocket;
+use tokio::time::{ timeout, timeout_at, Instant };
+use serde::{ Serialize, Deserialize, de::DeserializeOwned };
+
+use crate::error::CommunicationError;
+use super::Sensor;
+
+
+/// Payload bytes per chunk, small enough to fit a typical MTU.
+pub const SYNC_CHUNK_SIZE: usize = 1024;
+/// Largest serialized spectrum accepted, so a peer cannot make
+/// the receiver allocate for an arbitrary chunk count.
+pub const SYNC_MAX_SPECTRUM_LEN: usize = 1 << 24;
+const SYNC_MAX_CHUNKS: usize = SYNC_MAX_SPECTRUM_LEN / SYNC_CHUNK_SIZE;
+const SYNC_ROUND_TIMEOUT: Duration = Duration::from_millis(500);
+const SYNC_SENDER_TIMEOUT: Duration = Duration::from_millis(1000);
+const SYNC_MAX_ROUNDS: usize = 8;
+
+#[derive(Serialize, Deserialize)]
+enum SyncMessage {
+    Chunk { index: u32, total: u32, data: Vec<u8> },
+    Retransmit(Vec<u32>),
+    Complete
+}
+
+impl<Q: Hash + Eq> Sensor<Q> {
+
+    /// Push this sensor's spectrum to a peer calling `receive_spectrum`.
+    /// Uses a separate ephemeral socket, so the sensor's own connection
+    /// is not disturbed. Fails with `Timeout` if the peer stops answering.
+    pub async fn sync_spectrum(
+        &self, 
+        peer: SocketAddr
+    ) -> Result<(), CommunicationError> 
+    where Q: Serialize {
+
+        let entries: Vec<(&Q, &u16)> = self.spectrum.iter().collect();
+        let bytes = bincode::serialize(&entries)?;
+        let chunks: Vec<&[u8]> = bytes.chunks(SYNC_CHUNK_SIZE).collect();
+        if chunks.len() > SYNC_MAX_CHUNKS {
+            return Err(CommunicationError::TransferTooLarge(chunks.len()))
+        }
+        let total = chunks.len() as u32;
+
+        let local = match peer {
+            SocketAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+            SocketAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+        };
+        let socket = UdpSocket::bind(local).await?;
+
+        let send_chunk = |index: u32| {
+            let message = SyncMessage::Chunk { 
+                index, 
+                total, 
+                data: chunks[index as usize].to_vec() 
+            };
+            bincode::serialize(&message)
+        };
+
+        let mut pending: Vec<u32> = (0..total).collect();
+        let mut buffer = vec![0u8; SYNC_CHUNK_SIZE * 2];
+
+        for _ in 0..SYNC_MAX_ROUNDS {
+
+            for index in &pending {
+                socket.send_to(&send_chunk(*index)?, peer).await?;
+            }
+
+            match timeout(SYNC_SENDER_TIMEOUT, socket.recv_from(&mut buffer)).await {
+                Ok(received) => {
+                    let (n_bytes, _) = received?;
+                    match bincode::deserialize(&buffer[..n_bytes]) {
+                        Ok(SyncMessage::Complete) => return Ok(()),
+                        Ok(SyncMessage::Retransmit(missing)) => {
+                            pending = missing.into_iter()
+                                .filter(|index| *index < total)
+                                .collect()
+                        },
+                        _ => pending.clear()
+                    }
+                },
+                // Nothing heard back, so assume nothing arrived.
+                Err(_) => pending = (0..total).collect()
+            }
+        }
+
+        Err(CommunicationError::Timeout)
+    }
+
+    /// Wait for a peer's `sync_spectrum` and replace this spectrum with it.
+    /// Waits indefinitely for the first chunk, then requests retransmission
+    /// of missing chunks until complete, returning the number of receptors.
+    /// Fails with `TransferTooLarge` if the first chunk announces more than 
+    /// `SYNC_MAX_SPECTRUM_LEN` bytes.
+    /// NOTE: If this sensor is connected, only its peer can reach it.
+    pub async fn receive_spectrum(&mut self) -> Result<usize, CommunicationError> 
+    where Q: DeserializeOwned {
+
+        let mut buffer = vec![0u8; SYNC_CHUNK_SIZE * 2];
+        let mut chunks: Vec<Option<Vec<u8>>> = Vec::new();
+        let mut sender: Option<SocketAddr> = None;
+        let mut rounds = 0;
+
+        loop {
+            let received = if sender.is_none() {
+                Ok(self.socket.recv_from(&mut buffer).await)
+            } else {
+                timeout(SYNC_ROUND_TIMEOUT, self.socket.recv_from(&mut buffer)).await
+            };
+
+            let Ok(received) = received else {
+                let missing = missing_chunks(&chunks);
+                rounds += 1;
+                if rounds > SYNC_MAX_ROUNDS {
+                    return Err(CommunicationError::TransferIncomplete(missing.len()))
+                }
+                let message = bincode::serialize(&SyncMessage::Retransmit(missing))?;
+                // Sender is always known once a chunk has been received.
+                if let Some(sender) = sender {
+                    self.socket.send_to(&message, sender).await?;
+                }
+                continue
+            };
+
+            let (n_bytes, from) = received?;
+            let Ok(SyncMessage::Chunk { index, total, data }) 
+                = bincode::deserialize(&buffer[..n_bytes]) else { continue };
+
+            if sender.is_none() {
+                if total as usize > SYNC_MAX_CHUNKS {
+                    return Err(CommunicationError::TransferTooLarge(total as usize))
+                }
+                sender = Some(from);
+                chunks = vec![None; total as usize];
+            }
+            if sender != Some(from) || total as usize != chunks.len() { continue }
+            if let Some(slot) = chunks.get_mut(index as usize) {
+                *slot = Some(data);
+            }
+
+            if chunks.iter().all(Option::is_some) { break }
+        }
+
+        let sender = sender.expect("Sender is known after receiving chunks");
+        let complete = bincode::serialize(&SyncMessage::Complete)?;
+        self.socket.send_to(&complete, sender).await?;
+
+        // Linger long enough for the sender to retransmit in case `Complete` 
+        // was lost. The deadline is fixed, so other traffic cannot extend it.
+        let linger = Instant::now() + SYNC_SENDER_TIMEOUT + SYNC_ROUND_TIMEOUT;
+        while let Ok(received) 
+            = timeout_at(linger, self.socket.recv_from(&mut buffer)).await {
+            let (_, from) = received?;
+            if from == sender {
+                self.socket.send_to(&complete, sender).await?;
+            }
+        }
+
+        let bytes: Vec<u8> = chunks.into_iter().flatten().flatten().collect();
+        let entries: Vec<(Q, u16)> = bincode::deserialize(&bytes)?;
+        self.spectrum = entries.into_iter().collect();
+        self.reverse.take();
+        Ok(self.spectrum.len())
+    }
+}
+
+fn missing_chunks(chunks: &[Option<Vec<u8>>]) -> Vec<u32> {
+
+    chunks.iter()
+        .enumerate()
+        .filter(|(_, chunk)| chunk.is_none())
+        .map(|(index, _)| index as u32)
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use tokio::net::UdpSocket;
+
+    use crate::error::CommunicationError;
+    use crate::types::Sensor;
+    use super::{ SyncMessage, SYNC_MAX_CHUNKS };
+
+    #[tokio::test]
+    async fn transfers_a_large_spectrum() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let mut receiver: Sensor<u32> = Sensor::new("sync", any).await.unwrap();
+        let peer = receiver.address;
+        let receiving = tokio::spawn(async move {
+            let len = receiver.receive_spectrum().await;
+            (len, receiver)
+        });
+
+        let sender = Sensor::new("sync", any).await.unwrap()
+            .with_spectrum((0..5000u32).map(|quantum| (quantum, quantum as u16)));
+        sender.sync_spectrum(peer).await.unwrap();
+
+        let (len, receiver) = receiving.await.unwrap();
+        assert_eq!(len.unwrap(), 5000);
+        assert_eq!(receiver.spectrum, sender.spectrum);
+    }
+
+    #[tokio::test]
+    async fn rejects_an_oversized_transfer() {
+
+        let any = "127.0.0.1:0".parse().unwrap();
+        let mut receiver: Sensor<u32> = Sensor::new("sync", any).await.unwrap();
+        let peer = receiver.address;
+
+        let total = SYNC_MAX_CHUNKS as u32 + 1;
+        let chunk = SyncMessage::Chunk { index: 0, total, data: Vec::new() };
+        let socket = UdpSocket::bind(any).await.unwrap();
+        socket.send_to(&bincode::serialize(&chunk).unwrap(), peer).await.unwrap();
+
+        match receiver.receive_spectrum().await {
+            Err(CommunicationError::TransferTooLarge(chunks)) => assert_eq!(chunks, total as usize),
+            other => panic!("unexpected {other:?}")
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use tokio::net::UdpSocket;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{ Arc, RwLock, RwLockReadGuard, RwLockWriteGuard };
+
+use crate::error::{ CommunicationError, ConfigError };
+use crate::metrics::TractMetrics;
+use crate::transport::Transport;
+use super::{ Sensor, id::{ TractId, FiberId } };
+
+
+type SharedSpectrum<Q> = Arc<RwLock<HashMap<Q, u16>>>;
+
+impl<Q: Hash + Eq, T: Transport> Sensor<Q, T> {
+
+    /// Separate the spectrum (mapping) from the socket (transmission),
+    /// so that one task can edit receptors while another sends impulses.
+    /// Both halves share the spectrum behind a lock.
+    /// NOTE: Weighted receptors stay with the sender half.
+    pub fn split(mut self) -> (SpectrumHandle<Q>, SenderHalf<Q, T>) {
+
+        self.reverse.take();
+        let spectrum = Arc::new(RwLock::new(std::mem::take(&mut self.spectrum)));
+
+        let handle = SpectrumHandle { spectrum: spectrum.clone() };
+        let sender = SenderHalf { sensor: self, spectrum };
+        (handle, sender)
+    }
+}
+
+
+/// The configuration half of a split `Sensor`.
+/// Cloning the handle shares the same spectrum.
+pub struct SpectrumHandle<Q: Hash + Eq> {
+    spectrum: SharedSpectrum<Q>
+}
+
+impl<Q: Hash + Eq> Clone for SpectrumHandle<Q> {
+    fn clone(&self) -> Self {
+        SpectrumHandle { spectrum: self.spectrum.clone() }
+    }
+}
+
+impl<Q: Hash + Eq> SpectrumHandle<Q> {
+
+    // A panicked writer cannot leave a HashMap half-inserted,
+    // so a poisoned lock is still safe to use.
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<Q, u16>> {
+        self.spectrum.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<Q, u16>> {
+        self.spectrum.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Maps a sensory bit to a new NeuronId.
+    /// NOTE: Overwrites existing quantum key without checking.
+    pub fn add_receptor(&self, quantum: Q, fid: impl Into<FiberId>) {
+
+        self.write().insert(quantum, fid.into().raw());
+    }
+
+    /// Maps a sensory bit to a new NeuronId, failing with `AlreadyMapped` 
+    /// if the quantum already has a receptor.
+    pub fn try_add_receptor(&self, quantum: Q, fid: impl Into<FiberId>) -> Result<(), ConfigError> {
+
+        let mut spectrum = self.write();
+        if let Some(existing) = spectrum.get(&quantum) {
+            return Err(ConfigError::AlreadyMapped(*existing))
+        }
+        spectrum.insert(quantum, fid.into().raw());
+        Ok(())
+    }
+
+    /// Points an existing receptor at a different fiber ID,
+    /// returning the previous one, or `None` if the quantum has no receptor.
+    pub fn remap_receptor(&self, quantum: &Q, fid: u16) -> Option<u16> {
+
+        self.write().get_mut(quantum).map(|existing| std::mem::replace(existing, fid))
+    }
+
+    /// Removes a receptor, returning the fiber ID it was mapped to.
+    pub fn remove_receptor(&self, quantum: &Q) -> Option<u16> {
+
+        self.write().remove(quantum)
+    }
+
+    /// The fiber ID currently mapped to `quantum`.
+    pub fn fiber_id(&self, quantum: &Q) -> Option<u16> {
+
+        self.read().get(quantum).copied()
+    }
+
+    /// Number of receptors in the spectrum.
+    pub fn len(&self) -> usize { self.read().len() }
+
+    /// Whether the spectrum has no receptors.
+    pub fn is_empty(&self) -> bool { self.read().is_empty() }
+}
+
+
+/// The transmission half of a split `Sensor`.
+/// Keeps the rest of the sensor, so impulses are sent exactly as before 
+/// the split: through the same codec and targets, and subject to the same
+/// quiescing, sequencing, debouncing, refractory periods and edge triggering.
+pub struct SenderHalf<Q: Hash + Eq, T: Transport = UdpSocket> {
+    sensor: Sensor<Q, T>,
+    spectrum: SharedSpectrum<Q>
+}
+
+impl<Q: Hash + Eq, T: Transport> SenderHalf<Q, T> {
+
+    /// Name of the tract this half was split from.
+    pub fn tract_name(&self) -> &str { &self.sensor.tract_name }
+
+    /// Unique ID of the tract this half was split from.
+    pub fn tract_id(&self) -> TractId { self.sensor.id }
+
+    /// The rest of the sensor, e.g. to `quiesce` it.
+    /// Its own `spectrum` field is empty; receptors live in the `SpectrumHandle`.
+    pub fn sensor(&self) -> &Sensor<Q, T> { &self.sensor }
+
+    /// Current counters, continuing from the original sensor.
+    pub fn metrics(&self) -> TractMetrics { self.sensor.metrics() }
+
+    /// Attempts to send a sensory datum as a neurotransmission impulse.
+    /// The spectrum is only read-locked for the lookup, not the send.
+    pub async fn send_impulse(
+        &self, 
+        quantum: &Q
+    ) -> Result<(), CommunicationError> {
+
+        let fid = self.spectrum.read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(quantum)
+            .copied();
+
+        match fid.or_else(|| self.sensor.sample_weighted(quantum)) {
+            Some(fid) => self.sensor.transmit(fid).await,
+            None => {
+                self.sensor.metrics.record_unrecognized();
+                let name = self.sensor.tract_name.clone();
+                Err(CommunicationError::UnrecognizedTrigger(name)) 
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::transport::{ Transport, ChannelTransport };
+    use crate::types::{ Sensor, Motor };
+    use crate::wire::ImpulseFrame;
+
+    #[tokio::test]
+    async fn sender_half_keeps_the_send_configuration() {
+
+        let (a, b) = ChannelTransport::pair(8);
+        let mut sensor: Sensor<char, _> = Sensor::in_process("split", a);
+        sensor.set_tagged(true);
+        sensor.set_sequenced(true);
+        sensor.set_edge_triggered(true);
+        let motor: Motor<fn(()), (), (), _> = Motor::in_process("split", b);
+
+        let (spectrum, sender) = sensor.split();
+        spectrum.add_receptor('a', 1);
+        spectrum.add_receptor('b', 2);
+
+        sender.send_impulse(&'a').await.unwrap();
+        sender.send_impulse(&'a').await.unwrap();
+        sender.send_impulse(&'b').await.unwrap();
+
+        let mut buffer = [0u8; 64];
+        for (seq, fiber) in [(0, 1), (1, 2)] {
+            let (n_bytes, _) = motor.socket.recv_from(&mut buffer).await.unwrap();
+            assert_eq!(&buffer[..2], &crate::wire::WIRE_MAGIC);
+            let frame = ImpulseFrame::decode(&buffer[..n_bytes]).unwrap();
+            assert_eq!(frame, ImpulseFrame::Data { seq: Some(seq), fiber, payload: None });
+        }
+
+        sender.sensor().quiesce();
+        assert!(sender.send_impulse(&'a').await.is_err());
+    }
+}
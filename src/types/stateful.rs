@@ -0,0 +1,65 @@
+
+use std::sync::Mutex;
+
+use crate::transport::Transport;
+use super::Motor;
+
+
+/// Wrap a behavior that mutates its own state (e.g. integrates spikes
+/// into a position) so it can be stored as a `Fn` behavior.
+/// The state stays inside the behavior, so no `Arc<Mutex<..>>` is needed 
+/// in user code.
+/// NOTE: Calls are serialized by a lock, so a stateful behavior 
+/// never runs concurrently with itself.
+/// If it panics, the lock is recovered and its state is kept as-is.
+pub fn stateful<A, R>(
+    behavior: impl FnMut(A) -> R + Send
+) -> impl Fn(A) -> R + Send + Sync {
+
+    let behavior = Mutex::new(behavior);
+    move |args| (behavior.lock().unwrap_or_else(|e| e.into_inner()))(args)
+}
+
+impl<A, R, T: Transport> Motor<Box<dyn Fn(A) -> R + Send + Sync>, A, R, T> {
+
+    /// Maps a fiber ID to a behavior that keeps state between impulses.
+    /// NOTE: Overwrites existing fiber ID keys without checking.
+    pub fn add_stateful_fiber(
+        &mut self, 
+        impulse: u16, 
+        behavior: impl FnMut(A) -> R + Send + 'static
+    ) 
+    where
+        A: 'static,
+        R: 'static
+    {
+
+        self.add_fiber(impulse, Box::new(stateful(behavior)));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::transport::ChannelTransport;
+    use crate::types::Motor;
+    use super::stateful;
+
+    #[tokio::test]
+    async fn stateful_fibers_keep_their_state() {
+
+        let (_, b) = ChannelTransport::pair(1);
+        let mut motor: Motor<Box<dyn Fn(i32) -> i32 + Send + Sync>, i32, i32, _> = Motor::in_process("stateful", b);
+        let mut position = 0;
+        motor.add_stateful_fiber(1, move |step| { position += step; position });
+
+        assert_eq!(motor.dispatch(1, 2).unwrap(), 2);
+        assert_eq!(motor.dispatch(1, 3).unwrap(), 5);
+
+        let mut count = 0;
+        let counter = stateful(move |_: ()| { count += 1; count });
+        counter(());
+        assert_eq!(counter(()), 2);
+    }
+}
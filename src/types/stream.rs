@@ -0,0 +1,108 @@
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ Context, Poll };
+use futures_core::Stream;
+
+use crate::error::CommunicationError;
+use crate::transport::Transport;
+use super::Motor;
+
+
+type RecvFuture = Pin<Box<dyn Future<Output = Result<u16, CommunicationError>> + Send>>;
+
+/// A `Motor` as a `Stream` of dispatched impulses, from `Motor::into_stream`.
+/// Each item is the fiber ID and its behavior's result, or the error 
+/// that receiving or dispatching raised. The stream never ends by itself.
+pub struct MotorStream<B: Fn(A) -> R, A, R, F, T: Transport> {
+    motor: Arc<Motor<B, A, R, T>>,
+    args: F,
+    receiving: Option<RecvFuture>
+}
+
+impl<B, A, R, T> Motor<B, A, R, T>
+where
+    B: Fn(A) -> R + Send + Sync + 'static,
+    A: 'static,
+    R: 'static,
+    T: Transport + Send + Sync + 'static
+{
+
+    /// Turn the motor into a `Stream`, building the argument for each 
+    /// impulse with `args`. Behaviors run when the stream is polled.
+    pub fn into_stream<F: FnMut() -> A>(self, args: F) -> MotorStream<B, A, R, F, T> {
+
+        MotorStream { motor: Arc::new(self), args, receiving: None }
+    }
+}
+
+impl<B, A, R, F, T> MotorStream<B, A, R, F, T>
+where
+    B: Fn(A) -> R,
+    T: Transport
+{
+
+    /// The motor being streamed from.
+    pub fn motor(&self) -> &Motor<B, A, R, T> { &self.motor }
+}
+
+impl<B, A, R, F, T> Stream for MotorStream<B, A, R, F, T>
+where
+    B: Fn(A) -> R + Send + Sync + 'static,
+    A: 'static,
+    R: 'static,
+    F: FnMut() -> A + Unpin,
+    T: Transport + Send + Sync + 'static
+{
+    type Item = Result<(u16, R), CommunicationError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+
+        let this = &mut *self;
+        let receiving = this.receiving.get_or_insert_with(|| {
+            let motor = this.motor.clone();
+            Box::pin(async move { motor.recv_fiber().await })
+        });
+
+        let received = std::task::ready!(receiving.as_mut().poll(cx));
+        this.receiving = None;
+
+        let item = received.and_then(|fiber| {
+            this.motor.dispatch(fiber, (this.args)()).map(|result| (fiber, result))
+        });
+        Poll::Ready(Some(item))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::pin::Pin;
+    use futures_core::Stream;
+
+    use crate::error::CommunicationError;
+    use crate::transport::ChannelTransport;
+    use crate::types::{ Sensor, Motor };
+
+    async fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+
+        std::future::poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+    }
+
+    #[tokio::test]
+    async fn stream_yields_fibers_with_their_results() {
+
+        let (a, b) = ChannelTransport::pair(4);
+        let sensor: Sensor<char, _> = Sensor::in_process("stream", a).with_spectrum([('a', 1), ('z', 9)]);
+        let motor: Motor<fn(u8) -> u8, u8, u8, _> = Motor::in_process("stream", b)
+            .with_fiber(1, |x| x + 1);
+        let mut stream = motor.into_stream(|| 1);
+
+        sensor.send_impulse(&'a').await.unwrap();
+        sensor.send_impulse(&'z').await.unwrap();
+        assert!(matches!(next(&mut stream).await, Some(Ok((1, 2)))));
+        assert!(matches!(next(&mut stream).await, Some(Err(CommunicationError::UnrecognizedImpulse(9)))));
+    }
+}
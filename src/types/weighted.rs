@@ -0,0 +1,134 @@
+
+use std::hash::Hash;
+use rand::{ Rng, SeedableRng, rngs::StdRng };
+
+use crate::error::ConfigError;
+use crate::transport::Transport;
+use super::Sensor;
+
+
+/// Alternative fibers for one quantum, with cumulative weights for sampling.
+#[derive(Debug, Clone)]
+pub(crate) struct WeightedFibers {
+    fibers: Vec<u16>,
+    cumulative: Vec<f64>
+}
+
+impl WeightedFibers {
+
+    fn new(choices: Vec<(u16, f64)>) -> Result<Self, ConfigError> {
+
+        let valid = |weight: f64| weight.is_finite() && weight >= 0.0;
+        if !choices.iter().all(|(_, weight)| valid(*weight)) {
+            return Err(ConfigError::InvalidWeights)
+        }
+
+        let mut total = 0.0;
+        let (fibers, cumulative): (Vec<u16>, Vec<f64>) = choices.into_iter()
+            .map(|(fid, weight)| { total += weight; (fid, total) })
+            .unzip();
+
+        if total > 0.0 { 
+            Ok(WeightedFibers { fibers, cumulative }) 
+        } else { 
+            Err(ConfigError::InvalidWeights) 
+        }
+    }
+
+    pub(crate) fn fibers(&self) -> &[u16] { &self.fibers }
+
+    /// Each fiber with its weight, as given to `add_receptor_weighted`.
+    pub(crate) fn choices(&self) -> Vec<(u16, f64)> {
+
+        let mut previous = 0.0;
+        self.fibers.iter().zip(&self.cumulative)
+            .map(|(fid, bound)| { let weight = bound - previous; previous = *bound; (*fid, weight) })
+            .collect()
+    }
+
+    fn sample(&self, rng: &mut impl Rng) -> u16 {
+
+        let total = self.cumulative.last().copied().unwrap_or(0.0);
+        let target = rng.gen::<f64>() * total;
+        let index = self.cumulative.partition_point(|bound| *bound <= target);
+        self.fibers[index.min(self.fibers.len() - 1)]
+    }
+}
+
+impl<Q: Hash + Eq, T: Transport> Sensor<Q, T> {
+
+    /// Maps a sensory bit to several fibers, one of which is chosen
+    /// each time the quantum is sent, with probability proportional 
+    /// to its weight. Replaces any plain receptor for the same quantum.
+    /// NOTE: Overwrites existing quantum key without checking.
+    pub fn add_receptor_weighted(
+        &mut self, 
+        quantum: Q, 
+        choices: Vec<(u16, f64)>
+    ) -> Result<(), ConfigError> {
+
+        let choices = WeightedFibers::new(choices)?;
+        self.spectrum.remove(&quantum);
+        self.reverse.take();
+        self.weighted.insert(quantum, choices);
+        Ok(())
+    }
+
+    /// Reseed the generator used for weighted receptors.
+    /// With the same seed and the same sequence of weighted sends,
+    /// the same fibers are chosen (for a given version of `rand`).
+    /// Unseeded sensors draw from OS entropy.
+    pub fn seed_rng(&mut self, seed: u64) {
+
+        self.rng = std::sync::Mutex::new(StdRng::seed_from_u64(seed));
+    }
+
+    pub(crate) fn sample_weighted(&self, quantum: &Q) -> Option<u16> {
+
+        let choices = self.weighted.get(quantum)?;
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        Some(choices.sample(&mut *rng))
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::error::ConfigError;
+    use crate::transport::ChannelTransport;
+    use super::Sensor;
+
+    fn sensor() -> Sensor<char, ChannelTransport> {
+
+        let (a, _b) = ChannelTransport::pair(1);
+        Sensor::in_process("weighted", a)
+    }
+
+    #[test]
+    fn samples_in_proportion_to_weight() {
+
+        let mut sensor = sensor();
+        sensor.seed_rng(7);
+        sensor.add_receptor_weighted('a', vec![(1, 1.0), (2, 3.0), (3, 0.0)]).unwrap();
+
+        let mut counts = [0usize; 4];
+        for _ in 0..4000 { counts[sensor.sample_weighted(&'a').unwrap() as usize] += 1 }
+        assert_eq!(counts[3], 0);
+        assert!((800..1200).contains(&counts[1]), "{counts:?}");
+        assert_eq!(counts[1] + counts[2], 4000);
+    }
+
+    #[test]
+    fn rejects_invalid_weights() {
+
+        let mut sensor = sensor();
+        for choices in [vec![(1, 0.0)], vec![(1, -1.0)], vec![(1, f64::NAN)], vec![]] {
+            assert!(matches!(
+                sensor.add_receptor_weighted('a', choices), 
+                Err(ConfigError::InvalidWeights)
+            ));
+        }
+    }
+}
@@ -0,0 +1,302 @@
+
+//! Encoding of impulses on the wire.
+//! Every `Sensor`, `Motor` and helper goes through these functions,
+//! so this is the single place to change if cajal's framing changes.
+//! A plain impulse is the bare bincode-serialized `FiberWidth` (`u16`) fiber ID.
+//! NOTE: For cajal releases that wrap the `NeuronId` in an envelope,
+//! use `codec::EnvelopeCodec` on both ends of the tract instead.
+//! Every other frame starts with a header of the `WIRE_MAGIC` bytes,
+//! the `WIRE_VERSION` byte and a one-byte tag, so no frame is two bytes long
+//! and plain impulses are never mistaken for one. Datagrams of any other 
+//! length without the magic bytes are rejected with `ProtocolMismatch`.
+//!
+//! NOTE: Plain impulses stay headerless, because a cajal Input expects 
+//! the bare fiber ID, so by default a stray two-byte datagram is still read
+//! as one. Between phantom limbs, send every impulse with the header
+//! (`Sensor::set_tagged`) and reject the rest (`Motor::set_reject_plain`).
+//! The version is only negotiated by the opt-in handshake 
+//! (`Sensor::connect_with_handshake`), never by a plain `connect`,
+//! since a cajal Input cannot answer one.
+
+use serde::{ Serialize, Deserialize };
+
+use crate::error::CommunicationError;
+use crate::types::FiberWidth;
+
+
+/// Version of the tagged frame format. Motors reject frames from other versions.
+/// Plain impulses carry no version, to stay compatible with cajal.
+pub const WIRE_VERSION: u8 = 2;
+
+/// Marks a datagram as a tagged frame, ahead of the version byte.
+pub const WIRE_MAGIC: [u8; 2] = *b"PL";
+
+pub(crate) const IMPULSE_LEN: usize = std::mem::size_of::<FiberWidth>();
+pub(crate) const HEADER_LEN: usize = 4;
+
+pub(crate) const DATA_TAG: u8 = 0x01;
+pub(crate) const SILENCE_TAG: u8 = 0x02;
+pub(crate) const HEARTBEAT_TAG: u8 = 0x03;
+pub(crate) const ACK_TAG: u8 = 0x04;
+pub(crate) const UNRECOGNIZED_TAG: u8 = 0x05;
+pub(crate) const BATCH_TAG: u8 = 0x06;
+pub(crate) const CLOSING_TAG: u8 = 0x07;
+pub(crate) const HANDSHAKE_TAG: u8 = 0xFE;
+pub(crate) const REPORT_TAG: u8 = 0xFF;
+
+/// Every kind of datagram exchanged between tracts.
+/// Motors dispatch behaviors only for `Data` frames.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ImpulseFrame {
+
+    /// An impulse through `fiber`. Without a sequence number or payload,
+    /// this is encoded as a plain (cajal-compatible) impulse.
+    Data { seq: Option<u64>, fiber: u16, payload: Option<f32> },
+
+    /// The sensor is alive but has nothing to report.
+    Silence,
+
+    /// Keep-alive with no meaning beyond liveness.
+    Heartbeat,
+
+    /// Acknowledges the impulse with this sequence number.
+    Ack(u64),
+
+    /// Several impulses packed into one datagram, in firing order.
+    Batch(Vec<u16>),
+
+    /// Diagnostic echoed by a motor that has no behavior for this fiber ID.
+    /// Never echoed in turn, so echoes cannot loop.
+    Unrecognized(u16),
+
+    /// The number of sequenced impulses a sensor has sent, for loss estimation.
+    Report { sent: u64 },
+
+    /// The fiber IDs offered during a connect-time handshake.
+    Handshake(Vec<u16>),
+
+    /// The sensor is closing and will send no more impulses.
+    Closing
+}
+
+impl ImpulseFrame {
+
+    /// Serialize the frame into a datagram.
+    pub fn encode(&self) -> Result<Vec<u8>, CommunicationError> {
+
+        match self {
+            ImpulseFrame::Data { seq: None, fiber, payload: None } => encode_impulse(*fiber),
+            _ => self.encode_tagged()
+        }
+    }
+
+    /// Serialize the frame into a datagram, always with the tagged header,
+    /// so even a plain impulse can be told apart from stray traffic.
+    pub fn encode_tagged(&self) -> Result<Vec<u8>, CommunicationError> {
+
+        use ImpulseFrame::*;
+        let (tag, body) = match self {
+            Data { seq, fiber, payload } => (DATA_TAG, bincode::serialize(&(fiber, seq, payload))?),
+            Silence => (SILENCE_TAG, Vec::new()),
+            Heartbeat => (HEARTBEAT_TAG, Vec::new()),
+            Ack(seq) => (ACK_TAG, bincode::serialize(seq)?),
+            Batch(fibers) => (BATCH_TAG, bincode::serialize(fibers)?),
+            Unrecognized(fiber) => (UNRECOGNIZED_TAG, bincode::serialize(fiber)?),
+            Report { sent } => (REPORT_TAG, bincode::serialize(sent)?),
+            Handshake(fibers) => (HANDSHAKE_TAG, bincode::serialize(fibers)?),
+            Closing => (CLOSING_TAG, Vec::new())
+        };
+
+        let mut datagram = Vec::with_capacity(HEADER_LEN + body.len());
+        datagram.extend_from_slice(&WIRE_MAGIC);
+        datagram.extend_from_slice(&[WIRE_VERSION, tag]);
+        datagram.extend_from_slice(&body);
+        Ok(datagram)
+    }
+
+    /// Deserialize a datagram into a frame.
+    /// Tagged frames from another wire version fail with `UnsupportedVersion`,
+    /// and anything else that is not a plain impulse with `ProtocolMismatch`.
+    pub fn decode(datagram: &[u8]) -> Result<Self, CommunicationError> {
+
+        use ImpulseFrame::*;
+        if datagram.len() == IMPULSE_LEN {
+            return Ok(Data { seq: None, fiber: decode_impulse(datagram)?, payload: None })
+        }
+
+        let (version, tag) = peek_header(datagram).ok_or(CommunicationError::ProtocolMismatch)?;
+        if version != WIRE_VERSION {
+            return Err(CommunicationError::UnsupportedVersion(version))
+        }
+
+        let body = &datagram[HEADER_LEN..];
+        let frame = match tag {
+            DATA_TAG => {
+                let (fiber, seq, payload) = bincode::deserialize(body)?;
+                Data { seq, fiber, payload }
+            },
+            SILENCE_TAG => Silence,
+            HEARTBEAT_TAG => Heartbeat,
+            ACK_TAG => Ack(bincode::deserialize(body)?),
+            BATCH_TAG => Batch(bincode::deserialize(body)?),
+            UNRECOGNIZED_TAG => Unrecognized(bincode::deserialize(body)?),
+            REPORT_TAG => Report { sent: bincode::deserialize(body)? },
+            HANDSHAKE_TAG => Handshake(bincode::deserialize(body)?),
+            CLOSING_TAG => Closing,
+            unknown => return Err(CommunicationError::UnknownFrame(unknown))
+        };
+        Ok(frame)
+    }
+
+    /// The fiber ID, if this frame carries a single impulse.
+    pub fn fiber(&self) -> Option<u16> {
+
+        match self {
+            ImpulseFrame::Data { fiber, .. } => Some(*fiber),
+            _ => None
+        }
+    }
+
+    /// Every fiber ID this frame carries, in order.
+    pub fn fibers(&self) -> Vec<u16> {
+
+        match self {
+            ImpulseFrame::Data { fiber, .. } => vec![*fiber],
+            ImpulseFrame::Batch(fibers) => fibers.clone(),
+            _ => Vec::new()
+        }
+    }
+}
+
+
+/// The version and tag of a tagged frame, if the datagram has the magic bytes.
+/// Readable for any version, e.g. to answer a handshake from a peer 
+/// speaking another one.
+pub(crate) fn peek_header(datagram: &[u8]) -> Option<(u8, u8)> {
+
+    match datagram {
+        [m0, m1, version, tag, ..] if [*m0, *m1] == WIRE_MAGIC => Some((*version, *tag)),
+        _ => None
+    }
+}
+
+/// Whether a datagram is a plain (untagged) impulse.
+pub(crate) fn is_plain(datagram: &[u8]) -> bool { datagram.len() == IMPULSE_LEN }
+
+/// A typical Ethernet MTU, used until a tract is told otherwise.
+pub const DEFAULT_MTU: usize = 1500;
+
+/// IPv6 (40 bytes) plus UDP (8 bytes) headers; IPv4 needs 20 fewer,
+/// so this is the conservative figure for either family.
+const IP_UDP_OVERHEAD: usize = 48;
+
+/// Frame header plus the bincode length prefix of a batch.
+const BATCH_OVERHEAD: usize = HEADER_LEN + 8;
+
+/// How many impulses fit in one datagram without IP fragmentation, 
+/// when each carries `payload_size` bytes in addition to its `u16` fiber ID.
+/// Accounts for the IP/UDP headers and the batch frame overhead.
+pub fn max_impulses_per_datagram(payload_size: usize, mtu: usize) -> usize {
+
+    let available = mtu.saturating_sub(IP_UDP_OVERHEAD + BATCH_OVERHEAD);
+    available / (std::mem::size_of::<u16>() + payload_size)
+}
+
+
+/// Serialize a fiber ID into a plain impulse datagram.
+pub(crate) fn encode_impulse(fiber_id: FiberWidth) -> Result<Vec<u8>, CommunicationError> {
+
+    Ok(bincode::serialize(&fiber_id)?)
+}
+
+/// The plain impulse for a fiber ID, without allocating.
+/// Identical to `encode_impulse`: bincode writes an integer as its little-endian bytes.
+pub(crate) fn plain_impulse(fiber_id: FiberWidth) -> [u8; IMPULSE_LEN] { fiber_id.to_le_bytes() }
+
+/// Deserialize a plain impulse datagram into its fiber ID.
+pub(crate) fn decode_impulse(datagram: &[u8]) -> Result<FiberWidth, CommunicationError> {
+
+    Ok(bincode::deserialize_from(datagram)?)
+}
+
+/// Deserialize the fiber ID from a datagram, if it carries a single impulse.
+pub(crate) fn decode_fiber(datagram: &[u8]) -> Result<Option<u16>, CommunicationError> {
+
+    Ok(ImpulseFrame::decode(datagram)?.fiber())
+}
+
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn plain_impulse_is_bare_bincode_u16() {
+
+        let encoded = encode_impulse(0x0102).unwrap();
+        assert_eq!(encoded, bincode::serialize(&0x0102u16).unwrap());
+        assert_eq!(encoded, plain_impulse(0x0102));
+        assert_eq!(decode_impulse(&encoded).unwrap(), 0x0102);
+    }
+
+    #[test]
+    fn every_frame_round_trips() {
+
+        let frames = [
+            ImpulseFrame::Data { seq: None, fiber: 7, payload: None },
+            ImpulseFrame::Data { seq: Some(3), fiber: 7, payload: Some(0.5) },
+            ImpulseFrame::Silence,
+            ImpulseFrame::Heartbeat,
+            ImpulseFrame::Ack(9),
+            ImpulseFrame::Batch(vec![1, 2, 3]),
+            ImpulseFrame::Unrecognized(4),
+            ImpulseFrame::Report { sent: 12 },
+            ImpulseFrame::Handshake(vec![0, 1]),
+            ImpulseFrame::Closing
+        ];
+        for frame in frames {
+            assert_eq!(ImpulseFrame::decode(&frame.encode().unwrap()).unwrap(), frame);
+            assert_eq!(ImpulseFrame::decode(&frame.encode_tagged().unwrap()).unwrap(), frame);
+        }
+    }
+
+    #[test]
+    fn only_data_and_batches_carry_fibers() {
+
+        assert_eq!(ImpulseFrame::Batch(vec![1, 2]).fibers(), vec![1, 2]);
+        assert_eq!(ImpulseFrame::Batch(vec![1, 2]).fiber(), None);
+        assert_eq!(ImpulseFrame::Data { seq: None, fiber: 5, payload: None }.fiber(), Some(5));
+        assert!(ImpulseFrame::Heartbeat.fibers().is_empty());
+    }
+
+    #[test]
+    fn frames_from_another_version_are_rejected() {
+
+        let mut datagram = ImpulseFrame::Heartbeat.encode().unwrap();
+        assert_eq!(datagram[2], WIRE_VERSION);
+        datagram[2] = WIRE_VERSION + 1;
+        assert!(matches!(
+            ImpulseFrame::decode(&datagram), 
+            Err(CommunicationError::UnsupportedVersion(v)) if v == WIRE_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn full_batch_fits_in_one_packet() {
+
+        for mtu in [576, DEFAULT_MTU, 9000] {
+            let count = max_impulses_per_datagram(0, mtu);
+            let batch = ImpulseFrame::Batch(vec![u16::MAX; count]).encode().unwrap();
+            assert!(batch.len() + IP_UDP_OVERHEAD <= mtu, "{mtu}");
+            assert!(batch.len() + 2 + IP_UDP_OVERHEAD > mtu, "{mtu}");
+        }
+        // (MTU - 48 IPv6/UDP - 4 header - 8 length prefix) / bytes per impulse
+        assert_eq!(max_impulses_per_datagram(0, 576), 258);
+        assert_eq!(max_impulses_per_datagram(0, 1500), 720);
+        assert_eq!(max_impulses_per_datagram(0, 9000), 4470);
+        assert_eq!(max_impulses_per_datagram(2, 1500), 360);
+        assert_eq!(max_impulses_per_datagram(0, 20), 0);
+    }
+}
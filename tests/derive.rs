@@ -0,0 +1,52 @@
+//! The derive macros expand to `::phantom_limb` paths, 
+//! so they are exercised from outside the crate.
+#![cfg(feature = "derive")]
+
+use phantom_limb::{ Spectrum, MotorFibers };
+use phantom_limb::spectrum::Spectrum as _;
+use phantom_limb::registry::MotorFibers as _;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Spectrum)]
+enum Touch {
+    Light,
+    Firm,
+    Painful
+}
+
+#[test]
+fn spectrum_variants_number_in_declaration_order() {
+
+    assert_eq!(Touch::FIBER_COUNT, 3);
+    assert_eq!(Touch::Painful.fiber_id(), 2);
+    assert_eq!(Touch::variants(), vec![Touch::Light, Touch::Firm, Touch::Painful]);
+
+    let spectrum = Touch::spectrum();
+    assert_eq!(spectrum.len(), 3);
+    assert_eq!(spectrum[&Touch::Firm], 1);
+}
+
+
+fn forward(speed: u8) -> i16 { speed as i16 }
+fn reverse(speed: u8) -> i16 { -(speed as i16) }
+
+#[derive(Debug, PartialEq, MotorFibers)]
+#[motor(arg = u8, ret = i16)]
+enum Wheel {
+    #[behavior(forward)]
+    Forward,
+    #[behavior(reverse)]
+    Reverse
+}
+
+#[test]
+fn motor_fibers_wire_each_variant_to_its_behavior() {
+
+    assert_eq!(Wheel::FIBER_COUNT, 2);
+    assert_eq!(Wheel::from_fiber(1), Some(Wheel::Reverse));
+    assert_eq!(Wheel::from_fiber(2), None);
+    assert_eq!((Wheel::Forward.behavior())(3), 3);
+
+    let fibers = Wheel::fibers();
+    assert_eq!(fibers[&1](3), -3);
+}